@@ -11,14 +11,22 @@ use std::{
 
 
 
-/// # Pre-Compute Extensions.
+/// # Pre-Compute Extensions and Formats.
 pub fn main() {
+	// Note which of the optional codecs are enabled.
+	let avif = std::env::var_os("CARGO_FEATURE_AVIF").is_some();
+	let heif = std::env::var_os("CARGO_FEATURE_HEIF").is_some();
+	let jpeg2k = std::env::var_os("CARGO_FEATURE_JPEG2K").is_some();
+	let jxl = std::env::var_os("CARGO_FEATURE_JXL").is_some();
+	let raw = std::env::var_os("CARGO_FEATURE_RAW").is_some();
+
 	// Collect the supported formats.
 	let formats: Vec<ImageFormat> = ImageFormat::all()
 		.filter(|f| f.can_read() && f.reading_enabled())
 		.collect();
 
 	// Build up a list of matching file extensions by length.
+	let mut ext2: BTreeSet<u16> = BTreeSet::new();
 	let mut ext3: BTreeSet<u32> = BTreeSet::new();
 	let mut ext4: BTreeSet<u32> = BTreeSet::new();
 	for f in formats {
@@ -26,6 +34,12 @@ pub fn main() {
 			assert!(ext.is_ascii(), "Bug: extension is non-ascii: {ext}");
 
 			match ext.as_bytes() {
+				[a, b] => {
+					ext2.insert(u16::from_le_bytes([
+						a.to_ascii_lowercase(),
+						b.to_ascii_lowercase(),
+					]));
+				},
 				[a, b, c] => {
 					ext3.insert(u32::from_le_bytes([
 						b'.',
@@ -47,20 +61,45 @@ pub fn main() {
 		}
 	}
 
+	// TGA aliases, which only some image releases list. These need to be
+	// recognized regardless, as `PxKind::try_from_extension` maps them.
+	for ext in [b".icb", b".vda", b".vst"] { ext3.insert(u32::from_le_bytes(*ext)); }
+
 	// Formats not in the image crate.
-	ext3.insert(u32::from_le_bytes(*b".j2c"));
-	ext3.insert(u32::from_le_bytes(*b".j2k"));
-	ext3.insert(u32::from_le_bytes(*b".jp2"));
-	ext3.insert(u32::from_le_bytes(*b".jpc"));
-	ext3.insert(u32::from_le_bytes(*b".jxl"));
-	ext4.insert(u32::from_le_bytes(*b"avif"));
-	ext4.insert(u32::from_le_bytes(*b"jpg2"));
+	if avif { ext4.insert(u32::from_le_bytes(*b"avif")); }
+	if heif {
+		ext3.insert(u32::from_le_bytes(*b".hif"));
+		ext4.insert(u32::from_le_bytes(*b"heic"));
+		ext4.insert(u32::from_le_bytes(*b"heif"));
+	}
+	if jpeg2k {
+		ext3.insert(u32::from_le_bytes(*b".j2c"));
+		ext3.insert(u32::from_le_bytes(*b".j2k"));
+		ext3.insert(u32::from_le_bytes(*b".jp2"));
+		ext3.insert(u32::from_le_bytes(*b".jpc"));
+		ext4.insert(u32::from_le_bytes(*b"jpg2"));
+	}
+	if jxl { ext3.insert(u32::from_le_bytes(*b".jxl")); }
+	if raw {
+		ext3.insert(u32::from_le_bytes(*b".arw"));
+		ext3.insert(u32::from_le_bytes(*b".cr2"));
+		ext3.insert(u32::from_le_bytes(*b".dng"));
+		ext3.insert(u32::from_le_bytes(*b".nef"));
+	}
+
+	// SVG is rasterized separately too.
+	ext3.insert(u32::from_le_bytes(*b".svg"));
+	ext4.insert(u32::from_le_bytes(*b"svgz"));
 
 	// Build up a matching method we can use at runtime.
 	let out = format!(
 		r"
+#[must_use]
 /// # Match Image Extension.
-const fn check_extension(bytes: &[u8]) -> bool {{
+///
+/// Returns `true` if the path ends with a file extension associated with one
+/// of the supported image formats.
+pub const fn check_extension(bytes: &[u8]) -> bool {{
 	if let [.., 0..=46 | 48..=91 | 93..=255, b'.', a, b, c] = bytes {{
 		matches!(
 			u32::from_le_bytes([b'.', a.to_ascii_lowercase(), b.to_ascii_lowercase(), c.to_ascii_lowercase()]),
@@ -73,6 +112,12 @@ const fn check_extension(bytes: &[u8]) -> bool {{
 			{}
 		)
 	}}
+	else if let [.., 0..=46 | 48..=91 | 93..=255, b'.', a, b] = bytes {{
+		matches!(
+			u16::from_le_bytes([a.to_ascii_lowercase(), b.to_ascii_lowercase()]),
+			{}
+		)
+	}}
 	else {{ false }}
 }}",
 		ext3.into_iter()
@@ -83,11 +128,58 @@ const fn check_extension(bytes: &[u8]) -> bool {{
 			.map(|n| NiceU32::with_separator(n, b'_'))
 			.collect::<Vec<_>>()
 			.join(" | "),
+		ext2.into_iter()
+			.map(|n| n.to_string())
+			.collect::<Vec<_>>()
+			.join(" | "),
+	);
+
+	write("pxsum-ext.rs", &out);
+
+	// And a (wrapped) list of format names for the help screen.
+	let mut names = vec![
+		"BMP", "farbfeld", "GIF", "HDR", "ICO", "JPEG", "OpenEXR", "PNG", "PNM",
+		"QOI", "SVG", "TGA", "TIFF", "WebP",
+	];
+	if avif { names.push("AVIF"); }
+	if heif { names.push("HEIF"); }
+	if jpeg2k { names.push("JPEG 2000"); }
+	if jxl { names.push("JPEG XL"); }
+	if raw { names.extend_from_slice(&["ARW", "CR2", "DNG", "NEF"]); }
+	names.sort_unstable_by_key(|n| n.to_ascii_lowercase());
+	write("pxsum-formats.txt", &wrap_formats(&names));
+}
+
+/// # Wrap Format Names.
+///
+/// Join the names into the FORMATS section of the help screen, wrapping
+/// lines at eighty columns.
+fn wrap_formats(names: &[&str]) -> String {
+	let mut out = String::from(
+		"    Only image paths with valid file extensions for the following formats are\n    supported:",
 	);
+	let mut width = 14;
+	for (idx, name) in names.iter().enumerate() {
+		let comma = usize::from(idx + 1 != names.len());
+		if 80 < width + 1 + name.len() + comma {
+			out.push_str("\n   ");
+			width = 3;
+		}
+		out.push(' ');
+		out.push_str(name);
+		if comma == 1 { out.push(','); }
+		width += 1 + name.len() + comma;
+	}
+	out
+}
 
+/// # Write File.
+///
+/// Write the generated contents to `OUT_DIR`.
+fn write(name: &str, out: &str) {
 	let out_path = std::fs::canonicalize(std::env::var("OUT_DIR").expect("Missing OUT_DIR."))
 		.expect("Missing OUT_DIR.")
-		.join("pxsum-ext.rs");
+		.join(name);
 
 	std::fs::File::create(out_path)
 		.and_then(|mut f| f.write_all(out.as_bytes()).and_then(|_| f.flush()))
@@ -2,7 +2,8 @@
 # pxsum: Cli Arguments.
 */
 
-use crate::PxsumError;
+use crate::cli::CliError;
+use pxsum::Checksum;
 use dactyl::traits::BytesToUnsigned;
 use std::{
 	ffi::{
@@ -14,53 +15,94 @@ use std::{
 		OsStringExt,
 		OsStrExt,
 	},
+	path::{
+		Path,
+		PathBuf,
+	},
 };
 use trimothy::TrimMut;
 use walkdir::WalkDir;
 
 
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 /// # Runtime Settings.
 pub(super) struct Settings {
 	/// # Flags.
-	flags: u8,
+	flags: u32,
 
 	/// # Max Parallelism.
 	threads: NonZeroUsize,
+
+	/// # Similarity Distance.
+	similar: Option<u32>,
+
+	/// # Flatten Background.
+	background: Option<[u8; 3]>,
+
+	/// # Diff Reference (File or Directory).
+	diff_against: Option<PathBuf>,
+
+	/// # Diff Image Directory.
+	diff_image: Option<PathBuf>,
+
+	/// # Guard Command.
+	command: Vec<OsString>,
+
+	/// # SVG Render Size.
+	svg_size: [u32; 2],
 }
 
 impl Settings {
 	/// # From CLI Arguments.
-	pub(super) fn new() -> Result<(Self, Vec<OsString>), PxsumError> {
+	pub(super) fn new() -> Result<(Self, Vec<OsString>), CliError> {
 		Self::from_iter(std::env::args_os().map(OsStringExt::into_vec).skip(1))
 	}
 
+	#[expect(clippy::too_many_lines, reason = "There are a lot of arguments.")]
 	/// # From Arguments Iterator.
 	///
 	/// Any sort of `Vec`-iterating set of arguments will do.
 	///
 	/// This is a bit much, but just this side of "worth it" as we don't have
-	/// too many arguments to worry about. So long as we're under the clippy
-	/// line limit I think it's okay. Haha.
-	fn from_iter<I>(raw: I) -> Result<(Self, Vec<OsString>), PxsumError>
+	/// too many arguments to worry about. We've outgrown the clippy line
+	/// limit, but it's still mostly just one big match. Haha.
+	fn from_iter<I>(mut raw: I) -> Result<(Self, Vec<OsString>), CliError>
 	where I: Iterator<Item=Vec<u8>> {
 		// So much setup!
 		let mut flags = Self::PRINT_VALID | Self::PRINT_WARNINGS;
 		let mut threads = std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+		let mut similar: Option<u32> = None;
+		let mut background: Option<[u8; 3]> = None;
+		let mut diff_against: Option<PathBuf> = None;
+		let mut diff_image: Option<PathBuf> = None;
+		let mut command: Vec<OsString> = Vec::new();
+		let mut svg_size: [u32; 2] = [0, 0];
 		let mut dirs: Vec<OsString> = Vec::new();
 		let mut paths: Vec<OsString> = Vec::new();
 		let mut last = CurrentKey::None;
 
 		// Loop the loop!
-		for mut src in raw {
+		while let Some(mut src) = raw.next() {
 			src.trim_mut();
+
+			// The --flatten color is required, and can't be another flag.
+			if matches!(last, CurrentKey::Flatten) && matches!(src.first(), None | Some(b'-')) {
+				return Err(CliError::Background);
+			}
+
 			if src.is_empty() {
 				last = CurrentKey::None;
 				continue;
 			}
 
 			match src.as_slice() {
+				// -- (Everything after is the --guard command.)
+				[b'-', b'-'] if 0 != flags & Self::GUARD => {
+					command.extend(raw.by_ref().map(OsString::from_vec));
+					break;
+				},
+
 				// --bench
 				[b'-', b'-', b'b', b'e', b'n', b'c', b'h', ] => { flags |= Self::PRINT_TIME; },
 
@@ -68,23 +110,91 @@ impl Settings {
 				[b'-', b'c'] |
 				[b'-', b'-', b'c', b'h', b'e', b'c', b'k']  => { flags |= Self::CHECK; },
 
+				// --color-managed
+				[b'-', b'-', b'c', b'o', b'l', b'o', b'r', b'-', b'm', b'a', b'n', b'a', b'g', b'e', b'd'] => { flags |= Self::COLOR_MANAGED; },
+
+				// --compare
+				[b'-', b'-', b'c', b'o', b'm', b'p', b'a', b'r', b'e'] => { flags |= Self::COMPARE; },
+
+				// --cross-check
+				[b'-', b'-', b'c', b'r', b'o', b's', b's', b'-', b'c', b'h', b'e', b'c', b'k'] => { flags |= Self::CROSS_CHECK; },
+
+				// --deep
+				[b'-', b'-', b'd', b'e', b'e', b'p'] => { flags |= Self::DEEP; },
+
+				// --first-frame
+				[b'-', b'-', b'f', b'i', b'r', b's', b't', b'-', b'f', b'r', b'a', b'm', b'e'] => { flags |= Self::FIRST_FRAME; },
+
+				// --fix-extensions
+				[b'-', b'-', b'f', b'i', b'x', b'-', b'e', b'x', b't', b'e', b'n', b's', b'i', b'o', b'n', b's'] => { flags |= Self::FIX_EXTENSIONS; },
+
+				// --flatten
+				[b'-', b'-', b'f', b'l', b'a', b't', b't', b'e', b'n'] => {
+					last = CurrentKey::Flatten;
+					continue;
+				},
+
+				// --flatten=<HEX>
+				[b'-', b'-', b'f', b'l', b'a', b't', b't', b'e', b'n', b'=', rest @ ..] => {
+					background.replace(parse_background(rest)?);
+				},
+
+				// --frame-timing
+				[b'-', b'-', b'f', b'r', b'a', b'm', b'e', b'-', b't', b'i', b'm', b'i', b'n', b'g'] => { flags |= Self::FRAME_TIMING; },
+
 				// -g / --group-by-checksum
 				[b'-', b'g'] |
 				[b'-', b'-', b'g', b'r', b'o', b'u', b'p', b'-', b'b', b'y', b'-', b'c', b'h', b'e', b'c', b'k', b's', b'u', b'm']  => { flags |= Self::GROUP_BY_CHECKSUM; },
 
+				// --guard
+				[b'-', b'-', b'g', b'u', b'a', b'r', b'd'] => { flags |= Self::GUARD; },
+
+				// --ignore-alpha
+				[b'-', b'-', b'i', b'g', b'n', b'o', b'r', b'e', b'-', b'a', b'l', b'p', b'h', b'a'] => { flags |= Self::IGNORE_ALPHA; },
+
+				// --ignore-extension
+				[b'-', b'-', b'i', b'g', b'n', b'o', b'r', b'e', b'-', b'e', b'x', b't', b'e', b'n', b's', b'i', b'o', b'n'] => { flags |= Self::IGNORE_EXTENSION; },
+
+				// --lint
+				[b'-', b'-', b'l', b'i', b'n', b't'] => { flags |= Self::LINT; },
+
 				// --no-warnings
 				[b'-', b'-', b'n', b'o', b'-', b'w', b'a', b'r', b'n', b'i', b'n', b'g', b's']  => { flags &= ! Self::PRINT_WARNINGS; },
 
 				// --only-dupes
 				[b'-', b'-', b'o', b'n', b'l', b'y', b'-', b'd', b'u', b'p', b'e', b's']  => { flags |= Self::ONLY_DUPES; },
 
+				// --oriented
+				[b'-', b'-', b'o', b'r', b'i', b'e', b'n', b't', b'e', b'd'] => { flags |= Self::ORIENTED; },
+
+				// --perceptual
+				[b'-', b'-', b'p', b'e', b'r', b'c', b'e', b'p', b't', b'u', b'a', b'l'] => { flags |= Self::PERCEPTUAL; },
+
+				// --premultiplied
+				[b'-', b'-', b'p', b'r', b'e', b'm', b'u', b'l', b't', b'i', b'p', b'l', b'i', b'e', b'd'] => { flags |= Self::PREMULTIPLIED; },
+
 				// -q / --quiet
 				[b'-', b'q'] |
 				[b'-', b'-', b'q', b'u', b'i', b'e', b't']  => { flags &= ! Self::PRINT_VALID; },
 
+				// --raw-sensor
+				[b'-', b'-', b'r', b'a', b'w', b'-', b's', b'e', b'n', b's', b'o', b'r'] => { flags |= Self::RAW_SENSOR; },
+
+				// --sniff
+				[b'-', b'-', b's', b'n', b'i', b'f', b'f'] => { flags |= Self::SNIFF; },
+
+				// --split-pages
+				[b'-', b'-', b's', b'p', b'l', b'i', b't', b'-', b'p', b'a', b'g', b'e', b's'] => { flags |= Self::SPLIT_PAGES; },
+
+				// --stdin-tar
+				[b'-', b'-', b's', b't', b'd', b'i', b'n', b'-', b't', b'a', b'r'] => { flags |= Self::STDIN_TAR; },
+
 				// --strict
 				[b'-', b'-', b's', b't', b'r', b'i', b'c', b't']  => { flags |= Self::STRICT; },
 
+				// --tiled
+				[b'-', b'-', b't', b'i', b'l', b'e', b'd'] => { flags |= Self::TILED; },
+
 				// -d / --dir
 				[b'-', b'd'] |
 				[b'-', b'-', b'd', b'i', b'r'] => {
@@ -106,6 +216,50 @@ impl Settings {
 					dirs.push(OsString::from_vec(src));
 				},
 
+				// --diff-against
+				[b'-', b'-', b'd', b'i', b'f', b'f', b'-', b'a', b'g', b'a', b'i', b'n', b's', b't'] => {
+					last = CurrentKey::DiffAgainst;
+					continue;
+				},
+
+				// --diff-against=<PATH>
+				[b'-', b'-', b'd', b'i', b'f', b'f', b'-', b'a', b'g', b'a', b'i', b'n', b's', b't', b'=', rest @ ..] => {
+					diff_against.replace(PathBuf::from(OsStr::from_bytes(rest.trim_ascii())));
+				},
+
+				// --diff-image
+				[b'-', b'-', b'd', b'i', b'f', b'f', b'-', b'i', b'm', b'a', b'g', b'e'] => {
+					last = CurrentKey::DiffImage;
+					continue;
+				},
+
+				// --diff-image=<DIR>
+				[b'-', b'-', b'd', b'i', b'f', b'f', b'-', b'i', b'm', b'a', b'g', b'e', b'=', rest @ ..] => {
+					diff_image.replace(PathBuf::from(OsStr::from_bytes(rest.trim_ascii())));
+				},
+
+				// --similar
+				[b'-', b'-', b's', b'i', b'm', b'i', b'l', b'a', b'r'] => {
+					last = CurrentKey::Similar;
+					continue;
+				},
+
+				// --similar=<NUM>
+				[b'-', b'-', b's', b'i', b'm', b'i', b'l', b'a', b'r', b'=', rest @ ..] => {
+					similar.replace(parse_similar(rest)?);
+				},
+
+				// --svg-size
+				[b'-', b'-', b's', b'v', b'g', b'-', b's', b'i', b'z', b'e'] => {
+					last = CurrentKey::SvgSize;
+					continue;
+				},
+
+				// --svg-size=<SIZE>
+				[b'-', b'-', b's', b'v', b'g', b'-', b's', b'i', b'z', b'e', b'=', rest @ ..] => {
+					svg_size = parse_svg_size(rest)?;
+				},
+
 				// -j
 				[b'-', b'j', rest @ ..]  => {
 					let rest = rest.trim_ascii_start();
@@ -120,11 +274,11 @@ impl Settings {
 
 				// -h / --help
 				[b'-', b'h'] |
-				[b'-', b'-', b'h', b'e', b'l', b'p'] => return Err(PxsumError::PrintHelp),
+				[b'-', b'-', b'h', b'e', b'l', b'p'] => return Err(CliError::PrintHelp),
 
 				// -V / --version
 				[b'-', b'V'] |
-				[b'-', b'-', b'v', b'e', b'r', b's', b'i', b'o', b'n'] => return Err(PxsumError::PrintVersion),
+				[b'-', b'-', b'v', b'e', b'r', b's', b'i', b'o', b'n'] => return Err(CliError::PrintVersion),
 
 				// Everything else!
 				rest => match last {
@@ -134,37 +288,65 @@ impl Settings {
 					// Threads.
 					CurrentKey::Threads => { set_threads(&mut threads, rest); },
 
+					// Similarity.
+					CurrentKey::Similar => { similar.replace(parse_similar(rest)?); },
+
+					// Flatten background.
+					CurrentKey::Flatten => { background.replace(parse_background(rest)?); },
+
+					// SVG size.
+					CurrentKey::SvgSize => { svg_size = parse_svg_size(rest)?; },
+
+					// Diff reference.
+					CurrentKey::DiffAgainst => { diff_against.replace(PathBuf::from(OsString::from_vec(src))); },
+
+					// Diff image directory.
+					CurrentKey::DiffImage => { diff_image.replace(PathBuf::from(OsString::from_vec(src))); },
+
 					// Something else…
 					CurrentKey::None => { paths.push(OsString::from_vec(src)); },
 				},
-			};
+			}
 
 			// All but two cases require this to reset.
 			last = CurrentKey::None;
 		}
 
+		// Ditto for a --flatten at the very end.
+		if matches!(last, CurrentKey::Flatten) { return Err(CliError::Background); }
+
+		// The alpha policies are mutually exclusive.
+		let policies =
+			u8::from(0 != flags & Self::STRICT) +
+			u8::from(0 != flags & Self::PREMULTIPLIED) +
+			u8::from(0 != flags & Self::IGNORE_ALPHA) +
+			u8::from(background.is_some());
+		if 1 < policies { return Err(CliError::AlphaPolicy); }
+
+		// Guarding requires a command.
+		if 0 != flags & Self::GUARD && command.is_empty() {
+			return Err(CliError::Guard);
+		}
+
+		// Comparisons need exactly two paths, in the order given.
+		if 0 != flags & Self::COMPARE {
+			if paths.len() != 2 { return Err(CliError::Compare); }
+			return Ok((
+				Self { flags, threads, similar, background, diff_against, diff_image, command, svg_size },
+				paths,
+			));
+		}
+
 		// Finish up with some path work, unless -c/--check got set.
 		if 0 == flags & Self::CHECK {
-			// Go ahead and drop paths that don't have a proper extension.
-			paths.retain(|p| crate::check_extension(p.as_bytes()));
-
-			// And crawl any directories requested.
-			for d in dirs {
-				for e in WalkDir::new(d).follow_links(true).into_iter().flatten() {
-					if
-						! e.file_type().is_dir() &&
-						crate::check_extension(e.path().as_os_str().as_bytes())
-					{
-						paths.push(e.into_path().into_os_string());
-					}
-				}
-			}
+			crawl_paths(dirs, &mut paths, 0 != flags & Self::SNIFF);
 		}
 
-		// Path touch-ups.
+		// Path touch-ups. A lone STDIN image gets a lone thread, but TAR
+		// streams are crunched in parallel.
 		if paths.is_empty() {
 			paths.push(OsStr::new("-").to_owned());
-			threads = NonZeroUsize::MIN;
+			if 0 == flags & Self::STDIN_TAR { threads = NonZeroUsize::MIN; }
 		}
 		else {
 			paths.sort_unstable();
@@ -172,7 +354,10 @@ impl Settings {
 		}
 
 		// Done!
-		Ok((Self { flags, threads }, paths))
+		Ok((
+			Self { flags, threads, similar, background, diff_against, diff_image, command, svg_size },
+			paths,
+		))
 	}
 }
 
@@ -188,36 +373,181 @@ macro_rules! get {
 
 impl Settings {
 	/// # Verification Mode.
-	const CHECK: u8 =             0b0000_0001;
+	const CHECK: u32 =             0b0000_0000_0000_0000_0000_0000_0000_0001;
 
 	/// # Group Output by Checksum.
-	const GROUP_BY_CHECKSUM: u8 = 0b0000_0010;
+	const GROUP_BY_CHECKSUM: u32 = 0b0000_0000_0000_0000_0000_0000_0000_0010;
 
 	/// # Only Report (Grouped) Dupes.
-	const ONLY_DUPES: u8 =        0b0000_0110; // Implies GROUP_BY_CHECKSUM.
+	const ONLY_DUPES: u32 =        0b0000_0000_0000_0000_0000_0000_0000_0110; // Implies GROUP_BY_CHECKSUM.
 
 	/// # Checksum w/ Invisible Pixels.
-	const STRICT: u8 =            0b0000_1000;
+	const STRICT: u32 =            0b0000_0000_0000_0000_0000_0000_0000_1000;
 
 	/// # Print Total Execution Time.
-	const PRINT_TIME: u8 =        0b0001_0000;
+	const PRINT_TIME: u32 =        0b0000_0000_0000_0000_0000_0000_0001_0000;
 
 	/// # Print Verified (OK) Files.
-	const PRINT_VALID: u8 =       0b0010_0000;
+	const PRINT_VALID: u32 =       0b0000_0000_0000_0000_0000_0000_0010_0000;
 
 	/// # Print Read/Decode/Formatting Warnings.
-	const PRINT_WARNINGS: u8 =    0b0100_0000;
+	const PRINT_WARNINGS: u32 =    0b0000_0000_0000_0000_0000_0000_0100_0000;
+
+	/// # Checksum w/ Native Precision.
+	const DEEP: u32 =              0b0000_0000_0000_0000_0000_0000_1000_0000;
+
+	/// # Perceptual Fingerprints Instead of Checksums.
+	const PERCEPTUAL: u32 =        0b0000_0000_0000_0000_0000_0001_0000_0000;
+
+	/// # Only Checksum the First Frame of Animations.
+	const FIRST_FRAME: u32 =       0b0000_0000_0000_0000_0000_0010_0000_0000;
+
+	/// # Checksum Animation Timing.
+	const FRAME_TIMING: u32 =      0b0000_0000_0000_0000_0000_0100_0000_0000;
+
+	/// # Checksum Container Pages Separately.
+	const SPLIT_PAGES: u32 =       0b0000_0000_0000_0000_0000_1000_0000_0000;
+
+	/// # Apply Orientation Before Checksumming.
+	const ORIENTED: u32 =          0b0000_0000_0000_0000_0001_0000_0000_0000;
+
+	/// # Convert to sRGB Before Checksumming.
+	const COLOR_MANAGED: u32 =     0b0000_0000_0000_0000_0010_0000_0000_0000;
+
+	/// # Checksum w/ Premultiplied Alpha.
+	const PREMULTIPLIED: u32 =     0b0000_0000_0000_0000_0100_0000_0000_0000;
+
+	/// # Checksum w/o Alpha.
+	const IGNORE_ALPHA: u32 =      0b0000_0000_0000_0000_1000_0000_0000_0000;
+
+	/// # Checksum by the Tile.
+	const TILED: u32 =             0b0000_0000_0000_0001_0000_0000_0000_0000;
+
+	/// # Compare Two Images.
+	const COMPARE: u32 =           0b0000_0000_0000_0010_0000_0000_0000_0000;
+
+	/// # Pair Compared Files Without Their Extensions.
+	const IGNORE_EXTENSION: u32 =  0b0000_0000_0000_0100_0000_0000_0000_0000;
+
+	/// # Guard Images While Running a Command.
+	const GUARD: u32 =             0b0000_0000_0000_1000_0000_0000_0000_0000;
+
+	/// # Cross-Check Decoders.
+	const CROSS_CHECK: u32 =       0b0000_0000_0001_0000_0000_0000_0000_0000;
+
+	/// # Checksum Camera RAW Sensor Data.
+	const RAW_SENSOR: u32 =        0b0000_0000_0010_0000_0000_0000_0000_0000;
+
+	/// # Detect Formats by Content.
+	const SNIFF: u32 =             0b0000_0000_0100_0000_0000_0000_0000_0000;
+
+	/// # Lint Mode.
+	const LINT: u32 =              0b0000_0000_1000_0000_0000_0000_0000_0000;
+
+	/// # Fix Misnamed Extensions.
+	const FIX_EXTENSIONS: u32 =    0b0000_0001_1000_0000_0000_0000_0000_0000; // Implies LINT.
+
+	/// # Crunch a TAR Stream From STDIN.
+	const STDIN_TAR: u32 =         0b0000_0010_0000_0000_0000_0000_0000_0000;
 
 	get!(
 		"Verification Mode", check, CHECK,
+		"Comparison Mode", compare, COMPARE,
+		"Ignore Extensions", ignore_extension, IGNORE_EXTENSION,
+		"Cross-Check Decoders", cross_check, CROSS_CHECK,
 		"Group by Checksum", group_by_checksum, GROUP_BY_CHECKSUM,
 		"Only Report (Grouped) Duplicates", only_dupes, ONLY_DUPES,
 		"Strict Checksums", strict, STRICT,
+		"Deep Checksums", deep, DEEP,
+		"Perceptual Fingerprints", perceptual, PERCEPTUAL,
+		"First Frame Only", first_frame, FIRST_FRAME,
+		"Frame Timing", frame_timing, FRAME_TIMING,
+		"Split Pages", split_pages, SPLIT_PAGES,
+		"Oriented Checksums", oriented, ORIENTED,
+		"Color-Managed Checksums", color_managed, COLOR_MANAGED,
+		"Premultiplied Checksums", premultiplied, PREMULTIPLIED,
+		"Ignore Alpha", ignore_alpha, IGNORE_ALPHA,
+		"Tiled Checksums", tiled, TILED,
+		"Raw Sensor Checksums", raw_sensor, RAW_SENSOR,
+		"Sniff Formats", sniff, SNIFF,
+		"Lint Mode", lint, LINT,
+		"Fix Extensions", fix_extensions, FIX_EXTENSIONS,
+		"TAR Stream Mode", stdin_tar, STDIN_TAR,
 		"Print Total Execution Time.", print_time, PRINT_TIME,
 		"Print Verified (OK) Files.", print_valid, PRINT_VALID,
 		"Print Image Warnings", print_warnings, PRINT_WARNINGS,
 	);
 
+	/// # Checksum Flags.
+	///
+	/// Return the `Checksum` mode flags corresponding to the settings.
+	pub(super) const fn checksum_flags(&self) -> u16 {
+		let mut flags =
+			if self.strict() { Checksum::STRICT }
+			else if self.premultiplied() { Checksum::PREMULTIPLIED }
+			else if self.ignore_alpha() { Checksum::IGNORE_ALPHA }
+			else if self.background.is_some() { Checksum::FLATTEN }
+			else { Checksum::LOOSE };
+		if self.deep() { flags |= Checksum::DEEP; }
+		if self.oriented() { flags |= Checksum::ORIENTED; }
+		if self.color_managed() { flags |= Checksum::COLOR_MANAGED; }
+		if self.tiled() { flags |= Checksum::TILED; }
+		if self.raw_sensor() { flags |= Checksum::RAW_SENSOR; }
+		if ! self.first_frame() {
+			flags |= Checksum::ALL_FRAMES;
+			if self.frame_timing() { flags |= Checksum::TIMING; }
+		}
+		flags
+	}
+
+	/// # Checksum.
+	///
+	/// Return a new `Checksum` instance configured per the settings. (The
+	/// flags and background only matter for crunching; verification uses
+	/// those of the reference checksums instead.)
+	pub(super) const fn checksum(&self) -> Checksum {
+		Checksum::new(self.checksum_flags())
+			.with_background(match self.background { Some(bg) => bg, None => [0; 3] })
+			.with_svg_size(self.svg_size)
+			.with_sniff(self.sniff())
+	}
+
+	/// # Similarity Distance.
+	///
+	/// If `--similar` was set, return its (maximum Hamming) distance.
+	pub(super) const fn similar(&self) -> Option<u32> { self.similar }
+
+	/// # Flatten Background.
+	///
+	/// If `--flatten` was set, return its (RGB) background color.
+	pub(super) const fn background(&self) -> Option<[u8; 3]> { self.background }
+
+	/// # Diff Reference.
+	///
+	/// If `--diff-against` was set, return the reference file or directory.
+	pub(super) fn diff_against(&self) -> Option<&Path> { self.diff_against.as_deref() }
+
+	/// # Diff Image Directory.
+	///
+	/// If `--diff-image` was set, return the directory to save visualizations
+	/// to.
+	pub(super) fn diff_image(&self) -> Option<&Path> { self.diff_image.as_deref() }
+
+	/// # Guard Command.
+	///
+	/// If `--guard` was set, return the command (and arguments) to run on
+	/// each image.
+	pub(super) const fn guard(&self) -> Option<&[OsString]> {
+		if Self::GUARD == self.flags & Self::GUARD { Some(self.command.as_slice()) }
+		else { None }
+	}
+
+	/// # SVG Size.
+	///
+	/// Return the width and height to render SVGs at, if set with
+	/// `--svg-size`. Zeroes mean "auto".
+	pub(super) const fn svg_size(&self) -> [u32; 2] { self.svg_size }
+
 	/// # Threads.
 	pub(super) const fn threads(&self) -> NonZeroUsize { self.threads }
 }
@@ -233,15 +563,130 @@ enum CurrentKey {
 	/// # Not an option.
 	None,
 
+	/// # Diff Reference.
+	DiffAgainst,
+
+	/// # Diff Image Directory.
+	DiffImage,
+
 	/// # Directory.
 	Dir,
 
+	/// # Flatten Background.
+	Flatten,
+
+	/// # Similarity Distance.
+	Similar,
+
+	/// # SVG Render Size.
+	SvgSize,
+
 	/// # Max Worker Threads.
 	Threads,
 }
 
 
 
+/// # Parse Similarity Distance.
+///
+/// This method parses the requested `--similar` distance, which must be a
+/// number between zero and sixty-four (the number of bits in a fingerprint).
+fn parse_similar(wanted: &[u8]) -> Result<u32, CliError> {
+	u32::btou(wanted.trim_ascii())
+		.filter(|&n| n <= 64)
+		.ok_or(CliError::Similar)
+}
+
+/// # Parse SVG Size.
+///
+/// This method parses the requested `--svg-size`, which must be a width, or
+/// a width and height separated by an `x`, e.g. `512` or `512x256`. (The
+/// width may also be omitted, as in `x256`, to scale by the height instead.)
+fn parse_svg_size(wanted: &[u8]) -> Result<[u32; 2], CliError> {
+	let wanted = wanted.trim_ascii();
+	let (w, h) = wanted.iter()
+		.position(|&b| b == b'x' || b == b'X')
+		.map_or((wanted, &[][..]), |idx| (&wanted[..idx], &wanted[idx + 1..]));
+	let dim = |n: &[u8]|
+		if n.is_empty() { Some(0) }
+		else { u32::btou(n).filter(|&n| n != 0) };
+
+	match (dim(w), dim(h)) {
+		(Some(w), Some(h)) if w != 0 || h != 0 => Ok([w, h]),
+		_ => Err(CliError::SvgSize),
+	}
+}
+
+/// # Crawl Paths.
+///
+/// Drop any `paths` that don't have a proper image extension — or when
+/// `sniff`ing, aren't regular files — then recursively add any images found
+/// in `dirs`.
+fn crawl_paths(dirs: Vec<OsString>, paths: &mut Vec<OsString>, sniff: bool) {
+	// Go ahead and drop paths that don't have a proper extension.
+	if sniff { paths.retain(|p| p == "-" || Path::new(p).is_file()); }
+	else {
+		paths.retain(|p| pxsum::check_extension(p.as_bytes()) || pxsum::check_archive(p.as_bytes()));
+	}
+
+	// And crawl any directories requested.
+	for d in dirs {
+		paths.extend(crawl_dir(d, sniff).map(PathBuf::into_os_string));
+	}
+}
+
+#[expect(clippy::filetype_is_file, reason = "Regular files are the point.")]
+/// # Crawl Directory.
+///
+/// Recursively find and return the paths of any images — files with proper
+/// extensions — and image-bearing archives in `dir`. When `sniff`ing, every
+/// regular file is returned instead, leaving it to the decoder to sort out
+/// which are images.
+pub(super) fn crawl_dir<P: AsRef<Path>>(dir: P, sniff: bool) -> impl Iterator<Item=PathBuf> {
+	WalkDir::new(dir).follow_links(true).into_iter().flatten().filter_map(move |e|
+		if
+			if sniff { e.file_type().is_file() }
+			else {
+				! e.file_type().is_dir() && (
+					pxsum::check_extension(e.path().as_os_str().as_bytes()) ||
+					pxsum::check_archive(e.path().as_os_str().as_bytes())
+				)
+			}
+		{
+			Some(e.into_path())
+		}
+		else { None }
+	)
+}
+
+/// # Parse Background Color.
+///
+/// This method parses the requested `--flatten` color, which must be a
+/// six-digit `RRGGBB` hex value, optionally prefixed with a `#`.
+fn parse_background(wanted: &[u8]) -> Result<[u8; 3], CliError> {
+	/// # Hex Digit.
+	const fn digit(b: u8) -> Option<u8> {
+		match b {
+			b'0'..=b'9' => Some(b - b'0'),
+			b'a'..=b'f' => Some(b - b'a' + 10),
+			b'A'..=b'F' => Some(b - b'A' + 10),
+			_ => None,
+		}
+	}
+
+	let wanted = wanted.trim_ascii();
+	let wanted = wanted.strip_prefix(b"#").unwrap_or(wanted);
+	if wanted.len() != 6 { return Err(CliError::Background); }
+
+	let mut out = [0_u8; 3];
+	for (dst, pair) in out.iter_mut().zip(wanted.chunks_exact(2)) {
+		let hi = digit(pair[0]).ok_or(CliError::Background)?;
+		let lo = digit(pair[1]).ok_or(CliError::Background)?;
+		*dst = hi * 16 + lo;
+	}
+	Ok(out)
+}
+
 /// # Set Threads.
 ///
 /// This method parses the requested user value (in raw byte form) into a
@@ -270,7 +715,7 @@ mod test {
 	///
 	/// The version and help flags work the same way, causing parsing to abort
 	/// with an error. This method consolidates the assertions to verify that.
-	fn run_version_help(short: &[u8], long: &[u8], err: PxsumError) {
+	fn run_version_help(short: &[u8], long: &[u8], err: CliError) {
 		// Straight cases.
 		assert_eq!(
 			Settings::from_iter(std::iter::once(short.to_vec())).expect_err("Help not detected."),
@@ -308,12 +753,12 @@ mod test {
 
 	#[test]
 	fn t_settings_help() {
-		run_version_help(b"-h", b"--help", PxsumError::PrintHelp);
+		run_version_help(b"-h", b"--help", CliError::PrintHelp);
 	}
 
 	#[test]
 	fn t_settings_version() {
-		run_version_help(b"-V", b"--version", PxsumError::PrintVersion);
+		run_version_help(b"-V", b"--version", CliError::PrintVersion);
 	}
 
 	#[test]
@@ -323,10 +768,34 @@ mod test {
 
 		// Optionals should be off.
 		assert!(! settings.check());
+		assert!(! settings.compare());
+		assert!(! settings.ignore_extension());
+		assert!(! settings.cross_check());
 		assert!(! settings.group_by_checksum());
 		assert!(! settings.only_dupes());
 		assert!(! settings.print_time());
 		assert!(! settings.strict());
+		assert!(! settings.deep());
+		assert!(! settings.perceptual());
+		assert!(! settings.first_frame());
+		assert!(! settings.frame_timing());
+		assert!(! settings.split_pages());
+		assert!(! settings.oriented());
+		assert!(! settings.color_managed());
+		assert!(! settings.premultiplied());
+		assert!(! settings.ignore_alpha());
+		assert!(! settings.tiled());
+		assert!(! settings.raw_sensor());
+		assert!(! settings.sniff());
+		assert!(! settings.lint());
+		assert!(! settings.fix_extensions());
+		assert!(! settings.stdin_tar());
+		assert_eq!(settings.similar(), None);
+		assert_eq!(settings.background(), None);
+		assert_eq!(settings.diff_against(), None);
+		assert_eq!(settings.diff_image(), None);
+		assert_eq!(settings.guard(), None);
+		assert_eq!(settings.checksum_flags(), Checksum::DEFAULT);
 
 		// The defaults.
 		assert!(settings.print_valid());
@@ -334,6 +803,209 @@ mod test {
 		assert_eq!(paths, &["-"]);
 	}
 
+	#[test]
+	fn t_settings_stdin_tar() {
+		let all = std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+
+		// Plain STDIN is single-threaded.
+		let (settings, paths) = Settings::from_iter([].into_iter())
+			.expect("Zero args failed.");
+		assert_eq!(settings.threads(), NonZeroUsize::MIN);
+		assert_eq!(paths, &["-"]);
+
+		// TAR streams are not.
+		let (settings, paths) = Settings::from_iter([b"--stdin-tar".to_vec()].into_iter())
+			.expect("Settings failed.");
+		assert!(settings.stdin_tar());
+		assert_eq!(settings.threads(), all);
+		assert_eq!(paths, &["-"]);
+
+		// But can still be limited.
+		let (settings, _) = Settings::from_iter([
+			b"--stdin-tar".to_vec(),
+			b"-j".to_vec(),
+			b"2".to_vec(),
+		].into_iter()).expect("Settings failed.");
+		assert_eq!(settings.threads(), all.min(NonZeroUsize::new(2).unwrap()));
+	}
+
+	#[test]
+	fn t_settings_similar() {
+		for args in [
+			vec![b"--similar".to_vec(), b"5".to_vec()],
+			vec![b"--similar=5".to_vec()],
+			vec![b"--similar= 5".to_vec()],
+		] {
+			let (settings, _) = Settings::from_iter(args.into_iter())
+				.expect("Settings failed.");
+			assert_eq!(settings.similar(), Some(5));
+		}
+
+		for args in [
+			vec![b"--similar".to_vec(), b"65".to_vec()],
+			vec![b"--similar=nope".to_vec()],
+		] {
+			assert_eq!(
+				Settings::from_iter(args.into_iter()).expect_err("Bad distance accepted."),
+				CliError::Similar,
+			);
+		}
+	}
+
+	#[test]
+	fn t_settings_svg_size() {
+		let (settings, _) = Settings::from_iter(std::iter::empty()).expect("Settings failed.");
+		assert_eq!(settings.svg_size(), [0, 0]);
+
+		for (args, size) in [
+			(vec![b"--svg-size".to_vec(), b"512".to_vec()], [512, 0]),
+			(vec![b"--svg-size=512x256".to_vec()], [512, 256]),
+			(vec![b"--svg-size= 512X256".to_vec()], [512, 256]),
+			(vec![b"--svg-size".to_vec(), b"x256".to_vec()], [0, 256]),
+		] {
+			let (settings, _) = Settings::from_iter(args.into_iter())
+				.expect("Settings failed.");
+			assert_eq!(settings.svg_size(), size);
+		}
+
+		for args in [
+			vec![b"--svg-size".to_vec(), b"0".to_vec()],
+			vec![b"--svg-size=x".to_vec()],
+			vec![b"--svg-size=512x0".to_vec()],
+			vec![b"--svg-size=nope".to_vec()],
+		] {
+			assert_eq!(
+				Settings::from_iter(args.into_iter()).expect_err("Bad size accepted."),
+				CliError::SvgSize,
+			);
+		}
+	}
+
+	#[test]
+	fn t_settings_diff() {
+		for args in [
+			vec![b"-c".to_vec(), b"--diff-against".to_vec(), b"orig/".to_vec(), b"--diff-image".to_vec(), b"out/".to_vec()],
+			vec![b"-c".to_vec(), b"--diff-against=orig/".to_vec(), b"--diff-image=out/".to_vec()],
+		] {
+			let (settings, _) = Settings::from_iter(args.into_iter())
+				.expect("Settings failed.");
+			assert_eq!(settings.diff_against(), Some(Path::new("orig/")));
+			assert_eq!(settings.diff_image(), Some(Path::new("out/")));
+		}
+	}
+
+	#[test]
+	fn t_settings_compare() {
+		// Order is preserved.
+		let args = [b"--compare".to_vec(), b"b.png".to_vec(), b"a.webp".to_vec()];
+		let (settings, paths) = Settings::from_iter(args.into_iter())
+			.expect("Settings failed.");
+		assert!(settings.compare());
+		assert_eq!(paths, &["b.png", "a.webp"]);
+
+		// Anything but two is a problem.
+		for args in [
+			vec![b"--compare".to_vec()],
+			vec![b"--compare".to_vec(), b"a.png".to_vec()],
+			vec![b"--compare".to_vec(), b"a.png".to_vec(), b"b.png".to_vec(), b"c.png".to_vec()],
+		] {
+			assert_eq!(
+				Settings::from_iter(args.into_iter()).expect_err("Bad path count accepted."),
+				CliError::Compare,
+			);
+		}
+	}
+
+	#[test]
+	fn t_settings_guard() {
+		let args = [
+			b"--guard".to_vec(),
+			b"-j1".to_vec(),
+			b"a.png".to_vec(),
+			b"--".to_vec(),
+			b"oxipng".to_vec(),
+			b"-o".to_vec(),
+			b"max".to_vec(),
+			b"{}".to_vec(),
+			b" -q".to_vec(),
+		];
+		let (settings, paths) = Settings::from_iter(args.into_iter())
+			.expect("Settings failed.");
+		assert_eq!(paths, &["a.png"]);
+		assert_eq!(
+			settings.guard(),
+			Some([
+				OsString::from("oxipng"),
+				OsString::from("-o"),
+				OsString::from("max"),
+				OsString::from("{}"),
+				OsString::from(" -q"),
+			].as_slice()),
+		);
+
+		// Guarding needs a command.
+		for args in [
+			vec![b"--guard".to_vec(), b"a.png".to_vec()],
+			vec![b"--guard".to_vec(), b"a.png".to_vec(), b"--".to_vec()],
+		] {
+			assert_eq!(
+				Settings::from_iter(args.into_iter()).expect_err("Bad guard accepted."),
+				CliError::Guard,
+			);
+		}
+
+		// But without guarding, -- is just another (non-image) path.
+		let args = [b"a.png".to_vec(), b"--".to_vec(), b"b.png".to_vec()];
+		let (settings, paths) = Settings::from_iter(args.into_iter())
+			.expect("Settings failed.");
+		assert_eq!(settings.guard(), None);
+		assert_eq!(paths, &["a.png", "b.png"]);
+	}
+
+	#[test]
+	fn t_settings_alpha() {
+		for (args, flags, background) in [
+			(vec![b"--strict".to_vec()], Checksum::STRICT, None),
+			(vec![b"--premultiplied".to_vec()], Checksum::PREMULTIPLIED, None),
+			(vec![b"--ignore-alpha".to_vec()], Checksum::IGNORE_ALPHA, None),
+			(vec![b"--flatten".to_vec(), b"#FF8000".to_vec()], Checksum::FLATTEN, Some([255, 128, 0])),
+			(vec![b"--flatten=ff8000".to_vec()], Checksum::FLATTEN, Some([255, 128, 0])),
+		] {
+			let (settings, _) = Settings::from_iter(args.into_iter())
+				.expect("Settings failed.");
+			assert_eq!(settings.checksum_flags() & Checksum::ALPHA_POLICY, flags);
+			assert_eq!(settings.background(), background);
+		}
+
+		// Bad colors.
+		for args in [
+			vec![b"--flatten".to_vec(), b"#FFF".to_vec()],
+			vec![b"--flatten=nopenope".to_vec()],
+			vec![b"--flatten=#gg0000".to_vec()],
+			vec![b"--flatten=".to_vec()],
+			vec![b"--flatten".to_vec()],
+			vec![b"--flatten".to_vec(), b"".to_vec(), b"#FF8000".to_vec()],
+			vec![b"--flatten".to_vec(), b"--deep".to_vec()],
+			vec![b"--flatten".to_vec(), b"--".to_vec(), b"true".to_vec()],
+		] {
+			assert_eq!(
+				Settings::from_iter(args.into_iter()).expect_err("Bad color accepted."),
+				CliError::Background,
+			);
+		}
+
+		// Conflicts.
+		for args in [
+			vec![b"--strict".to_vec(), b"--premultiplied".to_vec()],
+			vec![b"--ignore-alpha".to_vec(), b"--flatten=000000".to_vec()],
+		] {
+			assert_eq!(
+				Settings::from_iter(args.into_iter()).expect_err("Conflict accepted."),
+				CliError::AlphaPolicy,
+			);
+		}
+	}
+
 	#[test]
 	/// # Test Flags.
 	///
@@ -370,6 +1042,10 @@ mod test {
 		toggle_flag!(b"-c".to_vec(), check, false);
 		toggle_flag!(b"--check".to_vec(), check, false);
 
+		toggle_flag!(b"--compare".to_vec(), compare, false);
+		toggle_flag!(b"--ignore-extension".to_vec(), ignore_extension, false);
+		toggle_flag!(b"--cross-check".to_vec(), cross_check, false);
+
 		toggle_flag!(b"-g".to_vec(), group_by_checksum, false);
 		toggle_flag!(b"--group-by-checksum".to_vec(), group_by_checksum, false);
 
@@ -380,6 +1056,30 @@ mod test {
 
 		toggle_flag!(b"--strict".to_vec(), strict, false);
 
+		toggle_flag!(b"--deep".to_vec(), deep, false);
+
+		toggle_flag!(b"--perceptual".to_vec(), perceptual, false);
+
+		toggle_flag!(b"--first-frame".to_vec(), first_frame, false);
+		toggle_flag!(b"--frame-timing".to_vec(), frame_timing, false);
+		toggle_flag!(b"--split-pages".to_vec(), split_pages, false);
+
+		toggle_flag!(b"--oriented".to_vec(), oriented, false);
+		toggle_flag!(b"--color-managed".to_vec(), color_managed, false);
+
+		toggle_flag!(b"--premultiplied".to_vec(), premultiplied, false);
+		toggle_flag!(b"--ignore-alpha".to_vec(), ignore_alpha, false);
+
+		toggle_flag!(b"--tiled".to_vec(), tiled, false);
+		toggle_flag!(b"--raw-sensor".to_vec(), raw_sensor, false);
+		toggle_flag!(b"--sniff".to_vec(), sniff, false);
+		toggle_flag!(b"--lint".to_vec(), lint, false);
+		toggle_flag!(b"--stdin-tar".to_vec(), stdin_tar, false);
+
+		// This one toggles two different options too.
+		toggle_flag!(b"--fix-extensions".to_vec(), fix_extensions, false);
+		toggle_flag!(b"--fix-extensions".to_vec(), lint, false);
+
 		// This one toggles two different options.
 		toggle_flag!(b"--only-dupes".to_vec(), only_dupes, false);
 		toggle_flag!(b"--only-dupes".to_vec(), group_by_checksum, false);
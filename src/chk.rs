@@ -2,18 +2,27 @@
 # pxsum: Checksums.
 */
 
+mod archive;
+
 use crate::{
+	PxDiff,
 	PxImage,
 	PxKind,
 	PxsumError,
+	TileRegion,
 };
 use std::{
+	collections::BTreeMap,
 	ffi::OsStr,
 	fmt,
 	fs::File,
-	io::BufReader,
+	io::Read,
+	ops::ControlFlow,
+	path::Path,
 };
 
+pub use archive::check_archive;
+
 
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -22,7 +31,7 @@ use std::{
 /// This struct serves as a reusable de/encoder of sorts for each worker
 /// thread, helping to reduce the number of allocations made over the course of
 /// the run.
-pub(super) struct Checksum {
+pub struct Checksum {
 	/// # Image Path.
 	src: String,
 
@@ -31,6 +40,18 @@ pub(super) struct Checksum {
 
 	/// # File Buffer.
 	buf: Vec<u8>,
+
+	/// # Tile Digests.
+	tiles: Vec<[u8; 8]>,
+
+	/// # Changed Regions (Last Verification).
+	changed: Vec<TileRegion>,
+
+	/// # SVG Render Size.
+	svg_size: [u32; 2],
+
+	/// # Sniff Formats (Ignore Extensions).
+	sniff: bool,
 }
 
 impl fmt::Display for Checksum {
@@ -38,6 +59,8 @@ impl fmt::Display for Checksum {
 	///
 	/// This prints a pxsum/path pairing in the same style used by `md5sum`,
 	/// `b3sum`, etc.: the hex hash + two spaces + the path.
+	///
+	/// Tiled checksums extend the hash with a colon and the hex tile digests.
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		if self.src.is_empty() { Ok(()) }
 		else {
@@ -47,6 +70,16 @@ impl fmt::Display for Checksum {
 				.map_err(|_| fmt::Error)?;
 			f.write_str(chk)?;
 
+			// Then the tiles, if any.
+			if ! self.tiles.is_empty() {
+				f.write_str(":")?;
+				for tile in &self.tiles {
+					let tile = faster_hex::hex_encode(tile.as_slice(), &mut buf[..16])
+						.map_err(|_| fmt::Error)?;
+					f.write_str(tile)?;
+				}
+			}
+
 			// Two spaces.
 			f.write_str("  ")?;
 
@@ -57,31 +90,220 @@ impl fmt::Display for Checksum {
 }
 
 impl Checksum {
-	/// # Strictness Flag.
+	/// # Loose Alpha Policy.
+	///
+	/// This is the default alpha policy, in which fully-transparent pixels
+	/// are hashed by position rather than color, so that invisible color
+	/// drift won't affect the checksum.
 	///
-	/// This bit is used to indicate that all pixel colors — even invisible
+	/// The alpha policies occupy the lowest three bits of the checksum's
+	/// first byte, and are mutually exclusive; pick one.
+	pub const LOOSE: u16 =         0b0000_0000_0000_0000;
+
+	/// # Strict Alpha Policy.
+	///
+	/// This pattern is used to indicate that all pixel colors — even invisible
 	/// ones — should be factored into the checksum.
 	///
 	/// When active, it is encoded into the first byte of the checksum so that
 	/// subsequent verification can infer which mode to use.
-	pub(super) const STRICT: u8 = 0b0000_0001;
+	pub const STRICT: u16 =        0b0000_0000_0000_0001;
+
+	/// # Premultiplied Alpha Policy.
+	///
+	/// This pattern is used to indicate that the color channels were
+	/// premultiplied by the alpha channel before hashing, so that
+	/// semi-transparent pixels whose colors drifted during a round trip
+	/// through premultiplied storage still match.
+	///
+	/// It is only meaningful for V2 checksums.
+	pub const PREMULTIPLIED: u16 = 0b0000_0000_0000_0010;
+
+	/// # Ignore Alpha Policy.
+	///
+	/// This pattern is used to indicate that the alpha channel was ignored
+	/// entirely, every pixel being treated as fully opaque.
+	///
+	/// It is only meaningful for V2 checksums.
+	pub const IGNORE_ALPHA: u16 =  0b0000_0000_0000_0011;
+
+	/// # Flatten Alpha Policy.
+	///
+	/// This pattern is used to indicate that the image was composited over a
+	/// solid background color before hashing, as it would be when exported
+	/// to a format without transparency.
+	///
+	/// The background color is recorded in the third, fourth, and fifth bytes
+	/// of the checksum; see `Checksum::with_background`. It is only
+	/// meaningful for V2 checksums.
+	pub const FLATTEN: u16 =       0b0000_0000_0000_0100;
+
+	/// # Alpha Policy Mask.
+	pub const ALPHA_POLICY: u16 =  0b0000_0000_0000_0111;
+
+	/// # Oriented Flag.
+	///
+	/// This bit is used to indicate that any EXIF, TIFF, or HEIF-style
+	/// orientation metadata was applied to the image before hashing, so that
+	/// a rotated copy matches its tagged original.
+	///
+	/// It is only meaningful for V2 checksums.
+	pub const ORIENTED: u16 =      0b0000_0000_0000_1000;
+
+	/// # Timing Flag.
+	///
+	/// This bit is used to indicate that the loop count and frame delays of
+	/// an animation were hashed alongside its pixels.
+	///
+	/// Like `Checksum::ALL_FRAMES` — which it requires — it only sticks for
+	/// actual animations.
+	pub const TIMING: u16 =        0b0000_0000_0001_0000;
+
+	/// # All Frames Flag.
+	///
+	/// This bit is used to indicate that every frame of an animated GIF, PNG,
+	/// or WebP — or every page or entry of a multi-image TIFF or ICO — was
+	/// hashed, rather than just the first.
+	///
+	/// It is only recorded for images that actually have more than one frame,
+	/// so static checksums are the same either way. It is only meaningful for
+	/// V2 checksums.
+	pub const ALL_FRAMES: u16 =    0b0000_0000_0010_0000;
+
+	/// # Deep Flag.
+	///
+	/// This bit is used to indicate that pixels were hashed at their native
+	/// precision — RGBA16 or RGBA32F — rather than being truncated to RGBA8.
+	///
+	/// It is only meaningful for V2 checksums.
+	pub const DEEP: u16 =          0b0000_0000_0100_0000;
+
+	/// # Version Two Flag.
+	///
+	/// This bit is used to indicate that the image dimensions and frame count
+	/// were hashed alongside the pixel data, preventing collisions between
+	/// differently-shaped images with the same (flat) pixel values.
+	///
+	/// All newly-crunched checksums set this bit, but because it was once fair
+	/// game for the hash itself, legacy checksums may have it set too. When
+	/// verifying, the bit is only trusted if the rest of the mode bits are
+	/// consistent with V2, and even then a mismatch is re-checked the legacy
+	/// way before being declared a failure.
+	pub const V2: u16 =            0b0000_0000_1000_0000;
+
+	/// # Color-Managed Flag.
+	///
+	/// This bit is used to indicate that any embedded ICC or CICP color
+	/// profile was used to convert the pixels to sRGB before hashing, so that
+	/// differently-tagged copies of the same picture match.
+	///
+	/// Unlike the other mode bits, this one lives in the second byte of the
+	/// checksum. It is only meaningful for V2 checksums.
+	pub const COLOR_MANAGED: u16 = 0b0000_0001_0000_0000;
+
+	/// # Tiled Flag.
+	///
+	/// This bit is used to indicate that the pixels were hashed in 64x64
+	/// tiles, with the tile digests combined into a Merkle tree whose root
+	/// stands in for the pixel data.
+	///
+	/// The (truncated) tile digests are recorded alongside the checksum in
+	/// the manifest, allowing verification to pinpoint which regions of an
+	/// image changed. See `Checksum::changed_regions`. It lives in the second
+	/// byte and is only meaningful for V2 checksums.
+	pub const TILED: u16 =         0b0000_0010_0000_0000;
+
+	/// # Raw Sensor Flag.
+	///
+	/// This bit is used to indicate that a camera RAW was hashed by its
+	/// untouched sensor data rather than its developed (demosaiced) pixels.
+	///
+	/// Like `Checksum::ALL_FRAMES`, it is only recorded for images it actually
+	/// applies to. It lives in the second byte and is only meaningful for V2
+	/// checksums.
+	pub const RAW_SENSOR: u16 =    0b0000_0100_0000_0000;
+
+	/// # CLI Default Flags.
+	///
+	/// The flags used by the `pxsum` CLI when no mode options are given:
+	/// `Checksum::LOOSE` alpha with `Checksum::ALL_FRAMES`. Use this to crunch
+	/// checksums that match the CLI's default output.
+	pub const DEFAULT: u16 =       Self::LOOSE | Self::ALL_FRAMES;
+
+	/// # All Mode Flags.
+	pub(crate) const FLAGS: u16 = Self::ALPHA_POLICY | Self::ORIENTED | Self::TIMING | Self::ALL_FRAMES | Self::DEEP | Self::V2 | Self::COLOR_MANAGED | Self::TILED | Self::RAW_SENSOR;
 
+	#[must_use]
 	/// # New (Empty) Instance.
 	///
 	/// Return a fresh instance that can be reused for any number of new or
 	/// existing checksums.
 	///
-	/// Note the `strict` flag is only relevant for `Checksum::crunch`;
-	/// verification uses the strictness of the reference checksum(s) instead.
-	pub(super) const fn new(strict: bool) -> Self {
+	/// The `flags` — any one alpha policy (`Checksum::STRICT`, etc.)
+	/// combined with any of `Checksum::DEEP`, `Checksum::ALL_FRAMES`,
+	/// `Checksum::TIMING`, `Checksum::ORIENTED`, `Checksum::COLOR_MANAGED`,
+	/// `Checksum::TILED`, and `Checksum::RAW_SENSOR` — are only relevant for crunching; verification
+	/// uses the modes of the reference checksum(s) instead.
+	///
+	/// Note that `0` hashes only the first frame of animated or multi-page
+	/// images; pass `Checksum::DEFAULT` to match the CLI's default output.
+	pub const fn new(flags: u16) -> Self {
 		let mut chk = [0_u8; 32];
-		if strict { chk[0] |= Self::STRICT; }
+		[chk[0], chk[1]] = (Self::V2 | (flags & Self::FLAGS)).to_le_bytes();
 
 		Self {
 			src: String::new(),
 			chk,
 			buf: Vec::new(),
+			tiles: Vec::new(),
+			changed: Vec::new(),
+			svg_size: [0, 0],
+			sniff: false,
+		}
+	}
+
+	#[must_use]
+	/// # With Background.
+	///
+	/// Set the (RGB) background color to composite images over when crunching
+	/// with the `Checksum::FLATTEN` alpha policy. The default is black.
+	///
+	/// Like the flags, this is only relevant for crunching; verification uses
+	/// the color recorded in the reference checksum(s) instead. It has no
+	/// effect for the other alpha policies.
+	pub const fn with_background(mut self, background: [u8; 3]) -> Self {
+		if Self::FLATTEN == self.alpha_policy() {
+			[self.chk[2], self.chk[3], self.chk[4]] = background;
 		}
+		self
+	}
+
+	#[must_use]
+	/// # With SVG Size.
+	///
+	/// Set the width and height to rasterize SVGs at, instead of their
+	/// intrinsic size. Either may be zero to derive it from the other,
+	/// preserving the aspect ratio.
+	///
+	/// Unlike the background, this is not recorded in the checksum, so must
+	/// be set the same way for both crunching and verification.
+	pub const fn with_svg_size(mut self, size: [u32; 2]) -> Self {
+		self.svg_size = size;
+		self
+	}
+
+	#[must_use]
+	/// # With Sniffing.
+	///
+	/// Accept file paths regardless of their extensions, identifying images
+	/// purely by their file signatures instead. Files without a recognizable
+	/// signature (or image extension) are rejected with
+	/// `PxsumError::NotImage` after reading just the first sixteen bytes.
+	///
+	/// Non-regular files are rejected as invalid paths.
+	pub const fn with_sniff(mut self, sniff: bool) -> Self {
+		self.sniff = sniff;
+		self
 	}
 
 	/// # Crunch a Checksum Given a File Path.
@@ -92,102 +314,637 @@ impl Checksum {
 	///
 	/// This will return an error if the path is invalid or unreadable, or the
 	/// data is missing or cannot be decoded into a valid RGBA image.
-	pub(super) fn crunch<P>(&mut self, src: P) -> Result<(), PxsumError>
+	pub fn crunch<P>(&mut self, src: P) -> Result<(), PxsumError>
 	where P: AsRef<OsStr> {
 		src.as_ref().to_str().ok_or(PxsumError::Path).and_then(|s| self.set_path(s))?;
 		let fmt = self.read_raw()?;
-		self.chk = PxImage::new(self.buf.as_slice(), fmt)?.into_checksum(self.strict());
+		let flags = self.flags();
+		let img = self.decode(fmt, flags)?;
+		self.set_chk(img, flags);
+
+		Ok(())
+	}
+
+	/// # Crunch Checksums Page by Page Given a File Path.
+	///
+	/// Same as `Checksum::crunch`, except multi-image containers — TIFFs and
+	/// ICOs with more than one page or entry — are checksummed one sub-image
+	/// at a time, with each source recorded as `path#index`.
+	///
+	/// The callback is run after each checksum is crunched, or just the once
+	/// for regular images.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the path is invalid or unreadable, or the
+	/// data is missing or cannot be decoded into a valid RGBA image. For
+	/// containers, processing stops at the first bad page.
+	pub fn crunch_pages<P, F>(&mut self, src: P, mut cb: F) -> Result<(), PxsumError>
+	where P: AsRef<OsStr>, F: FnMut(&Self) {
+		use std::fmt::Write;
+
+		src.as_ref().to_str().ok_or(PxsumError::Path).and_then(|s| self.set_path(s))?;
+		let fmt = self.read_raw()?;
+		let flags = self.flags();
+
+		// Just the one.
+		let pages =
+			if self.stdin() || split_page(&self.src).1.is_some() { 1 }
+			else { fmt.pages(self.buf.as_slice()) };
+		if pages < 2 {
+			let img = self.decode(fmt, flags)?;
+			self.set_chk(img, flags);
+			cb(self);
+		}
+		// Page by page.
+		else {
+			let len = self.src.len();
+			for page in 0..pages {
+				self.src.truncate(len);
+				let _res = write!(self.src, "#{page}");
+				let img = PxImage::new_page(self.buf.as_slice(), fmt, flags, page)?
+					.with_background(self.background_raw());
+				self.set_chk(img, flags);
+				cb(self);
+			}
+		}
 
 		Ok(())
 	}
 
+	/// # Crunch Checksums for Each Image in an Archive.
+	///
+	/// Same as `Checksum::crunch_pages`, except the source is a ZIP or
+	/// (optionally gzipped) TAR archive — see `check_archive` — and it is the
+	/// entries that are checksummed one at a time, with each source recorded
+	/// as `archive!/entry`. Nothing is extracted to disk.
+	///
+	/// Entries are identified as images by their file signatures or, failing
+	/// that, their extensions; anything else is silently skipped.
+	///
+	/// The callback is run after each image entry is processed, successfully
+	/// or not.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the path is invalid or the archive itself
+	/// is unreadable. Errors with individual entries are passed to the
+	/// callback instead.
+	pub fn crunch_archive<P, F>(&mut self, src: P, cb: F) -> Result<(), PxsumError>
+	where P: AsRef<OsStr>, F: FnMut(&Self, Result<(), PxsumError>) {
+		let flags = self.flags();
+		self.walk_archive(
+			src.as_ref(),
+			|c, fmt| c.decode(fmt, flags).map(|img| c.set_chk(img, flags)),
+			cb,
+		)
+	}
+
+	/// # Crunch Fingerprints for Each Image in an Archive.
+	///
+	/// Same as `Checksum::crunch_archive`, except each entry's perceptual
+	/// fingerprint (see `Checksum::crunch_fingerprint`) is passed to the
+	/// callback instead. The path is updated, but the checksum is not.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the path is invalid or the archive itself
+	/// is unreadable. Errors with individual entries are passed to the
+	/// callback instead.
+	pub fn crunch_archive_fingerprints<P, F>(&mut self, src: P, cb: F) -> Result<(), PxsumError>
+	where P: AsRef<OsStr>, F: FnMut(&Self, Result<u64, PxsumError>) {
+		// Fingerprints only consider the first frame anyway.
+		let flags = self.flags() & ! (Self::ALL_FRAMES | Self::TIMING);
+		self.walk_archive(
+			src.as_ref(),
+			|c, fmt| c.decode(fmt, flags).map(|img| img.fingerprint()),
+			cb,
+		)
+	}
+
+	/// # Crunch a Checksum Given a Reader.
+	///
+	/// Replace `self` with a new checksum for the image data read from `src`.
+	/// As there is no path per se, the source will be recorded as "-", same as
+	/// STDIN.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the data is unreadable, missing, or cannot
+	/// be decoded into a valid RGBA image.
+	pub fn crunch_reader<R: Read>(&mut self, mut src: R) -> Result<(), PxsumError> {
+		self.src.truncate(0);
+		self.src.push('-');
+		let fmt = digest_reader(&mut src, &mut self.buf, None, 0)?;
+		let flags = self.flags();
+		let img = decode_raw(self.buf.as_slice(), &self.src, fmt, flags, self.svg_size)?
+			.with_background(self.background_raw());
+		self.set_chk(img, flags);
+
+		Ok(())
+	}
+
+	/// # Crunch a Checksum Given Raw Bytes.
+	///
+	/// Replace `self` with a new checksum for the image data in `src`. As
+	/// there is no path per se, the source will be recorded as "-", same as
+	/// STDIN.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the data is missing or cannot be decoded
+	/// into a valid RGBA image.
+	pub fn crunch_bytes(&mut self, src: &[u8]) -> Result<(), PxsumError> {
+		self.src.truncate(0);
+		self.src.push('-');
+		if src.is_empty() { return Err(PxsumError::NoData); }
+		let fmt = PxKind::try_from_magic(src)?.refine(src);
+		let flags = self.flags();
+		let img = decode_raw(src, &self.src, fmt, flags, self.svg_size)?
+			.with_background(self.background_raw());
+		self.set_chk(img, flags);
+
+		Ok(())
+	}
+
+	/// # Crunch a Checksum Given Raw Bytes and a Name.
+	///
+	/// Same as `Checksum::crunch_bytes`, except the source is recorded as
+	/// `name` — a TAR member's, say — and its extension is used as a fallback
+	/// for data with no recognizable file signature.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the name is invalid, or the data is
+	/// missing or cannot be decoded into a valid RGBA image. Data that can't
+	/// be identified as an image at all returns `PxsumError::NotImage`.
+	pub fn crunch_named(&mut self, name: &str, src: &[u8]) -> Result<(), PxsumError> {
+		self.src.truncate(0);
+		let name = name.trim();
+		if name.is_empty() || name == "-" { return Err(PxsumError::Path); }
+		self.push_path(name)?;
+		if src.is_empty() { return Err(PxsumError::NoData); }
+
+		let fmt = PxKind::try_from_magic(src)
+			.or_else(|e| PxKind::try_from_extension(name.as_bytes()).ok_or(e))
+			.map_err(|e|
+				if crate::check_extension(name.as_bytes()) { e }
+				else { PxsumError::NotImage }
+			)?
+			.refine(src);
+		let flags = self.flags();
+		let img = decode_raw(src, &self.src, fmt, flags, self.svg_size)?
+			.with_background(self.background_raw());
+		self.set_chk(img, flags);
+
+		Ok(())
+	}
+
+	/// # Crunch a Perceptual Fingerprint Given a File Path.
+	///
+	/// Same as `Checksum::crunch`, except the decoded image is used to
+	/// calculate and return a perceptual fingerprint (see
+	/// `PxImage::fingerprint`) rather than a new checksum. The path is updated,
+	/// but the checksum is not.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the path is invalid or unreadable, or the
+	/// data is missing or cannot be decoded into a valid RGBA image.
+	pub fn crunch_fingerprint<P>(&mut self, src: P) -> Result<u64, PxsumError>
+	where P: AsRef<OsStr> {
+		src.as_ref().to_str().ok_or(PxsumError::Path).and_then(|s| self.set_path(s))?;
+		let fmt = self.read_raw()?;
+
+		// Fingerprints only consider the first frame anyway.
+		let flags = self.flags() & ! (Self::ALL_FRAMES | Self::TIMING);
+		self.decode(fmt, flags).map(|img| img.fingerprint())
+	}
+
+	/// # Cross-Check Decoders.
+	///
+	/// Decode the image data read during the last `Checksum::crunch` (or
+	/// `Checksum::crunch_reader`, etc.) again with an alternate backend — if
+	/// there is one for the format — and compare the pixels against the
+	/// primary's, using the same modes. See `PxImage::cross_check` for
+	/// details.
+	///
+	/// Returns `None` if there is no alternate backend for the format.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if there is no data, or either backend fails
+	/// to decode it.
+	pub fn cross_check(&self) -> Result<Option<PxDiff>, PxsumError> {
+		if self.buf.is_empty() { return Err(PxsumError::NoData); }
+		let fmt = PxKind::try_from_magic(&self.buf).or_else(|e|
+			PxKind::try_from_extension(self.file().as_bytes()).ok_or(e)
+		)?.refine(&self.buf);
+		PxImage::cross_check(&self.buf, fmt, self.flags())
+	}
+
+	/// # Decode Against a Reference.
+	///
+	/// Decode the current source — e.g. following a failed
+	/// `Checksum::verify_existing` — along with a `reference` copy, using the
+	/// same modes for both, and normalize their alpha per the policy. The
+	/// results, ours and theirs respectively, can then be compared with
+	/// `PxImage::diff`.
+	///
+	/// If the source is a single page of a multi-image container, the same
+	/// page is pulled from the reference.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if either image cannot be read or decoded.
+	pub fn decode_against<P>(&mut self, reference: P) -> Result<(PxImage, PxImage), PxsumError>
+	where P: AsRef<Path> {
+		let flags = self.flags();
+		let fmt = self.read_raw()?;
+		let mut ours = self.decode(fmt, flags)?;
+
+		let reference = reference.as_ref();
+		let raw = std::fs::read(reference).map_err(|_| PxsumError::Read)?;
+		if raw.is_empty() { return Err(PxsumError::NoData); }
+		let fmt = PxKind::try_from_magic(&raw).or_else(|e|
+			PxKind::try_from_extension(reference.as_os_str().as_encoded_bytes()).ok_or(e)
+		)?.refine(&raw);
+		let mut theirs = decode_raw(&raw, &self.src, fmt, flags, self.svg_size)?
+			.with_background(self.background_raw());
+
+		ours.apply_alpha_policy(flags);
+		theirs.apply_alpha_policy(flags);
+		Ok((ours, theirs))
+	}
+
+	#[must_use]
 	/// # Return Checksum.
-	pub(super) const fn chk(&self) -> [u8; 32] { self.chk }
+	pub const fn chk(&self) -> [u8; 32] { self.chk }
+
+	#[must_use]
+	/// # Return Tile Digests.
+	///
+	/// For tiled checksums, return the (truncated) digests of each 64x64 tile
+	/// of each frame or page, in order. See `Checksum::TILED`.
+	pub fn tiles(&self) -> &[[u8; 8]] { &self.tiles }
+
+	#[must_use]
+	/// # Changed Regions.
+	///
+	/// If the last `Checksum::verify_existing` failed, and both it and the
+	/// re-crunched image were tiled, return the bounding boxes of the
+	/// (contiguous) tiles that changed.
+	///
+	/// This will be empty if the images aren't comparable at that level, e.g.
+	/// because their dimensions differ.
+	pub fn changed_regions(&self) -> &[TileRegion] { &self.changed }
 
+	#[must_use]
 	/// # Return Source Path.
-	pub(super) fn src(&self) -> &str { &self.src }
+	///
+	/// For individual pages of multi-image containers, this will be in
+	/// `path#index` format.
+	pub fn src(&self) -> &str { &self.src }
 
-	/// # Verify a Checksum.
+	#[must_use]
+	/// # Return Source File Path.
 	///
-	/// Replace `self` with the checksum/path pairing stored in `line`, then
-	/// recrunch the data to see if it's still a match (`true`).
+	/// Same as `Checksum::src`, but without any `#index` page suffix. (Images
+	/// inside archives keep their `archive!/entry` format.)
+	pub fn file(&self) -> &str { split_page(&self.src).0 }
+
+	#[must_use]
+	/// # Alpha Policy.
+	///
+	/// Return the alpha policy bits — `Checksum::LOOSE`, `Checksum::STRICT`,
+	/// etc. — used by the checksum.
+	pub const fn alpha_policy(&self) -> u16 { self.flags() & Self::ALPHA_POLICY }
+
+	#[must_use]
+	/// # Background Color.
+	///
+	/// Return the (RGB) background color images were composited over, if the
+	/// checksum uses the `Checksum::FLATTEN` alpha policy.
+	pub const fn background(&self) -> Option<[u8; 3]> {
+		if Self::FLATTEN == self.alpha_policy() { Some(self.background_raw()) }
+		else { None }
+	}
+
+	#[must_use]
+	/// # Checksums in Strict Mode?
+	pub const fn strict(&self) -> bool { Self::STRICT == self.alpha_policy() }
+
+	#[must_use]
+	/// # Checksums in Deep Mode?
+	pub const fn deep(&self) -> bool { Self::DEEP == self.flags() & Self::DEEP }
+
+	#[must_use]
+	/// # Checksums Color-Managed?
+	pub const fn color_managed(&self) -> bool { Self::COLOR_MANAGED == self.flags() & Self::COLOR_MANAGED }
+
+	#[must_use]
+	/// # Checksums Tiled?
+	pub const fn tiled(&self) -> bool { Self::TILED == self.flags() & Self::TILED }
+
+	#[must_use]
+	/// # Checksums Oriented?
+	pub const fn oriented(&self) -> bool { Self::ORIENTED == self.flags() & Self::ORIENTED }
+
+	#[must_use]
+	/// # Checksums Include All Frames?
+	pub const fn all_frames(&self) -> bool { Self::ALL_FRAMES == self.flags() & Self::ALL_FRAMES }
+
+	#[must_use]
+	/// # Checksums Include Frame Timing?
+	pub const fn timing(&self) -> bool { Self::TIMING == self.flags() & Self::TIMING }
+
+	#[must_use]
+	/// # Version Two Checksum?
+	///
+	/// Note: for checksums parsed from a line, this may be a false positive;
+	/// see `Checksum::V2` for details.
+	pub const fn v2(&self) -> bool { Self::V2 == self.flags() & Self::V2 }
+
+	/// # Parse a Checksum/Path Line.
+	///
+	/// Replace `self` with the checksum/path pairing stored in `line` without
+	/// verifying it. (Grouped manifests should be normalized with
+	/// `ManifestLines` first.)
 	///
 	/// ## Errors
 	///
-	/// In addition to the errors returnable by `Self::crunch`, this will
-	/// fail if the line cannot be parsed.
-	pub(super) fn verify_existing(&mut self, line: &str) -> Result<bool, PxsumError> {
+	/// This will return an error if the line cannot be parsed or the path is
+	/// invalid.
+	pub fn parse_line(&mut self, line: &str) -> Result<(), PxsumError> {
 		// Clear the current source path early in case the line is corrupt.
 		self.src.truncate(0);
+		self.tiles.truncate(0);
 
 		// Split the two parts.
 		let (a, mut b) = line.split_at_checked(64).ok_or(PxsumError::LineDecode)?;
-		b = b.strip_prefix("  ").ok_or(PxsumError::LineDecode)?;
+
+		// Tiled checksums might have some extra data.
+		if let Some(rest) = b.strip_prefix(':') {
+			let (tiles, rest) = rest.split_once("  ").ok_or(PxsumError::LineDecode)?;
+			if tiles.is_empty() || ! tiles.len().is_multiple_of(16) {
+				return Err(PxsumError::LineDecode);
+			}
+			for chunk in tiles.as_bytes().chunks_exact(16) {
+				let mut tile = [0_u8; 8];
+				faster_hex::hex_decode(chunk, tile.as_mut_slice())
+					.map_err(|_| PxsumError::LineDecode)?;
+				self.tiles.push(tile);
+			}
+			b = rest;
+		}
+		else { b = b.strip_prefix("  ").ok_or(PxsumError::LineDecode)?; }
 
 		// De-hex the checksum.
 		faster_hex::hex_decode(a.as_bytes(), self.chk.as_mut_slice())
 			.map_err(|_| PxsumError::LineDecode)?;
 
+		self.set_path(b)
+	}
+
+	/// # Verify a Checksum.
+	///
+	/// Replace `self` with the checksum/path pairing stored in `line`, then
+	/// recrunch the data to see if it's still a match (`true`).
+	///
+	/// ## Errors
+	///
+	/// In addition to the errors returnable by `Self::crunch`, this will
+	/// fail if the line cannot be parsed.
+	pub fn verify_existing(&mut self, line: &str) -> Result<bool, PxsumError> {
+		self.changed.truncate(0);
+		self.parse_line(line)?;
+
 		// Now basically do the same thing as crunch, but use the result for
 		// comparison instead of making any changes to `self`.
-		self.set_path(b)?;
 		let fmt = self.read_raw()?;
+		self.verify_raw(fmt)
+	}
+
+	/// # Verify Many Checksums.
+	///
+	/// Same as calling `Checksum::verify_existing` on each line in turn,
+	/// except lines pointing inside the same TAR archive are all checked
+	/// during a single pass through it, rather than reading (and
+	/// decompressing) the archive from the top once per line.
+	///
+	/// After each verification, the callback is passed `self` — holding
+	/// that line's state, same as `Checksum::verify_existing` would leave it —
+	/// along with the line and the result. Archived lines are reported last,
+	/// in archive order.
+	pub fn verify_existing_many<I, S, F>(&mut self, lines: I, mut cb: F)
+	where
+		I: IntoIterator<Item=S>,
+		S: AsRef<str>,
+		F: FnMut(&mut Self, &str, Result<bool, PxsumError>),
+	{
+		// Verify the regular lines straight away, setting aside the TAR
+		// entries, by archive and entry.
+		let mut tars: BTreeMap<String, BTreeMap<String, Vec<S>>> = BTreeMap::new();
+		for line in lines {
+			self.changed.truncate(0);
+			if let Err(e) = self.parse_line(line.as_ref()) {
+				cb(self, line.as_ref(), Err(e));
+				continue;
+			}
+
+			match archive::split_archive(split_page(&self.src).0) {
+				Some((archive, entry)) if archive::is_tar(archive) => {
+					tars.entry(archive.to_owned())
+						.or_default()
+						.entry(entry.to_owned())
+						.or_default()
+						.push(line);
+				},
+				_ => {
+					let res = self.read_raw().and_then(|fmt| self.verify_raw(fmt));
+					cb(self, line.as_ref(), res);
+				},
+			}
+		}
+
+		// Now run through each archive once.
+		for (archive, mut entries) in tars {
+			let res = archive::walk(&archive, |name, r| {
+				if let Some(lines) = entries.remove(name) {
+					let fmt = digest_entry(r, &mut self.buf, name);
+					for line in lines {
+						self.changed.truncate(0);
+						let res = self.parse_line(line.as_ref())
+							.and(fmt)
+							.and_then(|fmt| self.verify_raw(fmt));
+						cb(self, line.as_ref(), res);
+					}
+				}
+
+				if entries.is_empty() { ControlFlow::Break(()) }
+				else { ControlFlow::Continue(()) }
+			});
 
-		// Do we have a match?
-		let chk = PxImage::new(self.buf.as_slice(), fmt)?.into_checksum(self.strict());
-		Ok(self.chk == chk)
+			// Anything left over wasn't found.
+			let err = res.err().unwrap_or(PxsumError::Read);
+			for line in entries.into_values().flatten() {
+				self.changed.truncate(0);
+				let res = self.parse_line(line.as_ref()).and(Err(err));
+				cb(self, line.as_ref(), res);
+			}
+		}
+	}
+
+	#[must_use]
+	/// # TAR Archive.
+	///
+	/// Return the path of the TAR archive the source of a verification line
+	/// lives inside, if any, as written.
+	///
+	/// Lines sharing an archive should be verified together with
+	/// `Checksum::verify_existing_many`.
+	pub fn tar_archive(line: &str) -> Option<&str> {
+		let (_, path) = line.split_once("  ")?;
+		archive::split_archive(split_page(path.trim()).0)
+			.map(|(archive, _)| archive)
+			.filter(|archive| archive::is_tar(archive))
+	}
+
+	/// # Walk Archive.
+	///
+	/// Read each entry of the archive at `src` into the buffer — with the
+	/// source set to `archive!/entry` — and pass the result of `crunch` to
+	/// the callback, skipping anything that isn't an image.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the path is invalid or the archive itself
+	/// is unreadable.
+	fn walk_archive<T, C, F>(&mut self, src: &OsStr, mut crunch: C, mut cb: F) -> Result<(), PxsumError>
+	where
+		C: FnMut(&mut Self, PxKind) -> Result<T, PxsumError>,
+		F: FnMut(&Self, Result<T, PxsumError>),
+	{
+		let archive = src.to_str()
+			.filter(|s| crate::check_archive(s.as_bytes()))
+			.ok_or(PxsumError::Path)?;
+
+		let mut path = String::new();
+		let res = archive::walk(archive, |name, r| {
+			path.truncate(0);
+			path.push_str(archive);
+			path.push_str(archive::SEPARATOR);
+			path.push_str(name);
+
+			let res = self.set_path(&path)
+				.and_then(|()| digest_entry(r, &mut self.buf, name))
+				.and_then(|fmt| crunch(self, fmt));
+			if ! matches!(res, Err(PxsumError::NotImage)) { cb(self, res); }
+			ControlFlow::Continue(())
+		});
+
+		// Don't leave a stray entry behind if the archive itself was bad.
+		if res.is_err() { self.src.truncate(0); }
+		res
+	}
+
+	/// # Verify Raw.
+	///
+	/// Decode the source data already read into the buffer, and compare it
+	/// against the parsed checksum. See `Checksum::verify_existing`.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the data cannot be decoded.
+	fn verify_raw(&mut self, fmt: PxKind) -> Result<bool, PxsumError> {
+		// Do we have a V2 match?
+		let flags = self.flags();
+		if Self::V2 == flags & Self::V2 {
+			if let Ok(mut img) = self.decode(fmt, flags) {
+				if self.chk == img.checksum(flags) { return Ok(true); }
+
+				// Note which tiles changed, if we can, in case this doesn't
+				// pan out.
+				let changed =
+					if Self::TILED == flags & Self::TILED { img.changed_regions(&self.tiles) }
+					else { Vec::new() };
+
+				// The reference might still be a legacy checksum that just
+				// happened to have the V2 bit set. If the decoding (and
+				// alpha handling) would be the same, we can re-check without
+				// starting over.
+				if
+					0 == flags & (Self::DEEP | Self::ALL_FRAMES | Self::ORIENTED | Self::COLOR_MANAGED) &&
+					matches!(flags & Self::ALPHA_POLICY, Self::LOOSE | Self::STRICT)
+				{
+					if self.chk == img.checksum(flags & Self::STRICT) { return Ok(true); }
+					self.changed = changed;
+					return Ok(false);
+				}
+
+				self.changed = changed;
+			}
+		}
+
+		// Check it the legacy way.
+		let flags = flags & Self::STRICT;
+		let chk = self.decode(fmt, flags)?.into_checksum(flags);
+		if self.chk == chk {
+			self.changed.truncate(0);
+			Ok(true)
+		}
+		else { Ok(false) }
 	}
 
 	/// # Read Source.
 	///
-	/// This attempts to read the source — a file or STDIN — into the reusable
-	/// buffer.
+	/// This attempts to read the source — a file, archive entry, or STDIN —
+	/// into the reusable buffer.
 	///
 	/// ## Errors
 	///
 	/// This will return an error if the data cannot be read or winds up empty,
-	/// but does not otherwise validate the raw bytes.
+	/// or has no recognizable file signature. (See `Checksum::with_sniff` for
+	/// the sniffing-specific errors.)
 	fn read_raw(&mut self) -> Result<PxKind, PxsumError> {
-		use std::io::Read;
-
-		#[inline]
-		/// # Digest Reader.
-		///
-		/// The STDIN and path-based reads differ in setup, but finish the same
-		/// way. This method helps remove all that trailing redundancy.
-		fn digest_reader<R: Read>(r: &mut R, buf: &mut Vec<u8>)
-		-> Result<PxKind, PxsumError> {
-			// Read just enough to guess the image format; if we can't do this
-			// much there's no point in continuing!
-			buf.resize(16_usize, 0_u8);
-			r.read_exact(buf.as_mut_slice()).map_err(|_| PxsumError::Read)?;
-			let fmt = PxKind::try_from_magic(buf.as_slice())?;
-
-			// Finish the job!
-			r.read_to_end(buf).map_err(|_| PxsumError::Read)?;
-			Ok(fmt)
-		}
-
 		// Read from STDIN.
 		if self.stdin() {
-			crate::stdin().and_then(|mut r| digest_reader(&mut r, &mut self.buf))
+			crate::claim_stdin().and_then(|mut r| digest_reader(&mut r, &mut self.buf, None, 0))
+		}
+		// Read from an archive.
+		else if let Some((archive, entry)) = archive::split_archive(split_page(&self.src).0) {
+			let buf = &mut self.buf;
+			archive::find(archive, entry, |name, r| digest_entry(r, buf, name))?
+				.unwrap_or(Err(PxsumError::Read))
 		}
 		// Read from file.
 		else {
 			// Open the file and obtain its size.
-			let file = File::open(self.src.as_str()).map_err(|_| PxsumError::Read)?;
+			let mut file = File::open(self.file()).map_err(|_| PxsumError::Read)?;
 			let meta = file.metadata().map_err(|_| PxsumError::Read)?;
 			let len = usize::try_from(meta.len()).map_err(|_| PxsumError::Read)?;
 
 			// Easy errors.
+			if self.sniff && ! meta.is_file() { return Err(PxsumError::Path); }
 			if len == 0 { return Err(PxsumError::NoData); }
-			else if len < 16 { return Err(PxsumError::Decode); }
+			let res =
+				if len < 16 { Err(PxsumError::Decode) }
+				else {
+					let fallback = PxKind::try_from_extension(self.file().as_bytes());
+					digest_reader(&mut file, &mut self.buf, fallback, len)
+				};
 
-			// Reserve and read!
-			if let Some(diff) = len.checked_sub(self.buf.capacity()) {
-				self.buf.try_reserve_exact(diff).map_err(|_| PxsumError::Read)?;
-			}
-			digest_reader(&mut BufReader::new(file), &mut self.buf)
+			// Sniffed paths that don't pan out aren't images, just files.
+			res.map_err(|e|
+				if
+					self.sniff &&
+					matches!(e, PxsumError::Decode) &&
+					! crate::check_extension(self.file().as_bytes())
+				{
+					PxsumError::NotImage
+				}
+				else { e }
+			)
 		}
 	}
 
@@ -211,11 +968,30 @@ impl Checksum {
 			return Ok(());
 		}
 
-		// Easy abort: unsupported extension.
-		if ! crate::check_extension(path.as_bytes()) {
+		// Easy abort: unsupported extension. (Archive entries are sorted out
+		// when read.)
+		if
+			! self.sniff &&
+			! crate::check_extension(split_page(path).0.as_bytes()) &&
+			archive::split_archive(path).is_none()
+		{
 			return Err(PxsumError::Path);
 		}
 
+		self.push_path(path)
+	}
+
+	/// # Push Path.
+	///
+	/// Write the (trimmed, non-STDIN) path to the empty `self.src`, lightly
+	/// normalizing it in the process, without regard for its extension. See
+	/// `Checksum::set_path` for details.
+	///
+	/// ## Errors
+	///
+	/// If the path contains invalid UTF-8 sequences, Windows bullshit, or does
+	/// not end with a "normal" component, an error will be returned instead.
+	fn push_path(&mut self, path: &str) -> Result<(), PxsumError> {
 		// If the path has no directory at the start, add one for consistency.
 		// The "last" variable will be called into use a little further on…
 		let mut last =
@@ -268,11 +1044,142 @@ impl Checksum {
 }
 
 impl Checksum {
+	/// # Flags.
+	///
+	/// Return the mode bits encoded in the first two bytes of the checksum.
+	/// (For legacy checksums, this is just the strictness.)
+	const fn flags(&self) -> u16 {
+		let flags = u16::from_le_bytes([self.chk[0], self.chk[1]]);
+		if Self::V2 == flags & Self::V2 && self.v2_consistent(flags) { flags & Self::FLAGS }
+		else { flags & Self::STRICT }
+	}
+
+	/// # Consistent V2 Checksum?
+	///
+	/// Legacy checksums are random save for the strict bit, so about half of
+	/// them will have the V2 bit set too. Real V2 checksums, however, never
+	/// set undefined mode bits or alpha policies, and leave the background
+	/// bytes zeroed unless flattened.
+	const fn v2_consistent(&self, flags: u16) -> bool {
+		0 == flags & ! Self::FLAGS &&
+		match flags & Self::ALPHA_POLICY {
+			Self::FLATTEN => true,
+			Self::LOOSE | Self::STRICT | Self::PREMULTIPLIED | Self::IGNORE_ALPHA =>
+				0 == self.chk[2] && 0 == self.chk[3] && 0 == self.chk[4],
+			_ => false,
+		}
+	}
+
+	/// # Set Checksum.
+	///
+	/// Checksum the image, saving the result and any tile digests to `self`.
+	fn set_chk(&mut self, mut img: PxImage, flags: u16) {
+		self.chk = img.checksum(flags);
+		self.tiles.truncate(0);
+		self.tiles.extend_from_slice(img.tiles());
+	}
+
+	/// # Background Color (Raw).
+	///
+	/// Return the bytes that would hold the background color for the
+	/// `Checksum::FLATTEN` alpha policy, whether or not it is in use.
+	const fn background_raw(&self) -> [u8; 3] {
+		[self.chk[2], self.chk[3], self.chk[4]]
+	}
+
+	/// # Decode Image.
+	///
+	/// Decode the raw image data in the buffer, or the specific page of it
+	/// if the source has a `path#index` suffix.
+	fn decode(&self, fmt: PxKind, flags: u16) -> Result<PxImage, PxsumError> {
+		decode_raw(self.buf.as_slice(), &self.src, fmt, flags, self.svg_size)
+			.map(|img| img.with_background(self.background_raw()))
+	}
+
 	/// # Source is STDIN?
 	fn stdin(&self) -> bool { self.src.is_empty() || self.src == "-" }
+}
 
-	/// # Checksums in Strict Mode?
-	const fn strict(&self) -> bool { Self::STRICT == self.chk[0] & Self::STRICT }
+
+
+/// # Decode Raw Image.
+///
+/// Decode the raw image data, or the specific page of it if the source has a
+/// `path#index` suffix, or — for SVGs — render it at the specific size, if
+/// any.
+fn decode_raw(raw: &[u8], src: &str, fmt: PxKind, flags: u16, svg_size: [u32; 2])
+-> Result<PxImage, PxsumError> {
+	if matches!(fmt, PxKind::Svg) && svg_size != [0, 0] {
+		return PxImage::new_svg(raw, flags, svg_size);
+	}
+
+	split_page(src).1.map_or_else(
+		|| PxImage::new(raw, fmt, flags),
+		|page| PxImage::new_page(raw, fmt, flags, page),
+	)
+}
+
+/// # Split Page.
+///
+/// Split a `path#index` source into its file path and page index, if any.
+/// (The suffix only counts if it is numeric and the rest of the path has a
+/// supported image extension.)
+fn split_page(src: &str) -> (&str, Option<usize>) {
+	if let Some((path, page)) = src.rsplit_once('#') {
+		if
+			! page.is_empty() &&
+			page.bytes().all(|b| b.is_ascii_digit()) &&
+			crate::check_extension(path.as_bytes())
+		{
+			if let Ok(page) = page.parse::<usize>() { return (path, Some(page)); }
+		}
+	}
+
+	(src, None)
+}
+
+#[inline]
+/// # Digest Reader.
+///
+/// The STDIN, path, and generic reader-based reads differ in setup, but finish
+/// the same way. This method helps remove all that trailing redundancy.
+///
+/// The `fallback` kind, if any, is used when the data has no recognizable
+/// signature. (See `PxKind::try_from_extension`.) The `len`, if known, is
+/// used to size the buffer up front once the signature checks out.
+fn digest_reader<R: Read + ?Sized>(r: &mut R, buf: &mut Vec<u8>, fallback: Option<PxKind>, len: usize)
+-> Result<PxKind, PxsumError> {
+	// Read just enough to guess the image format; if we can't do this
+	// much there's no point in continuing!
+	buf.resize(16_usize, 0_u8);
+	r.read_exact(buf.as_mut_slice()).map_err(|_| PxsumError::Read)?;
+	let fmt = PxKind::try_from_magic(buf.as_slice())
+		.or_else(|e| fallback.ok_or(e))?;
+
+	// Reserve and finish the job!
+	if let Some(diff) = len.checked_sub(buf.len()) {
+		buf.try_reserve_exact(diff).map_err(|_| PxsumError::Read)?;
+	}
+	r.read_to_end(buf).map_err(|_| PxsumError::Read)?;
+	Ok(fmt.refine(buf))
+}
+
+/// # Digest Archive Entry.
+///
+/// Same as `digest_reader`, but for an archive entry, with the fallback kind
+/// taken from its name. Entries that can't be identified as images return
+/// `PxsumError::NotImage` instead.
+fn digest_entry(r: &mut dyn Read, buf: &mut Vec<u8>, name: &str) -> Result<PxKind, PxsumError> {
+	let fallback = PxKind::try_from_extension(name.as_bytes());
+	digest_reader(r, buf, fallback, 0).map_err(|e|
+		if
+			matches!(e, PxsumError::Decode | PxsumError::Read) &&
+			! crate::check_extension(name.as_bytes())
+		{
+			PxsumError::NotImage
+		}
+		else { e }
+	)
 }
 
 
@@ -290,11 +1197,16 @@ mod test {
 			panic!("Unable to read {path}.");
 		};
 
-		let mut chk = Checksum::new(false);
+		let mut chk = Checksum::new(0);
 		for line in list.lines() {
 			let line = line.trim();
 			if line.is_empty() { continue; }
 
+			// Skip formats that aren't enabled.
+			if line.rsplit_once("  ").is_some_and(|(_, p)| ! crate::check_extension(p.as_bytes())) {
+				continue;
+			}
+
 			// We need to adjust the paths.
 			let new_line = line.replace("  ./assets/", "  ./skel/assets/");
 			assert_eq!(
@@ -324,8 +1236,7 @@ mod test {
 
 		// Collected via iterator.
 		let manual: Vec<u8> = img.pixels()
-			.map(|p| p.channels())
-			.flatten()
+			.flat_map(Pixel::channels)
 			.copied()
 			.collect::<Vec<u8>>();
 
@@ -336,6 +1247,382 @@ mod test {
 		assert_eq!(manual, automatic);
 	}
 
+	#[test]
+	/// # Test Crunch Sources.
+	///
+	/// Paths, readers, and byte slices should all produce the same checksum.
+	fn t_crunch_sources() {
+		for flags in [0, Checksum::STRICT, Checksum::DEEP] {
+			let mut chk = Checksum::new(flags);
+			chk.crunch("skel/assets/poe.png").expect("Path crunch failed.");
+			let expected = chk.chk();
+			assert_eq!(chk.src(), "./skel/assets/poe.png");
+
+			let file = File::open("skel/assets/poe.png").expect("Unable to open poe.png.");
+			chk.crunch_reader(file).expect("Reader crunch failed.");
+			assert_eq!(chk.chk(), expected, "Reader checksum mismatch.");
+			assert_eq!(chk.src(), "-");
+
+			let raw = std::fs::read("skel/assets/poe.png").expect("Unable to read poe.png.");
+			chk.crunch_bytes(&raw).expect("Byte crunch failed.");
+			assert_eq!(chk.chk(), expected, "Byte checksum mismatch.");
+
+			// Empty is empty.
+			assert_eq!(chk.crunch_bytes(&[]), Err(PxsumError::NoData));
+		}
+	}
+
+	#[test]
+	/// # Test SVG Size.
+	///
+	/// The render size should be respected no matter where the data comes
+	/// from.
+	fn t_crunch_svg_size() {
+		const SVG: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 4 4"><circle cx="2" cy="2" r="2"/></svg>"#;
+
+		let mut chk = Checksum::new(0);
+		chk.crunch_bytes(SVG).expect("Byte crunch failed.");
+		let intrinsic = chk.chk();
+
+		let mut chk = chk.with_svg_size([64, 0]);
+		chk.crunch_named("circle.svg", SVG).expect("Named crunch failed.");
+		let expected = chk.chk();
+		assert_ne!(expected, intrinsic, "SVG size was ignored.");
+
+		chk.crunch_bytes(SVG).expect("Byte crunch failed.");
+		assert_eq!(chk.chk(), expected, "Byte crunch ignored the SVG size.");
+
+		chk.crunch_reader(SVG).expect("Reader crunch failed.");
+		assert_eq!(chk.chk(), expected, "Reader crunch ignored the SVG size.");
+	}
+
+	#[test]
+	/// # Test Crunch/Verify Round Trip.
+	///
+	/// Freshly-crunched checksums should be V2 and verify as such.
+	fn t_roundtrip() {
+		for flags in [
+			0,
+			Checksum::STRICT,
+			Checksum::DEEP,
+			Checksum::STRICT | Checksum::DEEP,
+			Checksum::COLOR_MANAGED,
+			Checksum::PREMULTIPLIED,
+			Checksum::IGNORE_ALPHA | Checksum::DEEP,
+			Checksum::FLATTEN,
+		] {
+			let mut chk = Checksum::new(flags).with_background([255, 128, 0]);
+			chk.crunch("skel/assets/statler.png").expect("Crunch failed.");
+			assert!(chk.v2(), "Missing V2 flag.");
+			assert_eq!(chk.alpha_policy(), flags & Checksum::ALPHA_POLICY, "Wrong alpha policy.");
+			assert_eq!(
+				chk.background(),
+				(Checksum::FLATTEN == flags & Checksum::ALPHA_POLICY).then_some([255, 128, 0]),
+				"Wrong background.",
+			);
+			assert_eq!(chk.strict(), Checksum::STRICT == flags & Checksum::ALPHA_POLICY, "Wrong strictness.");
+			assert_eq!(chk.deep(), 0 != flags & Checksum::DEEP, "Wrong depth.");
+			assert_eq!(chk.color_managed(), 0 != flags & Checksum::COLOR_MANAGED, "Wrong color management.");
+
+			let line = chk.to_string();
+			let mut chk2 = Checksum::new(! flags);
+			assert_eq!(chk2.verify_existing(&line), Ok(true), "Round trip failed: {line}");
+			assert_eq!(chk.chk(), chk2.chk(), "Round trip mismatch.");
+		}
+	}
+
+	#[test]
+	/// # Test Tiled Checksums.
+	///
+	/// Tile digests should survive a manifest round trip, and verification
+	/// failures should report which regions changed.
+	fn t_tiled() {
+		use image::{
+			DynamicImage,
+			ImageFormat,
+			RgbaImage,
+		};
+
+		// Write a 200x150 (4x3 tile) image to a temporary file.
+		let path = std::env::temp_dir().join(format!("pxsum-tiled-{}.png", std::process::id()));
+		let save = |img: &RgbaImage| {
+			DynamicImage::ImageRgba8(img.clone()).save_with_format(&path, ImageFormat::Png)
+				.expect("PNG encoding failed.");
+		};
+		let mut img = RgbaImage::from_fn(200, 150, |x, y| {
+			image::Rgba([u8::try_from(x).unwrap(), u8::try_from(y).unwrap(), 128, 255])
+		});
+		save(&img);
+
+		let mut chk = Checksum::new(Checksum::TILED);
+		chk.crunch(&path).expect("Crunch failed.");
+		assert!(chk.tiled(), "Missing tiled flag.");
+		assert_eq!(chk.tiles().len(), 12, "Wrong tile count.");
+		let line = chk.to_string();
+		assert_eq!(line.as_bytes()[64], b':', "Missing tiles.");
+
+		// Parse it back.
+		let mut chk2 = Checksum::new(0);
+		chk2.parse_line(&line).expect("Parse failed.");
+		assert_eq!(chk2.tiles(), chk.tiles(), "Tile mismatch.");
+		assert_eq!(chk2.to_string(), line, "Line mismatch.");
+		assert_eq!(chk2.verify_existing(&line), Ok(true), "Verification failed.");
+		assert!(chk2.changed_regions().is_empty(), "Nothing should have changed.");
+
+		// The result should differ from a regular V2 checksum.
+		let mut chk3 = Checksum::new(0);
+		chk3.crunch(&path).expect("Crunch failed.");
+		assert_ne!(chk.chk(), chk3.chk(), "Tiled and untiled checksums should differ.");
+		assert!(chk3.tiles().is_empty(), "Untiled checksums have no tiles.");
+
+		// Change a few pixels.
+		img.put_pixel(100, 70, image::Rgba([0, 0, 0, 255]));
+		img.put_pixel(130, 70, image::Rgba([0, 0, 0, 255]));
+		img.put_pixel(199, 149, image::Rgba([0, 0, 0, 255]));
+		save(&img);
+		assert_eq!(chk2.verify_existing(&line), Ok(false), "Verification should have failed.");
+		let changed: Vec<String> = chk2.changed_regions().iter()
+			.map(ToString::to_string)
+			.collect();
+		let _res = std::fs::remove_file(&path);
+		assert_eq!(changed, ["128x64+64+64", "8x22+192+128"], "Wrong regions.");
+
+		// Malformed tiles.
+		for bad in [
+			format!("{}:  ./a.png", &line[..64]),
+			format!("{}:0123  ./a.png", &line[..64]),
+			format!("{}:0123456789abcdeg  ./a.png", &line[..64]),
+		] {
+			assert_eq!(chk2.parse_line(&bad), Err(PxsumError::LineDecode), "Bad tiles accepted.");
+		}
+	}
+
+	#[test]
+	/// # Test Sniffing.
+	///
+	/// With sniffing enabled, extension-less images should checksum the same
+	/// as their properly-named counterparts, while non-images are rejected.
+	fn t_sniff() {
+		let dir = std::env::temp_dir();
+		let image = dir.join(format!("pxsum-sniff-{}", std::process::id()));
+		let text = dir.join(format!("pxsum-sniff-{}.tmp", std::process::id()));
+		std::fs::copy("skel/assets/poe.png", &image).expect("Copy failed.");
+		std::fs::write(&text, "This is just a regular old text file.").expect("Write failed.");
+
+		let mut chk = Checksum::new(0);
+		chk.crunch("skel/assets/poe.png").expect("Crunch failed.");
+		let expected = chk.chk();
+
+		// Without sniffing, neither path is allowed.
+		assert_eq!(chk.crunch(&image), Err(PxsumError::Path));
+		assert_eq!(chk.crunch(&text), Err(PxsumError::Path));
+
+		// With it, the image is an image and the text is not.
+		let mut chk = chk.with_sniff(true);
+		let res = chk.crunch(&image).map(|()| chk.chk());
+		let res2 = chk.crunch(&text);
+		let _res = std::fs::remove_file(&image);
+		let _res = std::fs::remove_file(&text);
+		assert_eq!(res, Ok(expected), "Sniffed checksum mismatch.");
+		assert_eq!(res2, Err(PxsumError::NotImage));
+
+		// Directories aren't regular files.
+		assert_eq!(chk.crunch("skel/assets"), Err(PxsumError::Path));
+	}
+
+	#[test]
+	/// # Test Page Paths.
+	///
+	/// Sub-images can be referenced using `path#index` notation.
+	fn t_pages() {
+		for (src, expected) in [
+			("a.tif#2", ("a.tif", Some(2))),
+			("a.ico#0", ("a.ico", Some(0))),
+			("a.tif", ("a.tif", None)),
+			("a.tif#", ("a.tif#", None)),
+			("a.tif#x", ("a.tif#x", None)),
+			("a#2.tif", ("a#2.tif", None)),
+			("a.txt#2", ("a.txt#2", None)),
+		] {
+			assert_eq!(split_page(src), expected, "Split page failed: {src}");
+		}
+
+		// The icon only has the one entry, so should match itself.
+		let mut chk = Checksum::new(Checksum::ALL_FRAMES);
+		chk.crunch("skel/assets/santo.ico").expect("Crunch failed.");
+		let expected = chk.chk();
+
+		chk.crunch("skel/assets/santo.ico#0").expect("Page crunch failed.");
+		assert_eq!(chk.src(), "./skel/assets/santo.ico#0");
+		assert_eq!(chk.file(), "./skel/assets/santo.ico");
+		assert_eq!(chk.chk(), expected, "Page checksum mismatch.");
+
+		let line = chk.to_string();
+		assert_eq!(Checksum::new(0).verify_existing(&line), Ok(true), "Page verification failed.");
+		assert_eq!(chk.crunch("skel/assets/santo.ico#1"), Err(PxsumError::Decode));
+
+		// Regular images only trigger the callback once.
+		let mut seen = Vec::new();
+		chk.crunch_pages("skel/assets/santo.ico", |c| seen.push(c.to_string()))
+			.expect("Page crunch failed.");
+		assert_eq!(seen.len(), 1, "Wrong page count.");
+		assert!(seen[0].ends_with("  ./skel/assets/santo.ico"), "Wrong page path.");
+	}
+
+	#[test]
+	/// # Test Archives.
+	///
+	/// Images inside archives are checksummed as `archive!/entry`, and should
+	/// match their loose counterparts.
+	fn t_archive() {
+		let mut chk = Checksum::new(Checksum::ALL_FRAMES);
+		let expected: Vec<[u8; 32]> = ["down_arrow.gif", "santo.bmp", "ash.jpg"].into_iter()
+			.map(|f| {
+				chk.crunch(format!("skel/assets/{f}")).expect("Crunch failed.");
+				chk.chk()
+			})
+			.collect();
+
+		for archive in ["skel/archives/bundle.zip", "skel/archives/bundle.tar.gz"] {
+			let mut seen = Vec::new();
+			chk.crunch_archive(archive, |c, res| {
+				assert_eq!(res, Ok(()), "Entry crunch failed: {}", c.src());
+				seen.push((c.src().to_owned(), c.chk(), c.to_string()));
+			}).expect("Archive crunch failed.");
+
+			// The README should have been skipped.
+			assert_eq!(
+				seen.iter().map(|(src, _, _)| src.as_str()).collect::<Vec<_>>(),
+				[
+					format!("./{archive}!/icons/down_arrow.gif"),
+					format!("./{archive}!/icons/santo.bmp"),
+					format!("./{archive}!/photos/ash"),
+				],
+			);
+			for ((src, chk, _), expected) in seen.iter().zip(&expected) {
+				assert_eq!(chk, expected, "Checksum mismatch for {src}.");
+			}
+
+			// The lines should verify.
+			for (_, _, line) in &seen {
+				assert_eq!(Checksum::new(0).verify_existing(line), Ok(true), "Verification failed: {line}");
+			}
+
+			// Unless the entry doesn't exist.
+			let line = seen[0].2.replace("down_arrow.gif", "up_arrow.gif");
+			assert_eq!(Checksum::new(0).verify_existing(&line), Err(PxsumError::Read));
+		}
+
+		// Fingerprints should match too.
+		let expected: Vec<u64> = ["down_arrow.gif", "santo.bmp", "ash.jpg"].into_iter()
+			.map(|f| chk.crunch_fingerprint(format!("skel/assets/{f}")).expect("Fingerprint failed."))
+			.collect();
+		for archive in ["skel/archives/bundle.zip", "skel/archives/bundle.tar.gz"] {
+			let mut seen = Vec::new();
+			chk.crunch_archive_fingerprints(archive, |c, res| {
+				let fp = res.unwrap_or_else(|e| panic!("Entry fingerprint failed: {} {e}", c.src()));
+				seen.push(fp);
+			}).expect("Archive fingerprint failed.");
+			assert_eq!(seen, expected, "Fingerprint mismatch for {archive}.");
+		}
+
+		// Only archives are archives.
+		assert_eq!(chk.crunch_archive("skel/assets/poe.png", |_, _| {}), Err(PxsumError::Path));
+		assert_eq!(
+			chk.crunch_archive_fingerprints("skel/assets/poe.png", |_, _| {}),
+			Err(PxsumError::Path),
+		);
+	}
+
+	#[test]
+	/// # Test Batch Verification.
+	///
+	/// Lines from all over — TARs, ZIPs, and the regular filesystem — should
+	/// verify the same way together as they do apart.
+	fn t_verify_many() {
+		let mut lines = Vec::new();
+		let mut chk = Checksum::new(0);
+		for archive in ["skel/archives/bundle.zip", "skel/archives/bundle.tar.gz"] {
+			chk.crunch_archive(archive, |c, _| { lines.push(c.to_string()); })
+				.expect("Archive crunch failed.");
+		}
+		chk.crunch("skel/assets/poe.png").expect("Crunch failed.");
+		lines.push(chk.to_string());
+
+		// Only the TAR lines can be grouped.
+		let tars = lines.iter().filter_map(|l| Checksum::tar_archive(l)).collect::<Vec<_>>();
+		assert_eq!(tars, ["./skel/archives/bundle.tar.gz"; 3]);
+
+		// Add a duplicate, a missing entry, a missing archive, and some junk.
+		let santo = lines[4].clone();
+		assert!(santo.ends_with("bundle.tar.gz!/icons/santo.bmp"), "Wrong line: {santo}");
+		lines.insert(0, santo.clone());
+		lines.push(santo.replace("santo.bmp", "sancho.bmp"));
+		lines.push(santo.replace("bundle.tar.gz", "bungle.tar.gz"));
+		lines.push("nope".to_owned());
+
+		let mut results = Vec::new();
+		chk.verify_existing_many(&lines, |c, line, res| {
+			results.push((line.to_owned(), c.src().to_owned(), res));
+		});
+		assert_eq!(results.len(), lines.len(), "Wrong result count.");
+
+		let mut failed = 0;
+		for (line, src, res) in results {
+			// The state should match the line.
+			if res != Err(PxsumError::LineDecode) {
+				assert!(line.ends_with(&src), "State mismatch: {src} vs {line}");
+			}
+			if res == Ok(true) {
+				assert_eq!(Checksum::new(0).verify_existing(&line), Ok(true));
+			}
+			else { failed += 1; }
+		}
+		assert_eq!(failed, 3, "Wrong failure count.");
+
+		// The failures should fail the same way on their own.
+		let mut chk = Checksum::new(0);
+		chk.verify_existing_many(&lines[lines.len() - 3..], |c, line, res| {
+			assert_eq!(res, Checksum::new(0).verify_existing(line), "Result mismatch for {line}.");
+			assert!(! c.src().is_empty() || res == Err(PxsumError::LineDecode));
+		});
+	}
+
+	#[test]
+	/// # Test Claimed STDIN.
+	///
+	/// Once STDIN has been claimed — by a CLI reading a manifest from it, say —
+	/// checksums with a "-" source should fail straight away instead of
+	/// waiting on the lock.
+	fn t_verify_stdin_claimed() {
+		let _lock = crate::claim_stdin();
+		let mut chk = Checksum::new(0);
+		let line = format!("{}  -", "0".repeat(64));
+		assert_eq!(chk.verify_existing(&line), Err(PxsumError::Stdin));
+		assert_eq!(chk.crunch("-"), Err(PxsumError::Stdin));
+		assert!(crate::claim_stdin().is_err(), "STDIN was claimed twice.");
+	}
+
+	#[test]
+	/// # Test Named Bytes.
+	fn t_named() {
+		let mut chk = Checksum::new(0);
+		chk.crunch("skel/assets/ash.jpg").expect("Crunch failed.");
+		let expected = chk.chk();
+
+		let raw = std::fs::read("skel/assets/ash.jpg").expect("Unable to read image.");
+		for name in ["photos/ash.jpg", "photos/ash"] {
+			chk.crunch_named(name, &raw).expect("Named crunch failed.");
+			assert_eq!(chk.src(), format!("./{name}"));
+			assert_eq!(chk.chk(), expected, "Named checksum mismatch.");
+		}
+
+		assert_eq!(chk.crunch_named("README.txt", b"This is just a regular old text file."), Err(PxsumError::NotImage));
+		assert_eq!(chk.crunch_named("broken.jpg", b"This is just a regular old text file."), Err(PxsumError::Decode));
+		assert_eq!(chk.crunch_named("empty.jpg", &[]), Err(PxsumError::NoData));
+		assert_eq!(chk.crunch_named("", &raw), Err(PxsumError::Path));
+	}
+
 	#[test]
 	/// # Check Loose Checksums.
 	fn t_check() { run_check("skel/loose.chk"); }
@@ -343,4 +1630,28 @@ mod test {
 	#[test]
 	/// # Check Strict Checksums.
 	fn t_check_strict() { run_check("skel/strict.chk"); }
+
+	#[test]
+	/// # Legacy Checksums With the V2 Bit.
+	fn t_check_legacy_v2_bit() {
+		let mut chk = Checksum::new(0);
+		for line in [
+			"fc6e48e935f7b7330cb6bc95c8c725f57e8d9b1efe01b7afc90ea53e9d968aa9  ./skel/assets/ash.jpg",
+			"a4b3857f293b09317a9cfac0c89ae6288d2de12a99c396281a12b5b325fab7ea  ./skel/assets/down_arrow.gif",
+		] {
+			assert_eq!(chk.verify_existing(line), Ok(true), "Verification line failed: {line}");
+			assert_eq!(0x80, chk.chk()[0] & 0x80, "Byte zero should have the V2 bit set.");
+			assert!(! chk.v2(), "Legacy checksum mistaken for V2: {line}");
+			assert_eq!(chk.flags(), 0, "Legacy loose checksum has the wrong flags: {line}");
+		}
+
+		// Our own V2 checksums should always pass muster.
+		for flags in [0, Checksum::STRICT, Checksum::IGNORE_ALPHA, Checksum::DEFAULT | Checksum::DEEP | Checksum::TILED] {
+			let mut chk = Checksum::new(flags);
+			chk.crunch("skel/assets/down_arrow.gif").expect("Crunch failed.");
+			assert!(chk.v2(), "V2 checksum mistaken for legacy.");
+			assert_eq!(chk.chk()[1] & 0b1111_1000, 0, "Undefined bits were set.");
+			assert_eq!(chk.background_raw(), [0; 3], "Background bytes were set.");
+		}
+	}
 }
@@ -0,0 +1,251 @@
+/*!
+# pxsum: Archives.
+*/
+
+use crate::PxsumError;
+use flate2::read::GzDecoder;
+use std::{
+	fs::File,
+	io::{
+		BufReader,
+		Read,
+	},
+	ops::ControlFlow,
+};
+use zip::ZipArchive;
+
+
+
+/// # Entry Separator.
+///
+/// Images inside archives are recorded as `archive.zip!/inner/path.png`.
+pub(super) const SEPARATOR: &str = "!/";
+
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// # Archive Kind.
+enum ArchiveKind {
+	/// # Plain TAR.
+	Tar,
+
+	/// # Gzipped TAR.
+	TarGz,
+
+	/// # ZIP.
+	Zip,
+}
+
+impl ArchiveKind {
+	/// # From Path.
+	///
+	/// Identify the archive kind by the path's (case-insensitive) extension.
+	fn from_path(path: &[u8]) -> Option<Self> {
+		let ends_with = |ext: &[u8]|
+			path.len() > ext.len() &&
+			path[path.len() - ext.len()..].eq_ignore_ascii_case(ext);
+
+		if ends_with(b".zip") { Some(Self::Zip) }
+		else if ends_with(b".tar") { Some(Self::Tar) }
+		else if ends_with(b".tar.gz") || ends_with(b".tgz") { Some(Self::TarGz) }
+		else { None }
+	}
+}
+
+
+
+#[must_use]
+/// # Check Archive Extension.
+///
+/// Returns `true` if the path has a ZIP or (optionally gzipped) TAR archive
+/// extension — `.zip`, `.tar`, `.tar.gz`, or `.tgz` — whose images can be
+/// checksummed with `Checksum::crunch_archive`.
+pub fn check_archive(path: &[u8]) -> bool { ArchiveKind::from_path(path).is_some() }
+
+/// # Split Archive Path.
+///
+/// Split an `archive!/entry` source into its archive and entry paths, if it
+/// is one. (The archive part must have a supported archive extension.)
+pub(super) fn split_archive(src: &str) -> Option<(&str, &str)> {
+	src.match_indices(SEPARATOR).find_map(|(pos, _)| {
+		let (archive, entry) = (&src[..pos], &src[pos + SEPARATOR.len()..]);
+		if ! entry.is_empty() && check_archive(archive.as_bytes()) { Some((archive, entry)) }
+		else { None }
+	})
+}
+
+/// # Is TAR?
+///
+/// Returns `true` if the path has an (optionally gzipped) TAR extension.
+///
+/// Unlike ZIPs, TARs have no index, so finding any one entry means reading
+/// (and decompressing) everything before it.
+pub(super) fn is_tar(path: &str) -> bool {
+	matches!(
+		ArchiveKind::from_path(path.as_bytes()),
+		Some(ArchiveKind::Tar | ArchiveKind::TarGz),
+	)
+}
+
+/// # Find Entry.
+///
+/// Run the callback for the regular file named `entry` in the archive at
+/// `path`, returning its result, or `None` if there is no such entry.
+///
+/// ZIP entries are looked up directly by name — only falling back to a
+/// search if the name was recorded with cruft that has since been cleaned
+/// away — but TARs have to be walked until the entry turns up.
+///
+/// ## Errors
+///
+/// This will return an error if the path isn't an archive or the archive
+/// itself cannot be read.
+pub(super) fn find<F, T>(path: &str, entry: &str, cb: F) -> Result<Option<T>, PxsumError>
+where F: FnOnce(&str, &mut dyn Read) -> T {
+	let kind = ArchiveKind::from_path(path.as_bytes()).ok_or(PxsumError::Path)?;
+	let file = File::open(path).map(BufReader::new).map_err(|_| PxsumError::Read)?;
+
+	let mut cb = Some(cb);
+	let mut out = None;
+	let mut search = |name: &str, r: &mut dyn Read|
+		if name == entry {
+			out = cb.take().map(|cb| cb(name, r));
+			ControlFlow::Break(())
+		}
+		else { ControlFlow::Continue(()) };
+
+	match kind {
+		ArchiveKind::Tar => walk_tar(file, search)?,
+		ArchiveKind::TarGz => walk_tar(GzDecoder::new(file), search)?,
+		ArchiveKind::Zip => {
+			let mut zip = ZipArchive::new(file).map_err(|_| PxsumError::Decode)?;
+			let hit = match zip.by_name(entry) {
+				Ok(mut r) if r.is_file() => search(entry, &mut r).is_break(),
+				_ => false,
+			};
+			if ! hit { walk_zip(&mut zip, search); }
+		},
+	}
+
+	Ok(out)
+}
+
+/// # Walk Archive.
+///
+/// Run the callback for each regular file in the archive at `path`, in
+/// order, passing its (cleaned) name and a reader for its contents, until it
+/// breaks.
+///
+/// Entries whose names aren't valid UTF-8, or whose contents can't be read —
+/// encrypted ZIP members, etc. — are skipped.
+///
+/// ## Errors
+///
+/// This will return an error if the path isn't an archive or the archive
+/// itself cannot be read.
+pub(super) fn walk<F>(path: &str, cb: F) -> Result<(), PxsumError>
+where F: FnMut(&str, &mut dyn Read) -> ControlFlow<()> {
+	let kind = ArchiveKind::from_path(path.as_bytes()).ok_or(PxsumError::Path)?;
+	let file = File::open(path).map(BufReader::new).map_err(|_| PxsumError::Read)?;
+	match kind {
+		ArchiveKind::Tar => walk_tar(file, cb),
+		ArchiveKind::TarGz => walk_tar(GzDecoder::new(file), cb),
+		ArchiveKind::Zip => {
+			let mut zip = ZipArchive::new(file).map_err(|_| PxsumError::Decode)?;
+			walk_zip(&mut zip, cb);
+			Ok(())
+		},
+	}
+}
+
+/// # Walk TAR.
+///
+/// Same as `walk`, but for an uncompressed TAR stream.
+///
+/// ## Errors
+///
+/// This will return an error if the stream cannot be read as a TAR.
+pub(super) fn walk_tar<R, F>(src: R, mut cb: F) -> Result<(), PxsumError>
+where R: Read, F: FnMut(&str, &mut dyn Read) -> ControlFlow<()> {
+	let mut tar = tar::Archive::new(src);
+	for entry in tar.entries().map_err(|_| PxsumError::Decode)? {
+		let mut entry = entry.map_err(|_| PxsumError::Decode)?;
+		if ! entry.header().entry_type().is_file() { continue; }
+		let Some(name) = std::str::from_utf8(&entry.path_bytes()).ok().and_then(clean_name)
+		else { continue; };
+		if cb(&name, &mut entry).is_break() { break; }
+	}
+
+	Ok(())
+}
+
+/// # Walk ZIP.
+///
+/// Same as `walk`, but for an already-opened ZIP file.
+fn walk_zip<F>(zip: &mut ZipArchive<BufReader<File>>, mut cb: F)
+where F: FnMut(&str, &mut dyn Read) -> ControlFlow<()> {
+	for idx in 0..zip.len() {
+		let Ok(mut entry) = zip.by_index(idx) else { continue; };
+		if ! entry.is_file() { continue; }
+		let Some(name) = clean_name(entry.name()) else { continue; };
+		if cb(&name, &mut entry).is_break() { break; }
+	}
+}
+
+/// # Clean Entry Name.
+///
+/// Strip leading slashes, empty segments, and `.` segments from an entry
+/// name, returning `None` if there's nothing left.
+///
+/// This matches the normalization `Checksum` applies to paths, so names
+/// recorded in manifests can be found again later.
+fn clean_name(name: &str) -> Option<String> {
+	let mut out = String::with_capacity(name.len());
+	for part in name.split('/').filter(|p| ! p.is_empty() && *p != ".") {
+		if ! out.is_empty() { out.push('/'); }
+		out.push_str(part);
+	}
+
+	if out.is_empty() { None }
+	else { Some(out) }
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_check_archive() {
+		for (path, expected) in [
+			("assets.zip", true),
+			("./a/assets.ZIP", true),
+			("assets.tar", true),
+			("assets.tar.gz", true),
+			("assets.tgz", true),
+			("assets.gz", false),
+			(".zip", false),
+			("assets.png", false),
+		] {
+			assert_eq!(check_archive(path.as_bytes()), expected, "Wrong answer for {path}.");
+		}
+	}
+
+	#[test]
+	fn t_split_archive() {
+		assert_eq!(split_archive("./a.zip!/b/c.png"), Some(("./a.zip", "b/c.png")));
+		assert_eq!(split_archive("./a!/b.tgz!/c.png"), Some(("./a!/b.tgz", "c.png")));
+		assert_eq!(split_archive("./a.zip!/"), None);
+		assert_eq!(split_archive("./a.png"), None);
+		assert_eq!(split_archive("./a!/b.png"), None);
+	}
+
+	#[test]
+	fn t_clean_name() {
+		assert_eq!(clean_name("a/b.png").as_deref(), Some("a/b.png"));
+		assert_eq!(clean_name("./a//./b.png").as_deref(), Some("a/b.png"));
+		assert_eq!(clean_name("/a/b.png").as_deref(), Some("a/b.png"));
+		assert_eq!(clean_name("./"), None);
+	}
+}
@@ -0,0 +1,330 @@
+/*!
+# pxsum: CLI Errors.
+*/
+
+use pxsum::PxsumError;
+use std::{
+	error::Error,
+	fmt,
+	num::NonZeroU64,
+};
+
+
+
+/// # Help Text.
+///
+/// It's long, but at least it's static!
+const HELP: &str = concat!(
+	r"
+,_     _
+ |\\_,-~/
+ / _  _ |    ,--.
+(  @  @ )   / ,-'
+ \  _T_/-._( (
+ /         `. \
+|         _  \ |
+ \ \ ,  /      |   ", "\x1b[38;5;199mpxsum\x1b[0;38;5;69m v", env!("CARGO_PKG_VERSION"), "\x1b[0m", r#"
+  || |-_\__   /    Checksum decoded
+ ((_/`(____,-'     image pixel data.
+
+USAGE:
+    pxsum [FLAGS] [OPTIONS] [FILE(S)]...
+    pxsum --guard [FLAGS] [OPTIONS] [FILE(S)]... -- <COMMAND>...
+
+FLAGS:
+        --bench           Print the total execution time before exiting.
+    -c, --check           Read existing pxsum/path pairs from FILE(S) and
+                          check if they still ring true. This takes priority
+                          over crunch-specific options, like -d/--dir.
+        --color-managed   Convert images with embedded ICC or CICP color
+                          profiles — Adobe RGB, Display P3, CMYK, etc. — to
+                          sRGB before checksumming them, so that
+                          differently-tagged copies match.
+        --compare         Compare the pixels of exactly two FILE(S) directly,
+                          printing their formats, dimensions, and — if they
+                          aren't identical under the alpha policy — the
+                          differences. If both are directories, their images
+                          are paired up by relative path and compared in turn,
+                          with any unpaired entries reported as MISSING.
+        --cross-check     Decode JPEGs a second time with a separate decoder
+                          (jpeg-decoder), printing a warning if the two
+                          disagree about the pixels by more than rounding.
+                          Other formats have no second decoder, and are
+                          tallied in a single warning at the end.
+        --deep            Checksum high-bit-depth images at their native
+                          precision (RGBA16 or RGBA32F) instead of truncating
+                          everything to RGBA8.
+        --first-frame     Only checksum the first frame of animated images, and
+                          the first (or best) sub-image of multi-page TIFFs and
+                          multi-size ICOs. (By default, everything is
+                          included.)
+        --fix-extensions  Same as --lint, but also rename misnamed files to use
+                          the proper extensions for their formats.
+        --frame-timing    Include the loop count and frame delays of animated
+                          images in their checksums.
+    -g, --group-by-checksum
+                          Crunch as usual, but group the results by checksum.
+                          Note this will delay output until the end of the run.
+        --guard           Back up each image and run the COMMAND on it,
+                          restoring the original if the COMMAND failed or
+                          the pixels changed.
+    -h, --help            Print help information and exit.
+        --ignore-alpha    Ignore the alpha channel entirely, checksumming every
+                          pixel as if it were fully opaque.
+        --ignore-extension
+                          When comparing directories, pair files by their
+                          relative paths minus the extensions, so e.g. a.png
+                          can be checked against a.webp.
+        --lint            Check images for problems that wouldn't otherwise
+                          stop them from being checksummed — extensions that
+                          don't match their formats, trailing data after the
+                          end of the image, and tolerated truncation — and
+                          print a line for each one found.
+        --no-warnings     Suppress warnings related to image decoding when
+                          crunching anew, and malformed check manifest lines
+                          when -c/--check.
+        --only-dupes      Same as -g/--group-by-checksum, but only checksums
+                          with two or more matching images will be printed.
+        --oriented        Apply EXIF, TIFF, and HEIF-style orientation metadata
+                          to images before checksumming them, so that rotated
+                          copies match their tagged originals.
+        --perceptual      Print perceptual fingerprint/path pairs instead of
+                          checksums. Unlike checksums, fingerprints are fuzzy,
+                          and stay the same (or close) for resized and
+                          recompressed copies.
+        --premultiplied   Premultiply the color channels by the alpha channel
+                          before checksumming, so that semi-transparent color
+                          drift from premultiplied storage doesn't matter.
+    -q, --quiet           Suppress OK messages in -c/--check mode.
+        --raw-sensor      Checksum camera RAWs by their untouched sensor data
+                          rather than their developed pixels.
+        --sniff           Accept any regular file, regardless of extension,
+                          identifying images by their file signatures alone.
+                          (Files that turn out not to be images are silently
+                          skipped.)
+        --split-pages     Checksum each sub-image of multi-page TIFFs and
+                          multi-size ICOs separately, printing them as
+                          path#index pairs.
+        --stdin-tar       Read a TAR stream from STDIN instead of FILE(S),
+                          checksumming its images in parallel and printing
+                          each under its member name.
+        --strict          Include color data from invisible pixels in checksum
+                          calculations.
+        --tiled           Hash images as a Merkle tree of 64x64 tiles,
+                          extending each line with the tile digests so that
+                          -c/--check can report which regions of a failed
+                          image changed.
+    -V, --version         Print version information and exit.
+
+OPTIONS:
+    -d, --dir <DIR>       Recursively search <DIR> for image files (and
+                          archives) and pxsum them (along with any other
+                          FILE(S)). Has no effect when -c/--check.
+        --diff-against <PATH>
+                          When -c/--check, compare the pixels of each image
+                          that FAILED against a reference copy — <PATH> itself,
+                          or the same relative path inside it if a directory —
+                          and report the number of differing pixels, max
+                          channel delta, PSNR, and bounding box.
+        --diff-image <DIR>
+                          Alongside --diff-against, save a visualization of
+                          each difference as a PNG inside <DIR>.
+        --flatten <HEX>   Composite images over a solid #RRGGBB background
+                          color before checksumming them, as when exporting to
+                          a format without transparency.
+    -j <NUM>              Limit parallelization to this many threads (instead
+                          of giving each logical core its own image to work
+                          on). If negative, the value will be subtracted from
+                          the total number of logical cores.
+        --similar <NUM>   Crunch perceptual fingerprints as with --perceptual,
+                          but print only groups of near-duplicate images,
+                          i.e. those whose fingerprints differ by no more than
+                          <NUM> bits (0-64). Around 5-10 is a good start.
+        --svg-size <SIZE> Rasterize SVGs at this size — a width, height, or
+                          both, like 512, x256, or 512x256 — instead of their
+                          intrinsic size before checksumming them. Must be
+                          set the same way when -c/--check.
+
+ARGS:
+    [FILE(S)]...          One or more image file paths to checksum, or if
+                          -c/--check, one or more text file paths containing
+                          pxsums to verify.
+
+                          ZIP and TAR (.zip, .tar, .tar.gz, .tgz) archives
+                          have their images checksummed in place, each
+                          recorded as archive!/entry.
+
+                          With no FILE(S) or -, input is read from STDIN.
+    -- <COMMAND>...       With --guard, the command to run on each image, in
+                          place. Any {} in its arguments is replaced with the
+                          image path; otherwise the path is appended.
+
+FORMATS:
+"#, include_str!(concat!(env!("OUT_DIR"), "/pxsum-formats.txt")), r#"
+
+EXIT CODES:
+    0: Business as usual!
+    1: Something blew up!
+    2: No checksum/path pairs were outputted.
+    3: One or more images failed to re-verify, the --compare images
+       differ, or --lint found issues.
+    4: One or more --compare directory entries had no counterpart (but
+       nothing else differed).
+"#
+);
+
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// # CLI Error Type.
+///
+/// This wraps library errors with the CLI-specific failures, summaries, and
+/// abort hints for "special" screens like Help and Version.
+pub(super) enum CliError {
+	/// # Conflicting alpha policies.
+	AlphaPolicy,
+
+	/// # Invalid flatten color.
+	Background,
+
+	/// # Wrong number of comparison paths.
+	Compare,
+
+	/// # Missing guard command.
+	Guard,
+
+	/// # Job server failed.
+	///
+	/// This would trigger in the event a `tx.send()` request fails, but that
+	/// shouldn't happen in practice.
+	JobServer,
+
+	/// # Nothing Doing.
+	///
+	/// This error is used when no paths were checksummed, allowing the program
+	/// to exit with a different code.
+	Noop,
+
+	/// # Same as above, but in dupe mode.
+	NoDupes,
+
+	/// # Print Help.
+	///
+	/// Not an "error", per se, but demands early abort.
+	PrintHelp,
+
+	/// # Print Version.
+	///
+	/// Not an "error", per se, but demands early abort.
+	PrintVersion,
+
+	/// # Library Error.
+	Pxsum(PxsumError),
+
+	/// # Invalid similarity distance.
+	Similar,
+
+	/// # Invalid SVG size.
+	SvgSize,
+
+	/// # Comparison Failure.
+	///
+	/// This error is used when the images passed to `--compare` differ,
+	/// allowing the program to exit with a different code.
+	Different,
+
+	/// # Verification Failure(s).
+	///
+	/// This error is used to indicate the total number of verification
+	/// failures, allowing the program to exit with a different code.
+	Failed(NonZeroU64),
+
+	/// # Lint Issue(s).
+	///
+	/// This error is used to indicate the total number of files with lint
+	/// issues (or read/decode errors), allowing the program to exit with a
+	/// different code.
+	Linted(NonZeroU64),
+
+	/// # Guard Rollback(s).
+	///
+	/// This error is used to indicate the total number of images restored
+	/// after a `--guard` command changed their pixels, allowing the program to
+	/// exit with a different code.
+	Restored(NonZeroU64),
+
+	/// # Directory Comparison Failure(s).
+	///
+	/// This error is used to indicate the number of pixel-changed (or
+	/// undecodable) and unpaired entries, respectively, found when comparing
+	/// two directories, allowing the program to exit with a different code.
+	Mismatched(u64, u64),
+}
+
+impl fmt::Display for CliError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let string = match self {
+			Self::Pxsum(e) => return fmt::Display::fmt(e, f),
+			Self::Restored(n) => return write!(
+				f,
+				"{n} image{} had to be restored",
+				if n.get() == 1 { "" } else { "s" }
+			),
+			Self::Mismatched(changed, missing) => {
+				if 0 != *changed {
+					write!(f, "{changed} image{} did NOT match", if *changed == 1 { "" } else { "s" })?;
+					if 0 != *missing { f.write_str("; ")?; }
+				}
+				if 0 != *missing {
+					write!(f, "{missing} image{} had no counterpart", if *missing == 1 { "" } else { "s" })?;
+				}
+				return Ok(());
+			},
+			Self::Different => "The images are NOT pixel-identical.",
+			Self::Linted(n) => return write!(
+				f,
+				"{n} file{} had issues",
+				if n.get() == 1 { "" } else { "s" }
+			),
+			Self::Failed(n) => return write!(
+				f,
+				"{n} computed checksum{} did NOT match",
+				if n.get() ==1 { "" } else { "s" }
+			),
+			Self::AlphaPolicy => "Only one of --strict, --premultiplied, --ignore-alpha, and --flatten may be used at a time.",
+			Self::Background => "The --flatten color must be a hex value like #RRGGBB.",
+			Self::Compare => "--compare requires exactly two image paths or two directories.",
+			Self::Guard => "--guard requires a command, e.g. pxsum --guard [FILE(S)] -- oxipng {}",
+			Self::JobServer => "Job server choked!",
+			Self::NoDupes => "No duplicate images were found.",
+			Self::Noop => "No pixel checksums were computed.",
+			Self::PrintHelp => HELP,
+			Self::PrintVersion => concat!("pxsum v", env!("CARGO_PKG_VERSION")),
+			Self::Similar => "The --similar distance must be a number between 0 and 64.",
+			Self::SvgSize => "The --svg-size must be a width and/or height like 512 or 512x256.",
+		};
+
+		f.write_str(string)
+	}
+}
+
+impl Error for CliError {}
+
+impl From<PxsumError> for CliError {
+	#[inline]
+	fn from(src: PxsumError) -> Self { Self::Pxsum(src) }
+}
+
+impl CliError {
+	#[must_use]
+	/// # Exit Code.
+	pub(super) const fn exit_code(self) -> i32 {
+		match self {
+			Self::PrintHelp | Self::PrintVersion => 0,
+			Self::Noop | Self::NoDupes => 2,
+			Self::Failed(_) | Self::Different | Self::Linted(_) | Self::Restored(_) => 3,
+			Self::Mismatched(changed, _) => if changed == 0 { 4 } else { 3 },
+			_ => 1,
+		}
+	}
+}
@@ -2,15 +2,46 @@
 # pxsum: Image.
 */
 
+mod color;
+mod cross;
+mod diff;
+#[cfg(feature = "jxl")] mod jxl;
+#[cfg(feature = "raw")] mod raw;
+mod svg;
+mod tile;
+
 use crate::{
 	Checksum,
 	PxsumError,
 };
 use image::{
+	AnimationDecoder,
+	ColorType,
 	DynamicImage,
+	Frame,
+	ImageDecoder,
 	ImageFormat,
+	codecs::{
+		gif::GifDecoder,
+		png::PngDecoder,
+		webp::WebPDecoder,
+	},
+	metadata::{
+		LoopCount,
+		Orientation,
+	},
+};
+use moxcms::{
+	ColorProfile,
+	DataColorSpace,
 };
-use std::num::Wrapping;
+use std::{
+	borrow::Cow,
+	io::Cursor,
+	num::Wrapping,
+};
+pub use diff::PxDiff;
+pub use tile::TileRegion;
 
 
 
@@ -19,31 +50,75 @@ use std::num::Wrapping;
 ///
 /// This enum collects all of the supported image formats from all of the
 /// third-party crates we're using for decoding.
-pub(super) enum PxKind {
+pub enum PxKind {
+	#[cfg(feature = "raw")]
+	/// # Sony RAW.
+	Arw,
+
+	#[cfg(feature = "avif")]
 	/// # AVIF.
 	Avif,
 
 	/// # Bitmap.
 	Bmp,
 
+	#[cfg(feature = "raw")]
+	/// # Canon RAW.
+	Cr2,
+
+	#[cfg(feature = "raw")]
+	/// # Digital Negative.
+	Dng,
+
+	/// # Farbfeld.
+	Farbfeld,
+
 	/// # GIF.
 	Gif,
 
+	/// # Radiance HDR.
+	Hdr,
+
+	#[cfg(feature = "heif")]
+	/// # HEIF (HEIC).
+	Heif,
+
 	/// # Icon.
 	Ico,
 
 	/// # JPEG.
 	Jpeg,
 
+	#[cfg(feature = "jpeg2k")]
 	/// # JPEG 2000.
 	Jpeg2k,
 
+	#[cfg(feature = "jxl")]
 	/// # JPEG XL.
 	JpegXl,
 
+	#[cfg(feature = "raw")]
+	/// # Nikon RAW.
+	Nef,
+
+	/// # `OpenEXR`.
+	OpenExr,
+
 	/// # PNG.
 	Png,
 
+	/// # PNM (PBM, PGM, PPM, PAM).
+	Pnm,
+
+	/// # QOI.
+	Qoi,
+
+	/// # SVG.
+	Svg,
+
+	/// # Targa.
+	Tga,
+
 	/// # Tiff(any?).
 	Tiff,
 
@@ -56,12 +131,19 @@ impl TryFrom<ImageFormat> for PxKind {
 
 	fn try_from(src: ImageFormat) -> Result<Self, Self::Error> {
 		match src {
+			#[cfg(feature = "avif")]
 			ImageFormat::Avif => Ok(Self::Avif),
 			ImageFormat::Bmp => Ok(Self::Bmp),
+			ImageFormat::Farbfeld => Ok(Self::Farbfeld),
 			ImageFormat::Gif => Ok(Self::Gif),
+			ImageFormat::Hdr => Ok(Self::Hdr),
 			ImageFormat::Ico => Ok(Self::Ico),
 			ImageFormat::Jpeg => Ok(Self::Jpeg),
+			ImageFormat::OpenExr => Ok(Self::OpenExr),
 			ImageFormat::Png => Ok(Self::Png),
+			ImageFormat::Pnm => Ok(Self::Pnm),
+			ImageFormat::Qoi => Ok(Self::Qoi),
+			ImageFormat::Tga => Ok(Self::Tga),
 			ImageFormat::Tiff => Ok(Self::Tiff),
 			ImageFormat::WebP => Ok(Self::WebP),
 			_ => Err(PxsumError::Decode),
@@ -71,69 +153,339 @@ impl TryFrom<ImageFormat> for PxKind {
 
 impl PxKind {
 	/// # Decode.
-	fn decode(self, src: &[u8]) -> Result<DynamicImage, PxsumError> {
-		use jpegxl_rs::image::ToDynamic;
-
+	///
+	/// If `flags` include `Checksum::ORIENTED`, any EXIF, TIFF, or HEIF-style
+	/// orientation will be applied to the result. (JPEG XL images are always
+	/// oriented, as that's what the decoder does.)
+	///
+	/// If they include `Checksum::COLOR_MANAGED`, any embedded ICC or CICP
+	/// color profile will be used to convert the pixels to sRGB.
+	fn decode(self, src: &[u8], flags: u16) -> Result<DynamicImage, PxsumError> {
+		#[cfg(feature = "avif")]
 		#[cold]
 		/// # Decode AVIF.
 		///
 		/// Not a popular format, hence cold.
-		fn decode_avif(src: &[u8]) -> Result<DynamicImage, PxsumError> {
-			libavif::decode_rgb(src).ok()
+		fn decode_avif(src: &[u8], oriented: bool, managed: bool)
+		-> Result<DynamicImage, PxsumError> {
+			let mut img = libavif::decode_rgb(src).ok()
 				.and_then(|img| image::ImageBuffer::from_vec(
 					img.width(),
 					img.height(),
 					img.to_vec(),
 				))
 				.map(DynamicImage::ImageRgba8)
-				.ok_or(PxsumError::Decode)
+				.ok_or(PxsumError::Decode)?;
+
+			// The decoder ignores the rotation/mirroring properties, so we
+			// have to apply them ourselves.
+			if oriented {
+				for o in heif_orientation(src) { img.apply_orientation(o); }
+			}
+
+			// Same for color.
+			if managed {
+				if let Some(profile) = color::heif_profile(src)? {
+					img = color::into_srgb(img, &profile)?;
+				}
+			}
+
+			Ok(img)
 		}
 
+		#[cfg(feature = "jpeg2k")]
 		#[cold]
 		/// # Decode JPEG 2000.
 		///
 		/// Not a popular format, hence cold.
-		fn decode_jpeg2k(src: &[u8]) -> Result<DynamicImage, PxsumError> {
-			jpeg2k::Image::from_bytes(src).ok()
-				.and_then(|img| DynamicImage::try_from(&img).ok())
-				.ok_or(PxsumError::Decode)
-		}
+		fn decode_jpeg2k(src: &[u8], managed: bool) -> Result<DynamicImage, PxsumError> {
+			let img = jpeg2k::Image::from_bytes(src).map_err(|_| PxsumError::Decode)?;
 
-		#[cold]
-		/// # Decode JPEG XL.
-		///
-		/// Not a popular format, hence cold.
-		fn decode_jpegxl(src: &[u8]) -> Result<DynamicImage, PxsumError> {
-			jpegxl_rs::decoder_builder()
-				.build()
-				.and_then(|dec| dec.decode_to_image(src))
-				.ok()
-				.flatten()
-				.ok_or(PxsumError::Decode)
+			// The decoder doesn't give us access to embedded ICC profiles, so
+			// we can't color-manage images that have them.
+			if managed && img.has_icc_profile() { return Err(PxsumError::Unsupported); }
+
+			DynamicImage::try_from(&img).map_err(|_| PxsumError::Decode)
 		}
 
+		let oriented = Checksum::ORIENTED == flags & Checksum::ORIENTED;
+		let managed = Checksum::COLOR_MANAGED == flags & Checksum::COLOR_MANAGED;
+
 		// Most decoding is handled by the image crate.
 		let fmt = match self {
 			Self::Bmp => ImageFormat::Bmp,
+			Self::Farbfeld => ImageFormat::Farbfeld,
 			Self::Gif => ImageFormat::Gif,
+			Self::Hdr => ImageFormat::Hdr,
 			Self::Ico => ImageFormat::Ico,
 			Self::Jpeg => ImageFormat::Jpeg,
+			Self::OpenExr => ImageFormat::OpenExr,
 			Self::Png => ImageFormat::Png,
+			Self::Pnm => ImageFormat::Pnm,
+			Self::Qoi => ImageFormat::Qoi,
+			Self::Tga => ImageFormat::Tga,
 			Self::Tiff => ImageFormat::Tiff,
 			Self::WebP => ImageFormat::WebP,
 
 			// The image crate doesn't _really_ support AVIF yet, so we need to
 			// step in for these.
-			Self::Avif => return decode_avif(src),
+			#[cfg(feature = "avif")]
+			Self::Avif => return decode_avif(src, oriented, managed),
+
+			// Ditto for HEIF, its big brother.
+			#[cfg(feature = "heif")]
+			Self::Heif => return decode_heif(src, oriented, managed),
+
+			// Camera RAWs need to be developed.
+			#[cfg(feature = "raw")]
+			Self::Arw | Self::Cr2 | Self::Dng | Self::Nef => return raw::decode(src, oriented, managed),
+
+			// SVGs need to be rasterized first.
+			Self::Svg => return svg::render(src, [0, 0]),
 
 			// JPEG 2000 does its own thing.
-			Self::Jpeg2k => return decode_jpeg2k(src),
+			#[cfg(feature = "jpeg2k")]
+			Self::Jpeg2k => return decode_jpeg2k(src, managed),
 
 			// And so does JPEG XL.
-			Self::JpegXl => return decode_jpegxl(src),
+			#[cfg(feature = "jxl")]
+			Self::JpegXl => return jxl::decode(src, managed),
 		};
 
-		Ok(image::load_from_memory_with_format(src, fmt)?)
+		// Orientation and color management require a little more effort.
+		if oriented || managed {
+			let mut dec = image::ImageReader::with_format(Cursor::new(src), fmt)
+				.into_decoder()?;
+			let orientation =
+				if oriented { dec.orientation()? }
+				else { Orientation::NoTransforms };
+			let profile =
+				if managed { self.color_profile(src, dec.icc_profile()?)? }
+				else { None };
+
+			// CMYK JPEGs need to be decoded from scratch; everything else can
+			// be converted after the fact.
+			let mut img = match profile {
+				Some(ref p) if matches!(self, Self::Jpeg) && p.color_space == DataColorSpace::Cmyk =>
+					color::decode_cmyk_jpeg(src, p)?,
+				Some(ref p) => color::into_srgb(DynamicImage::from_decoder(dec)?, p)?,
+				None => DynamicImage::from_decoder(dec)?,
+			};
+			img.apply_orientation(orientation);
+			Ok(img)
+		}
+		else { Ok(image::load_from_memory_with_format(src, fmt)?) }
+	}
+
+	/// # Color Profile.
+	///
+	/// Return the color profile of an image decoded by the image crate, given
+	/// the ICC profile (if any) reported by its decoder. PNGs might have CICP
+	/// data instead.
+	///
+	/// Profiles that are effectively sRGB are ignored.
+	fn color_profile(self, src: &[u8], icc: Option<Vec<u8>>)
+	-> Result<Option<ColorProfile>, PxsumError> {
+		if matches!(self, Self::Png) { color::png_profile(src, icc) }
+		else { icc.map_or(Ok(None), |raw| color::icc_profile(&raw)) }
+	}
+
+	/// # Decode Animation.
+	///
+	/// Return the fully-composited RGBA8 frames and loop count of an animated
+	/// GIF, PNG, or WebP, or `None` if the image is static (or in a format
+	/// whose decoder only ever returns the first frame). The frames will be
+	/// oriented and/or color-managed according to the `flags`, as with
+	/// `PxKind::decode`.
+	///
+	/// The animation decoders only speak RGBA8, so in deep mode, an error is
+	/// returned for sixteen-bit APNGs rather than silently losing precision.
+	fn decode_frames(self, src: &[u8], flags: u16)
+	-> Result<Option<(Vec<Frame>, LoopCount)>, PxsumError> {
+		let deep = Checksum::DEEP == flags & Checksum::DEEP;
+		let oriented = Checksum::ORIENTED == flags & Checksum::ORIENTED;
+		let managed = Checksum::COLOR_MANAGED == flags & Checksum::COLOR_MANAGED;
+
+		let mut orientation = Orientation::NoTransforms;
+		let mut profile = None;
+		let (mut frames, loops) = match self {
+			Self::Gif => {
+				let mut dec = GifDecoder::new(Cursor::new(src))?;
+				if managed { profile = self.color_profile(src, dec.icc_profile()?)?; }
+				let loops = dec.loop_count();
+				(dec.into_frames().collect_frames()?, loops)
+			},
+			Self::Png => {
+				let mut dec = PngDecoder::new(Cursor::new(src))?;
+				if ! dec.is_apng()? { return Ok(None); }
+				if deep && matches!(
+					dec.color_type(),
+					ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16
+				) {
+					return Err(PxsumError::Unsupported);
+				}
+				if oriented { orientation = dec.orientation()?; }
+				if managed { profile = self.color_profile(src, dec.icc_profile()?)?; }
+
+				let dec = dec.apng()?;
+				let loops = dec.loop_count();
+				(dec.into_frames().collect_frames()?, loops)
+			},
+			Self::WebP => {
+				let mut dec = WebPDecoder::new(Cursor::new(src))?;
+				if ! dec.has_animation() { return Ok(None); }
+				if oriented { orientation = dec.orientation()?; }
+				if managed { profile = self.color_profile(src, dec.icc_profile()?)?; }
+				let loops = dec.loop_count();
+				(dec.into_frames().collect_frames()?, loops)
+			},
+			_ => return Ok(None),
+		};
+
+		// A single frame is no animation at all.
+		if frames.len() < 2 { return Ok(None); }
+
+		// Reorient and/or convert as needed.
+		if orientation != Orientation::NoTransforms || profile.is_some() {
+			for frame in &mut frames {
+				let mut img = DynamicImage::ImageRgba8(std::mem::take(frame.buffer_mut()));
+				if let Some(ref p) = profile { img = color::into_srgb(img, p)?; }
+				img.apply_orientation(orientation);
+				*frame.buffer_mut() = img.into_rgba8();
+			}
+		}
+
+		Ok(Some((frames, loops)))
+	}
+
+	#[must_use]
+	/// # File Extensions.
+	///
+	/// Return the (lowercase) file extensions associated with the format, the
+	/// most common first.
+	pub const fn extensions(self) -> &'static [&'static str] {
+		match self {
+			#[cfg(feature = "raw")]
+			Self::Arw => &["arw"],
+			#[cfg(feature = "avif")]
+			Self::Avif => &["avif"],
+			Self::Bmp => &["bmp"],
+			#[cfg(feature = "raw")]
+			Self::Cr2 => &["cr2"],
+			#[cfg(feature = "raw")]
+			Self::Dng => &["dng"],
+			Self::Farbfeld => &["ff"],
+			Self::Gif => &["gif"],
+			Self::Hdr => &["hdr"],
+			#[cfg(feature = "heif")]
+			Self::Heif => &["heic", "heif", "hif"],
+			Self::Ico => &["ico"],
+			Self::Jpeg => &["jpg", "jpeg"],
+			#[cfg(feature = "jpeg2k")]
+			Self::Jpeg2k => &["jp2", "j2k", "j2c", "jpc", "jpg2"],
+			#[cfg(feature = "jxl")]
+			Self::JpegXl => &["jxl"],
+			#[cfg(feature = "raw")]
+			Self::Nef => &["nef"],
+			Self::OpenExr => &["exr"],
+			Self::Png => &["png"],
+			Self::Pnm => &["pnm", "pbm", "pgm", "ppm", "pam"],
+			Self::Qoi => &["qoi"],
+			Self::Svg => &["svg", "svgz"],
+			Self::Tga => &["tga", "icb", "vda", "vst"],
+			Self::Tiff => &["tiff", "tif"],
+			Self::WebP => &["webp"],
+		}
+	}
+
+	#[must_use]
+	/// # As Str.
+	///
+	/// Return the (human-readable) name of the format.
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			#[cfg(feature = "avif")]
+			Self::Avif => "AVIF",
+			#[cfg(feature = "raw")]
+			Self::Arw => "ARW",
+			Self::Bmp => "BMP",
+			#[cfg(feature = "raw")]
+			Self::Cr2 => "CR2",
+			#[cfg(feature = "raw")]
+			Self::Dng => "DNG",
+			Self::Farbfeld => "farbfeld",
+			Self::Gif => "GIF",
+			Self::Hdr => "HDR",
+			#[cfg(feature = "heif")]
+			Self::Heif => "HEIF",
+			Self::Ico => "ICO",
+			Self::Jpeg => "JPEG",
+			#[cfg(feature = "jpeg2k")]
+			Self::Jpeg2k => "JPEG 2000",
+			#[cfg(feature = "jxl")]
+			Self::JpegXl => "JPEG XL",
+			#[cfg(feature = "raw")]
+			Self::Nef => "NEF",
+			Self::OpenExr => "OpenEXR",
+			Self::Png => "PNG",
+			Self::Pnm => "PNM",
+			Self::Qoi => "QOI",
+			Self::Svg => "SVG",
+			Self::Tga => "TGA",
+			Self::Tiff => "TIFF",
+			Self::WebP => "WebP",
+		}
+	}
+
+	/// # Page Count.
+	///
+	/// Return the number of sub-images — pages or entries — stored in a
+	/// multi-image TIFF or ICO container. For everything else, this is always
+	/// one.
+	#[must_use]
+	pub fn pages(self, src: &[u8]) -> usize {
+		match self {
+			Self::Ico => ico_entries(src).len().max(1),
+			Self::Tiff => tiff_ifds(src).len().max(1),
+			_ => 1,
+		}
+	}
+
+	/// # Extract Page.
+	///
+	/// Return a standalone copy of the given sub-image of a TIFF or ICO
+	/// container, so that it can be decoded in the usual way. (Page zero of
+	/// any other format is just the source itself.)
+	///
+	/// For ICOs, this is a fresh single-entry icon. For TIFFs, the header is
+	/// simply repointed to the page's directory, as all other offsets within
+	/// the file are absolute.
+	fn extract_page(self, src: &[u8], page: usize) -> Option<Cow<'_, [u8]>> {
+		match self {
+			Self::Ico => {
+				let (entry, data) = ico_entries(src).get(page).copied()?;
+				let data = src.get(data..)?;
+				let len = usize::try_from(u32::from_le_bytes(entry[8..12].try_into().ok()?)).ok()?;
+				let data = data.get(..len)?;
+
+				let mut out = Vec::with_capacity(22 + len);
+				out.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+				out.extend_from_slice(&entry[..12]);
+				out.extend_from_slice(22_u32.to_le_bytes().as_slice());
+				out.extend_from_slice(data);
+				Some(Cow::Owned(out))
+			},
+			Self::Tiff => {
+				let ifd = tiff_ifds(src).get(page).copied()?;
+				let mut out = src.to_vec();
+				let ifd =
+					if out.starts_with(b"MM") { ifd.to_be_bytes() }
+					else { ifd.to_le_bytes() };
+				out[4..8].copy_from_slice(ifd.as_slice());
+				Some(Cow::Owned(out))
+			},
+			_ => (page == 0).then_some(Cow::Borrowed(src)),
+		}
 	}
 
 	/// # Guess Format.
@@ -141,100 +493,1015 @@ impl PxKind {
 	/// Look for a known file signature in the first dozen bytes, similar to
 	/// `image::guess_format`, but covering all (and only) the specific image
 	/// formats we support.
-	pub(super) const fn try_from_magic(src: &[u8]) -> Result<Self, PxsumError> {
+	///
+	/// XML prefixes only count as SVG if the first element — within the first
+	/// few KiB — is actually an `<svg>`.
+	///
+	/// ## Errors
+	///
+	/// If the signature is unrecognized, an error will be returned.
+	pub const fn try_from_magic(src: &[u8]) -> Result<Self, PxsumError> {
 		match src.first_chunk::<12>() {
 			Some([0xff, 0xd8, 0xff, ..]) => Ok(Self::Jpeg),
 			Some([0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, ..]) => Ok(Self::Png),
 			Some([b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..]) => Ok(Self::Gif),
 			Some([b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P']) => Ok(Self::WebP),
+			#[cfg(feature = "avif")]
 			Some([0x00, 0x00, 0x00, 0x20 | 0x1c, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f']) => Ok(Self::Avif),
+			#[cfg(feature = "heif")]
+			Some(
+				[_, _, _, _, b'f', b't', b'y', b'p', b'h', b'e', b'i' | b'v', b'c' | b'x' | b'm' | b's'] |
+				[_, _, _, _, b'f', b't', b'y', b'p', b'm', b'i' | b's', b'f', b'1']
+			) => Ok(Self::Heif),
+			#[cfg(feature = "jxl")]
 			Some([0xff, 0x0a, ..] | [0x00, 0x00, 0x00, 0x0c, b'J', b'X', b'L', 0x20, 0x0d, 0x0a, 0x87, 0x0a]) => Ok(Self::JpegXl),
 			Some([b'B', b'M', ..]) => Ok(Self::Bmp),
 			Some([0x00, 0x00, 0x01, 0x00, ..]) => Ok(Self::Ico),
+			#[cfg(feature = "jpeg2k")]
 			Some([0x00, 0x00, 0x00, 0x0c, b'j', b'P', 0x20, 0x20, 0x0d, 0x0a, 0x87, 0x0a] | [0xff, b'O', 0xff, b'Q', ..]) => Ok(Self::Jpeg2k),
+			#[cfg(feature = "raw")]
+			Some([b'I', b'I', b'*', 0x00, _, _, _, _, b'C', b'R', 0x02, 0x00]) => Ok(Self::Cr2),
 			Some([b'M', b'M', 0x00, b'*', ..] | [b'I', b'I', b'*', 0x00, ..]) => Ok(Self::Tiff),
+			Some([b'q', b'o', b'i', b'f', ..]) => Ok(Self::Qoi),
+			Some([b'f', b'a', b'r', b'b', b'f', b'e', b'l', b'd', ..]) => Ok(Self::Farbfeld),
+			Some([0x76, 0x2f, 0x31, 0x01, ..]) => Ok(Self::OpenExr),
+			Some([b'#', b'?', b'R', b'A', b'D', b'I', b'A', b'N', b'C', b'E', ..] | [b'#', b'?', b'R', b'G', b'B', b'E', ..]) => Ok(Self::Hdr),
+			Some([b'P', b'1'..=b'7', b'\t' | b'\n' | b'\r' | b' ', ..]) => Ok(Self::Pnm),
+			Some(
+				[b'<', b's', b'v', b'g', ..] |
+				[b'<', b'?', b'x', b'm', b'l', ..] |
+				[0xef, 0xbb, 0xbf, b'<', ..] |
+				[b'<', b'!', b'D', b'O', b'C', b'T', b'Y', b'P', b'E', b' ', b's', b'v']
+			) if svg::has_root(src) => Ok(Self::Svg),
 			_ => Err(PxsumError::Decode),
 		}
 	}
+
+	#[cfg_attr(
+		not(feature = "raw"),
+		expect(clippy::missing_const_for_fn, reason = "Only const without RAW support."),
+	)]
+	#[must_use]
+	/// # Refine Format.
+	///
+	/// Most camera RAWs are TIFFs as far as their signatures are concerned,
+	/// so `PxKind::try_from_magic` can only call them that. Given the full
+	/// source, this will peek inside TIFFs to see if they're actually NEFs,
+	/// ARWs, or DNGs, returning the more specific kind if so.
+	///
+	/// All other kinds are returned as-is.
+	pub fn refine(self, src: &[u8]) -> Self {
+		#[cfg(feature = "raw")]
+		if matches!(self, Self::Tiff) {
+			if let Some(kind) = raw::kind(src) { return kind; }
+		}
+
+		#[cfg(not(feature = "raw"))] let _ = src;
+		self
+	}
+
+	#[must_use]
+	/// # Guess Format (Extension).
+	///
+	/// TGA images — also known as ICB, VDA, and VST — have no file signature
+	/// to speak of, and SVGs may be gzipped or start with arbitrary whitespace
+	/// or comments, so if `PxKind::try_from_magic` comes up empty, this can be
+	/// used to fall back to the file extension instead.
+	///
+	/// Returns `None` for everything else.
+	pub const fn try_from_extension(path: &[u8]) -> Option<Self> {
+		match path {
+			[.., b'.', b't' | b'T', b'g' | b'G', b'a' | b'A'] |
+			[.., b'.', b'i' | b'I', b'c' | b'C', b'b' | b'B'] |
+			[.., b'.', b'v' | b'V', b'd' | b'D', b'a' | b'A'] |
+			[.., b'.', b'v' | b'V', b's' | b'S', b't' | b'T'] => Some(Self::Tga),
+			[.., b'.', b's' | b'S', b'v' | b'V', b'g' | b'G'] |
+			[.., b'.', b's' | b'S', b'v' | b'V', b'g' | b'G', b'z' | b'Z'] => Some(Self::Svg),
+			_ => None,
+		}
+	}
 }
 
 
 
 /// # Image Wrapper.
 ///
-/// This holds the pixel buffer for an image in RGBA format, along with
-/// knowledge of the original storage to potentially avoid alpha traversal.
-pub(super) struct PxImage {
+/// This holds the pixel buffer for an image in RGBA format, along with its
+/// dimensions and knowledge of the original storage to potentially avoid
+/// alpha traversal.
+pub struct PxImage {
 	/// # Pixel Buffer.
 	buf: Vec<u8>,
 
 	/// # No Alpha Data (original type).
 	no_alpha: bool,
+
+	/// # Width.
+	width: u32,
+
+	/// # Height.
+	height: u32,
+
+	/// # Frame Count.
+	frames: u32,
+
+	/// # Loop Count.
+	///
+	/// This is zero for static images and infinite animations.
+	loops: u32,
+
+	/// # Frame Delays (Milliseconds, as Reduced Ratios).
+	///
+	/// This is empty for static images.
+	delays: Vec<(u32, u32)>,
+
+	/// # Page Dimensions.
+	///
+	/// This is only populated for multi-image containers, whose sub-images
+	/// needn't all be the same size.
+	sizes: Vec<(u32, u32)>,
+
+	/// # Bytes Per Pixel.
+	///
+	/// This is `4` for regular RGBA8, `8` for deep RGBA16, or `16` for deep
+	/// RGBA32F.
+	px: u8,
+
+	/// # Background Color.
+	///
+	/// This is only used by the `Checksum::FLATTEN` alpha policy.
+	background: [u8; 3],
+
+	/// # Tile Digests (Truncated).
+	///
+	/// This is only populated by `Checksum::TILED` checksumming.
+	tiles: Vec<[u8; 8]>,
+
+	/// # Raw Sensor Data?
+	///
+	/// This is only set for camera RAWs decoded per `Checksum::RAW_SENSOR`.
+	sensor: bool,
 }
 
 impl PxImage {
 	/// # Decode Image.
 	///
-	/// Decode the image from memory.
+	/// Decode the image from memory, using the `Checksum` mode bits in
+	/// `flags` (e.g. `Checksum::DEEP`) to determine how the pixels should be
+	/// normalized.
 	///
 	/// ## Errors
 	///
 	/// This will return an error if the image cannot be decoded or has an
 	/// invalid pixel count.
-	pub(super) fn new(src: &[u8], format: PxKind) -> Result<Self, PxsumError> {
+	pub fn new(src: &[u8], format: PxKind, flags: u16) -> Result<Self, PxsumError> {
+		let deep = Checksum::DEEP == flags & Checksum::DEEP;
+
+		// The AVIF decoder only speaks RGBA8; in deep mode, refuse anything
+		// that would lose precision because of that.
+		#[cfg(feature = "avif")]
+		if deep && matches!(format, PxKind::Avif) && avif_high_bitdepth(src) {
+			return Err(PxsumError::Unsupported);
+		}
+
+		// Camera RAWs might want their sensor data instead.
+		#[cfg(feature = "raw")]
+		if
+			Checksum::RAW_SENSOR == flags & Checksum::RAW_SENSOR &&
+			matches!(format, PxKind::Arw | PxKind::Cr2 | PxKind::Dng | PxKind::Nef)
+		{
+			let mut img = Self::from_dynamic(raw::sensor(src)?, true)?;
+			img.sensor = true;
+			return Ok(img);
+		}
+
+		// Animations are handled separately.
+		if Checksum::ALL_FRAMES == flags & Checksum::ALL_FRAMES {
+			if let Some((frames, loops)) = format.decode_frames(src, flags)? {
+				return Self::from_frames(frames, loops, deep);
+			}
+		}
+
+		// As are multi-image containers.
+		if
+			Checksum::ALL_FRAMES == flags & Checksum::ALL_FRAMES &&
+			1 < format.pages(src)
+		{
+			return Self::from_pages(src, format, flags);
+		}
+
 		// Decode the image as-is.
-		let img = format.decode(src)?;
+		Self::from_dynamic(format.decode(src, flags)?, deep)
+	}
+
+	/// # New (SVG).
+	///
+	/// Same as `PxImage::new`, but for SVGs, which are rasterized at the given
+	/// width and height rather than their intrinsic size. Either dimension may
+	/// be zero to derive it from the other, preserving the aspect ratio.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the SVG cannot be parsed or rendered at
+	/// the requested size.
+	pub fn new_svg(src: &[u8], flags: u16, size: [u32; 2]) -> Result<Self, PxsumError> {
+		Self::from_dynamic(svg::render(src, size)?, Checksum::DEEP == flags & Checksum::DEEP)
+	}
+
+	/// # Cross-Check Decoders.
+	///
+	/// Decode the image with both the primary backend and an alternate one —
+	/// if the format has two — and compare the resulting pixels. Differences
+	/// beyond `PxDiff::DECODER_TOLERANCE` hint at a bug in one or the other.
+	///
+	/// Only the first frame or page is considered, and orientation and color
+	/// management are left out of it, as those aren't up to the decoders.
+	///
+	/// Returns `None` if there is no alternate backend for the format.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if either backend fails to decode the image.
+	pub fn cross_check(src: &[u8], format: PxKind, flags: u16)
+	-> Result<Option<PxDiff>, PxsumError> {
+		let Some(theirs) = cross::decode(format, src) else { return Ok(None); };
+		let deep = Checksum::DEEP == flags & Checksum::DEEP;
+		let flags = flags & ! (Checksum::ALL_FRAMES | Checksum::ORIENTED | Checksum::COLOR_MANAGED);
+		let ours = Self::from_dynamic(format.decode(src, flags)?, deep)?;
+		let theirs = Self::from_dynamic(theirs?, deep)?;
+		Ok(Some(ours.diff(&theirs)))
+	}
 
+	/// # From Decoded Image.
+	///
+	/// Tease out the pixels of a single decoded image.
+	fn from_dynamic(img: DynamicImage, deep: bool) -> Result<Self, PxsumError> {
 		// If we know there's no alpha channel in the original, make a note of
 		// it as it can save us some time later on.
-		let no_alpha = matches!(img,
-			DynamicImage::ImageLuma8(_) |
-			DynamicImage::ImageRgb8(_) |
-			DynamicImage::ImageLuma16(_) |
-			DynamicImage::ImageRgb16(_) |
-			DynamicImage::ImageRgb32F(_)
-		);
+		let no_alpha = no_alpha(&img);
 
 		// Convert to RGBA and tease out just the pixel data.
-		let buf: Vec<u8> = img.into_rgba8().into_vec();
+		let (width, height) = (img.width(), img.height());
+		let (buf, px) = into_pixels(img, deep);
 		let len = buf.len();
 
 		// Check the counts, but we should be good here.
 		if len == 0 { Err(PxsumError::NoData) }
-		else if len % 4 == 0 { Ok(Self { buf, no_alpha }) }
+		else if len.is_multiple_of(usize::from(px)) {
+			Ok(Self {
+				buf,
+				no_alpha,
+				width,
+				height,
+				frames: 1,
+				loops: 0,
+				delays: Vec::new(),
+				sizes: Vec::new(),
+				px,
+				background: [0; 3],
+				tiles: Vec::new(),
+				sensor: false,
+			})
+		}
 		else { Err(PxsumError::Decode) }
 	}
 
+	/// # Decode Page.
+	///
+	/// Same as `PxImage::new`, but for a single sub-image — page or entry —
+	/// of a multi-image TIFF or ICO container. See `PxKind::pages` for the
+	/// count.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the page does not exist or cannot be
+	/// decoded, or has an invalid pixel count.
+	pub fn new_page(src: &[u8], format: PxKind, flags: u16, page: usize)
+	-> Result<Self, PxsumError> {
+		let raw = format.extract_page(src, page).ok_or(PxsumError::Decode)?;
+		Self::new(&raw, format, flags & ! (Checksum::ALL_FRAMES | Checksum::TIMING))
+	}
+
+	/// # From Animation Frames.
+	///
+	/// Stitch the (composited) frames of an animation together into a single
+	/// buffer, one after the other.
+	fn from_frames(frames: Vec<Frame>, loops: LoopCount, deep: bool)
+	-> Result<Self, PxsumError> {
+		let (width, height) = frames[0].buffer().dimensions();
+		let frames_len = u32::try_from(frames.len()).map_err(|_| PxsumError::Decode)?;
+		let loops = match loops {
+			LoopCount::Infinite => 0,
+			LoopCount::Finite(n) => n.get(),
+		};
+
+		let mut buf = Vec::new();
+		let mut delays = Vec::with_capacity(frames.len());
+		let mut px = 4;
+		for frame in frames {
+			// Delays are reduced so equivalent ratios hash the same way.
+			let (n, d) = frame.delay().numer_denom_ms();
+			let gcd = gcd(n, d);
+			delays.push((n / gcd, d / gcd));
+
+			// The frames should all be the same size, but let's make sure.
+			let img = frame.into_buffer();
+			if img.dimensions() != (width, height) { return Err(PxsumError::Decode); }
+
+			let (b, p) = into_pixels(DynamicImage::ImageRgba8(img), deep);
+			buf.extend_from_slice(&b);
+			px = p;
+		}
+
+		if buf.is_empty() { Err(PxsumError::NoData) }
+		else {
+			Ok(Self {
+				buf,
+				no_alpha: false,
+				width,
+				height,
+				frames: frames_len,
+				loops,
+				delays,
+				sizes: Vec::new(),
+				px,
+				background: [0; 3],
+				tiles: Vec::new(),
+				sensor: false,
+			})
+		}
+	}
+
+	/// # From Pages.
+	///
+	/// Decode and stitch together every sub-image of a TIFF or ICO
+	/// container, one after the other. Unlike animation frames, these can
+	/// vary in size, so each one's dimensions are recorded too.
+	fn from_pages(src: &[u8], format: PxKind, flags: u16)
+	-> Result<Self, PxsumError> {
+		let deep = Checksum::DEEP == flags & Checksum::DEEP;
+		let pages = format.pages(src);
+		let frames = u32::try_from(pages).map_err(|_| PxsumError::Decode)?;
+
+		let mut buf = Vec::new();
+		let mut sizes = Vec::with_capacity(pages);
+		let mut all_no_alpha = true;
+		let mut px = 0;
+		for page in 0..pages {
+			let raw = format.extract_page(src, page).ok_or(PxsumError::Decode)?;
+			let img = format.decode(&raw, flags)?;
+			sizes.push((img.width(), img.height()));
+			all_no_alpha &= no_alpha(&img);
+
+			// Deep pages have to agree on their precision.
+			let (b, p) = into_pixels(img, deep);
+			if px != 0 && px != p { return Err(PxsumError::Unsupported); }
+			if b.is_empty() || ! b.len().is_multiple_of(usize::from(p)) {
+				return Err(PxsumError::Decode);
+			}
+			buf.extend_from_slice(&b);
+			px = p;
+		}
+
+		let (width, height) = sizes[0];
+		Ok(Self {
+			buf,
+			no_alpha: all_no_alpha,
+			width,
+			height,
+			frames,
+			loops: 0,
+			delays: Vec::new(),
+			sizes,
+			px,
+			background: [0; 3],
+			tiles: Vec::new(),
+			sensor: false,
+		})
+	}
+
+	#[must_use]
+	/// # With Background.
+	///
+	/// Set the (RGB) background color to composite the image over when
+	/// checksumming with the `Checksum::FLATTEN` alpha policy. The default is
+	/// black.
+	pub const fn with_background(mut self, background: [u8; 3]) -> Self {
+		self.background = background;
+		self
+	}
+
+	#[must_use]
+	/// # Width.
+	pub const fn width(&self) -> u32 { self.width }
+
+	#[must_use]
+	/// # Height.
+	pub const fn height(&self) -> u32 { self.height }
+
+	#[must_use]
+	/// # Frame Count.
+	pub const fn frames(&self) -> u32 { self.frames }
+
+	#[must_use]
+	/// # Tile Digests.
+	///
+	/// Return the (truncated) digests of the image's tiles, one per 64x64
+	/// square of each frame or page, in order. These are only populated by
+	/// `PxImage::checksum` with the `Checksum::TILED` flag.
+	pub fn tiles(&self) -> &[[u8; 8]] { &self.tiles }
+
+	#[must_use]
+	/// # Changed Regions.
+	///
+	/// Compare the tile digests against those of a `reference` checksum —
+	/// e.g. an earlier `PxImage::tiles` — and return the bounding boxes of
+	/// the (contiguous) tiles that differ.
+	///
+	/// If either set of tiles is missing or the layouts differ, the images
+	/// aren't comparable at this level and nothing is returned.
+	pub fn changed_regions(&self, reference: &[[u8; 8]]) -> Vec<TileRegion> {
+		tile::changed_regions(&self.planes(), &self.tiles, reference)
+	}
+
+	#[must_use]
+	/// # Pixel Difference.
+	///
+	/// Compare the pixels against `other`, returning the number that differ,
+	/// by how much, and where.
+	///
+	/// Both images should be decoded (and alpha-normalized) the same way for
+	/// the results to mean anything; see `Checksum::decode_against`.
+	pub fn diff(&self, other: &Self) -> PxDiff { diff::compare(self, other) }
+
+	#[must_use]
+	/// # Pixel Difference Image.
+	///
+	/// Render the differences between `self` and `other` as an RGBA8 image,
+	/// with unchanged pixels drawn as a faded grayscale copy of `self` and
+	/// changed ones in red, brighter for bigger deltas. Frames and pages are
+	/// stacked top to bottom.
+	///
+	/// Returns `None` if the images aren't comparable.
+	pub fn diff_image(&self, other: &Self) -> Option<image::RgbaImage> {
+		diff::visualize(self, other)
+	}
+
+	/// # Plane Dimensions.
+	///
+	/// Return the dimensions of each frame or page in the buffer.
+	fn planes(&self) -> Vec<(u32, u32)> {
+		if self.sizes.is_empty() {
+			vec![(self.width, self.height); self.frames as usize]
+		}
+		else { self.sizes.clone() }
+	}
+
+	#[must_use]
+	/// # Perceptual Fingerprint.
+	///
+	/// Calculate and return a 64-bit [difference hash](https://www.hackerfactor.com/blog/index.php?/archives/529-Kind-of-Like-That.html)
+	/// of the (first frame of the) image.
+	///
+	/// Unlike checksums, fingerprints are meant to be fuzzy; resized or
+	/// recompressed copies of an image should produce the same or similar
+	/// values, making them useful for finding near-duplicates. (See
+	/// `SimilarityIndex` for that.)
+	///
+	/// Note this should be called _before_ any loose checksumming, as that
+	/// normalizes invisible pixels in place.
+	pub fn fingerprint(&self) -> u64 {
+		/// # Grid Width.
+		const W: usize = 9;
+
+		/// # Grid Height.
+		const H: usize = 8;
+
+		// Sum up the (alpha-weighted) luma for each grid cell.
+		let width = self.width as usize;
+		let height = self.height as usize;
+		let px = usize::from(self.px);
+		let mut sums = [0.0_f64; W * H];
+		let mut counts = [0_u32; W * H];
+		for (y, row) in self.buf.chunks_exact(width * px).take(height).enumerate() {
+			let gy = y * H / height;
+			for (x, pixel) in row.chunks_exact(px).enumerate() {
+				let idx = gy * W + x * W / width;
+				sums[idx] += luma(pixel);
+				counts[idx] += 1;
+			}
+		}
+
+		// Average them. If the image is smaller than the grid, there might be
+		// some gaps; those can borrow their neighbors' values.
+		let mut grid = [0.0_f64; W * H];
+		for idx in 0..W * H {
+			let src =
+				if counts[idx] != 0 { idx }
+				else {
+					let (gy, gx) = (idx / W, idx % W);
+					let y = (gy * height / H).min(height - 1) * H / height;
+					let x = (gx * width / W).min(width - 1) * W / width;
+					y * W + x
+				};
+			if counts[src] != 0 { grid[idx] = sums[src] / f64::from(counts[src]); }
+		}
+
+		// Set a bit for each left-to-right increase.
+		let mut out = 0_u64;
+		for row in grid.chunks_exact(W) {
+			for pair in row.windows(2) {
+				out <<= 1;
+				if pair[0] < pair[1] { out |= 1; }
+			}
+		}
+		out
+	}
+
+	/// # Apply Alpha Policy.
+	///
+	/// Normalize the pixels in place according to the alpha policy in
+	/// `flags` (e.g. `Checksum::PREMULTIPLIED`). This is done automatically by
+	/// `PxImage::checksum`, but can be useful for other sorts of comparisons.
+	///
+	/// As with checksumming, subsequent calls on the same instance should use
+	/// the same policy.
+	pub fn apply_alpha_policy(&mut self, flags: u16) {
+		// Legacy checksums only know loose and strict.
+		let policy =
+			if Checksum::V2 == flags & Checksum::V2 { flags & Checksum::ALPHA_POLICY }
+			else { flags & Checksum::STRICT };
+
+		// Images without alpha are unaffected by the policies.
+		if ! self.no_alpha {
+			match policy {
+				Checksum::STRICT => {},
+				Checksum::PREMULTIPLIED => map_pixels(
+					&mut self.buf,
+					self.px,
+					|[r, g, b, a]| [r * a, g * a, b * a, a],
+				),
+				Checksum::IGNORE_ALPHA => map_pixels(
+					&mut self.buf,
+					self.px,
+					|[r, g, b, _]| [r, g, b, 1.0],
+				),
+				Checksum::FLATTEN => {
+					let [br, bg, bb] = self.background.map(|c| f64::from(c) / 255.0);
+					map_pixels(
+						&mut self.buf,
+						self.px,
+						|[r, g, b, a]| {
+							let a = a.clamp(0.0, 1.0);
+							let rest = 1.0 - a;
+							[
+								r.mul_add(a, br * rest),
+								g.mul_add(a, bg * rest),
+								b.mul_add(a, bb * rest),
+								1.0,
+							]
+						},
+					);
+				},
+				// For loose comparisons, replace invisible pixels with their
+				// index so color drift won't affect the checksum. (This is
+				// also the fallback for reserved patterns.)
+				_ => {
+					let px = usize::from(self.px);
+					let mut i = Wrapping(0_u32);
+					#[expect(clippy::explicit_counter_loop, reason = "Wrapping doesn't implement Step.")]
+					for chunk in self.buf.chunks_exact_mut(px) {
+						// The alpha channel is the last quarter of the pixel.
+						if chunk[px - px / 4..].iter().all(|&b| b == 0) {
+							chunk.fill(0);
+							chunk[..4].copy_from_slice(i.0.to_le_bytes().as_slice());
+						}
+						i += 1;
+					}
+				},
+			}
+		}
+	}
+
+	#[must_use]
 	/// # Hash Pixels.
 	///
-	/// Calculate and return a checksum of the pixel data.
-	pub(super) fn into_checksum(self, strict: bool) -> [u8; 32] {
-		// Destructure.
-		let Self { mut buf, no_alpha } = self;
-
-		// For loose comparisons, replace invisible pixels with their index so
-		// color drift won't affect the checksum.
-		if ! strict && ! no_alpha {
-			let mut i = Wrapping(0_u32);
-			for chunk in buf.chunks_exact_mut(4) {
-				if chunk[3] == 0 {
-					chunk.copy_from_slice(i.0.to_le_bytes().as_slice());
+	/// Calculate and return a checksum of the pixel data, using the
+	/// `Checksum` mode bits in `flags` (e.g. `Checksum::STRICT`). These should
+	/// match the flags passed to `PxImage::new`.
+	///
+	/// Note that for every alpha policy other than strict, the pixels are
+	/// normalized in place, so any subsequent calls on the same instance
+	/// should use the same policy.
+	pub fn checksum(&mut self, flags: u16) -> [u8; 32] {
+		// The frame-related flags only stick for actual animations, keeping
+		// static checksums the same either way.
+		let flags =
+			if self.frames < 2 { flags & ! (Checksum::ALL_FRAMES | Checksum::TIMING) }
+			else { flags };
+
+		// Ditto for the sensor flag and RAWs.
+		let flags =
+			if self.sensor { flags }
+			else { flags & ! Checksum::RAW_SENSOR };
+
+		// Normalize the alpha per the policy.
+		let v2 = Checksum::V2 == flags & Checksum::V2;
+		self.apply_alpha_policy(flags);
+
+		// Hash the pixel data, prefixed with the dimensions for V2, and the
+		// pixel size for deep.
+		let mut hasher = blake3::Hasher::new();
+		if v2 {
+			hasher.update(self.width.to_le_bytes().as_slice());
+			hasher.update(self.height.to_le_bytes().as_slice());
+			hasher.update(self.frames.to_le_bytes().as_slice());
+			if Checksum::DEEP == flags & Checksum::DEEP {
+				hasher.update(&[self.px]);
+			}
+			for (w, h) in &self.sizes {
+				hasher.update(w.to_le_bytes().as_slice());
+				hasher.update(h.to_le_bytes().as_slice());
+			}
+			if Checksum::TIMING == flags & Checksum::TIMING {
+				hasher.update(self.loops.to_le_bytes().as_slice());
+				for (n, d) in &self.delays {
+					hasher.update(n.to_le_bytes().as_slice());
+					hasher.update(d.to_le_bytes().as_slice());
 				}
-				i += 1;
 			}
 		}
 
-		// Hash the pixel data!
-		let mut hasher = blake3::Hasher::new();
-		hasher.update(buf.as_slice());
+		// Tiled checksums hash the pixels by the tile, then the tiles as a
+		// Merkle tree.
+		self.tiles.truncate(0);
+		if v2 && Checksum::TILED == flags & Checksum::TILED {
+			let leaves = tile::hash_tiles(&self.buf, usize::from(self.px), &self.planes());
+			self.tiles.extend(leaves.iter().map(|l| [l[0], l[1], l[2], l[3], l[4], l[5], l[6], l[7]]));
+			hasher.update(tile::merkle_root(leaves).as_slice());
+		}
+		else { hasher.update(self.buf.as_slice()); }
 		let mut chk = <[u8; 32]>::from(hasher.finalize());
 
-		// Steal one bit from the first byte to serve as a strictness indicator.
-		if strict { chk[0] |= Checksum::STRICT; }
-		else { chk[0] &= ! Checksum::STRICT; }
+		// V2 checksums steal a few bits from the first two bytes to record
+		// the mode(s) used.
+		if v2 {
+			let [mask_a, _] = Checksum::FLAGS.to_le_bytes();
+			let [a, b] = ((flags & Checksum::FLAGS) | Checksum::V2).to_le_bytes();
+			chk[0] = (chk[0] & ! mask_a) | a;
+
+			// The unused bits of the second byte and the three background
+			// bytes are zeroed (unless flattened), giving verification a way
+			// to tell V2 checksums from legacy ones with the V2 bit set.
+			chk[1] = b;
+			[chk[2], chk[3], chk[4]] =
+				if Checksum::FLATTEN == flags & Checksum::ALPHA_POLICY { self.background }
+				else { [0; 3] };
+		}
+		// Legacy checksums steal just the one, for strictness.
+		else {
+			let [bit, _] = Checksum::STRICT.to_le_bytes();
+			if Checksum::STRICT == flags & Checksum::STRICT { chk[0] |= bit; }
+			else { chk[0] &= ! bit; }
+		}
 
 		chk
 	}
+
+	#[must_use]
+	/// # Hash Pixels (Consuming).
+	///
+	/// Same as `PxImage::checksum`, but consumes `self`.
+	pub fn into_checksum(mut self, flags: u16) -> [u8; 32] { self.checksum(flags) }
+}
+
+
+
+#[cfg(feature = "avif")]
+/// # AVIF: High Bit Depth?
+///
+/// Scan the AV1 codec configuration (`av1C`) box for the `high_bitdepth` flag,
+/// which is set for 10- and 12-bit images.
+fn avif_high_bitdepth(src: &[u8]) -> bool {
+	src.windows(7).any(|w| matches!(
+		w,
+		[b'a', b'v', b'1', b'C', 0x81, _, flags] if 0 != flags & 0b0100_0000
+	))
+}
+
+#[cfg(feature = "heif")]
+#[cold]
+/// # Decode HEIF.
+///
+/// Not a popular format (outside of iPhones), hence cold.
+///
+/// Grid images — the norm for HEICs straight off a phone, which are
+/// stored as a mosaic of 512x512 tiles — are reassembled by libheif
+/// itself, so we only ever need to ask for the primary image.
+///
+/// Ditto for the transformations. Ignoring them would also skip the clean
+/// aperture crop, leaving the coding padding in place, and libheif can't
+/// decode some (alpha) grids without them anyway, so instead, any rotation
+/// or mirroring is undone afterward if orientation isn't wanted.
+fn decode_heif(src: &[u8], oriented: bool, managed: bool)
+-> Result<DynamicImage, PxsumError> {
+	use libheif_rs::{
+		ColorSpace,
+		HeifContext,
+		LibHeif,
+		RgbChroma,
+	};
+
+	let ctx = HeifContext::read_from_bytes(src).map_err(|_| PxsumError::Decode)?;
+	let handle = ctx.primary_image_handle().map_err(|_| PxsumError::Decode)?;
+	let bits = handle.luma_bits_per_pixel();
+	let alpha = handle.has_alpha_channel();
+	let chroma = match (8 < bits, alpha) {
+		(false, false) => RgbChroma::Rgb,
+		(false, true) => RgbChroma::Rgba,
+		(true, false) => RgbChroma::HdrRgbLe,
+		(true, true) => RgbChroma::HdrRgbaLe,
+	};
+
+	let raw = LibHeif::new()
+		.decode(&handle, ColorSpace::Rgb(chroma), None)
+		.map_err(|_| PxsumError::Decode)?;
+	let plane = raw.planes().interleaved.ok_or(PxsumError::Decode)?;
+	let mut img = heif_image(plane.data, plane.width, plane.height, plane.stride, bits, alpha)
+		.ok_or(PxsumError::Decode)?;
+
+	// Undo the rotation and mirroring, in reverse.
+	if ! oriented {
+		for o in heif_orientation(src).into_iter().rev() {
+			img.apply_orientation(match o {
+				Orientation::Rotate90 => Orientation::Rotate270,
+				Orientation::Rotate270 => Orientation::Rotate90,
+				o => o,
+			});
+		}
+	}
+
+	if managed {
+		if let Some(profile) = color::heif_profile(src)? {
+			img = color::into_srgb(img, &profile)?;
+		}
+	}
+
+	Ok(img)
+}
+
+#[cfg(feature = "heif")]
+/// # HEIF Pixels.
+///
+/// Copy the decoded rows — which may be padded — into a tightly-packed RGB(A)
+/// buffer. High bit depth samples are little-endian, and are scaled up to the
+/// full sixteen bits so they compare cleanly against other formats.
+fn heif_image(data: &[u8], width: u32, height: u32, stride: usize, bits: u8, alpha: bool)
+-> Option<DynamicImage> {
+	let deep = 8 < bits;
+	let row = usize::try_from(width).ok()? *
+		if alpha { 4 } else { 3 } *
+		if deep { 2 } else { 1 };
+	let rows = usize::try_from(height).ok()?;
+	if row == 0 || stride < row { return None; }
+
+	let mut buf = Vec::with_capacity(row * rows);
+	for chunk in data.chunks(stride).take(rows) {
+		buf.extend_from_slice(chunk.get(..row)?);
+	}
+	if buf.len() != row * rows { return None; }
+
+	if deep {
+		let (up, down) = (16 - u32::from(bits.min(16)), u32::from(bits.min(16)) * 2 - 16);
+		let buf: Vec<u16> = buf.chunks_exact(2)
+			.map(|v| {
+				let v = u16::from_le_bytes([v[0], v[1]]);
+				(v << up) | v.checked_shr(down).unwrap_or(0)
+			})
+			.collect();
+		if alpha { image::ImageBuffer::from_vec(width, height, buf).map(DynamicImage::ImageRgba16) }
+		else { image::ImageBuffer::from_vec(width, height, buf).map(DynamicImage::ImageRgb16) }
+	}
+	else if alpha { image::ImageBuffer::from_vec(width, height, buf).map(DynamicImage::ImageRgba8) }
+	else { image::ImageBuffer::from_vec(width, height, buf).map(DynamicImage::ImageRgb8) }
+}
+
+#[cfg(any(feature = "avif", feature = "heif"))]
+/// # HEIF Orientation.
+///
+/// Return the transformations described by the HEIF image rotation (`irot`)
+/// and mirroring (`imir`) properties, in the order they should be applied.
+///
+/// Note that HEIF rotations are counter-clockwise, and mirror mode zero
+/// exchanges the top and bottom, while mode one exchanges the left and right.
+fn heif_orientation(src: &[u8]) -> Vec<Orientation> {
+	let mut out = Vec::new();
+
+	// The properties are tiny: a four-byte size (9), the type, then a single
+	// byte with the value in the low bit(s).
+	for w in src.windows(9) {
+		if let [0, 0, 0, 9, b'i', b'r', b'o', b't', angle] = *w {
+			match angle & 0b11 {
+				1 => { out.push(Orientation::Rotate270); },
+				2 => { out.push(Orientation::Rotate180); },
+				3 => { out.push(Orientation::Rotate90); },
+				_ => {},
+			}
+			break;
+		}
+	}
+	for w in src.windows(9) {
+		if let [0, 0, 0, 9, b'i', b'm', b'i', b'r', mode] = *w {
+			out.push(
+				if 0 == mode & 1 { Orientation::FlipVertical }
+				else { Orientation::FlipHorizontal }
+			);
+			break;
+		}
+	}
+
+	out
+}
+
+/// # ICO Entries.
+///
+/// Return the raw sixteen-byte directory entries of an ICO, along with the
+/// offsets of their image data, in the order they appear.
+fn ico_entries(src: &[u8]) -> Vec<([u8; 16], usize)> {
+	let Some([0, 0, 1, 0, a, b, rest @ ..]) = src.get(..) else { return Vec::new(); };
+	let count = usize::from(u16::from_le_bytes([*a, *b]));
+	rest.chunks_exact(16)
+		.take(count)
+		.filter_map(|entry| {
+			let entry: [u8; 16] = entry.try_into().ok()?;
+			let offset = u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]);
+			Some((entry, usize::try_from(offset).ok()?))
+		})
+		.collect()
+}
+
+/// # TIFF Image File Directories.
+///
+/// Walk the chain of (classic) TIFF image file directories, returning the
+/// offset of each. The walk is aborted early if the chain loops back on
+/// itself or wanders out of bounds.
+fn tiff_ifds(src: &[u8]) -> Vec<u32> {
+	/// # Sanity Limit.
+	const MAX_PAGES: usize = 4096;
+
+	let read_u16: fn([u8; 2]) -> u16;
+	let read_u32: fn([u8; 4]) -> u32;
+	match src.get(..4) {
+		Some(b"II*\0") => {
+			read_u16 = u16::from_le_bytes;
+			read_u32 = u32::from_le_bytes;
+		},
+		Some(b"MM\0*") => {
+			read_u16 = u16::from_be_bytes;
+			read_u32 = u32::from_be_bytes;
+		},
+		_ => return Vec::new(),
+	}
+
+	let mut out = Vec::new();
+	let mut next = src.get(4..8).and_then(|b| b.try_into().ok()).map_or(0, read_u32);
+	while next != 0 && out.len() < MAX_PAGES && ! out.contains(&next) {
+		// The directory is a two-byte entry count, the twelve-byte entries,
+		// then the four-byte offset of the next directory.
+		let Ok(pos) = usize::try_from(next) else { break; };
+		let Some(count) = src.get(pos..pos + 2).and_then(|b| b.try_into().ok()).map(read_u16)
+		else { break; };
+		let end = pos + 2 + usize::from(count) * 12;
+		let Some(after) = src.get(end..end + 4).and_then(|b| b.try_into().ok()).map(read_u32)
+		else { break; };
+
+		out.push(next);
+		next = after;
+	}
+	out
+}
+
+/// # Greatest Common Divisor.
+///
+/// This is used to reduce frame delay ratios. Zero is treated as one to keep
+/// the division safe.
+const fn gcd(mut a: u32, mut b: u32) -> u32 {
+	while b != 0 {
+		let t = b;
+		b = a % b;
+		a = t;
+	}
+	if a == 0 { 1 } else { a }
+}
+
+/// # Into Pixels.
+///
+/// Convert the image to RGBA8 — or in deep mode, RGBA16 or RGBA32F — and
+/// return the raw (little endian) pixel bytes along with the number of bytes
+/// per pixel.
+fn into_pixels(img: DynamicImage, deep: bool) -> (Vec<u8>, u8) {
+	// Floats stay floats.
+	if deep && matches!(img, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)) {
+		let buf = img.into_rgba32f().into_vec().into_iter()
+			.flat_map(|f| normalize_f32(f).to_le_bytes())
+			.collect();
+		(buf, 16)
+	}
+	// Everything else gets RGBA16.
+	else if deep {
+		let buf = img.into_rgba16().into_vec().into_iter()
+			.flat_map(u16::to_le_bytes)
+			.collect();
+		(buf, 8)
+	}
+	// Or RGBA8.
+	else { (img.into_rgba8().into_vec(), 4) }
+}
+
+/// # No Alpha?
+///
+/// Returns `true` if the decoded image has no alpha channel, which can save
+/// us some time later on.
+const fn no_alpha(img: &DynamicImage) -> bool {
+	matches!(img,
+		DynamicImage::ImageLuma8(_) |
+		DynamicImage::ImageRgb8(_) |
+		DynamicImage::ImageLuma16(_) |
+		DynamicImage::ImageRgb16(_) |
+		DynamicImage::ImageRgb32F(_)
+	)
+}
+
+/// # Luma.
+///
+/// Return the Rec. 601 luma of an RGBA8, RGBA16, or RGBA32F pixel, scaled by
+/// its alpha so invisible colors don't count.
+fn luma(pixel: &[u8]) -> f64 {
+	let Some([r, g, b, a]) = unpack(pixel) else { return 0.0; };
+	0.114_f64.mul_add(b, 0.299_f64.mul_add(r, 0.587 * g)) * a.clamp(0.0, 1.0)
+}
+
+/// # Unpack Pixel.
+///
+/// Return the channels of an RGBA8, RGBA16, or RGBA32F pixel as floats,
+/// normalized to `0.0..=1.0` for the integer types.
+fn unpack(pixel: &[u8]) -> Option<[f64; 4]> {
+	match *pixel {
+		[r, g, b, a] => Some([r, g, b, a].map(|v| f64::from(v) / 255.0)),
+		[r1, r2, g1, g2, b1, b2, a1, a2] => Some(
+			[[r1, r2], [g1, g2], [b1, b2], [a1, a2]]
+				.map(|v| f64::from(u16::from_le_bytes(v)) / 65_535.0)
+		),
+		[r1, r2, r3, r4, g1, g2, g3, g4, b1, b2, b3, b4, a1, a2, a3, a4] => Some(
+			[[r1, r2, r3, r4], [g1, g2, g3, g4], [b1, b2, b3, b4], [a1, a2, a3, a4]]
+				.map(|v| f64::from(f32::from_le_bytes(v)))
+		),
+		_ => None,
+	}
+}
+
+/// # Map Pixels.
+///
+/// Run each RGBA8, RGBA16, or RGBA32F pixel in `buf` through `cb` as
+/// normalized floats — `0.0..=1.0` for the integer types — and write the
+/// results back in the original format.
+fn map_pixels<F>(buf: &mut [u8], px: u8, cb: F)
+where F: Fn([f64; 4]) -> [f64; 4] {
+	/// # Quantize.
+	fn quantize(v: f64, max: f64) -> f64 { (v.clamp(0.0, 1.0) * max).round() }
+
+	let px = usize::from(px);
+	for chunk in buf.chunks_exact_mut(px) {
+		let Some(old) = unpack(chunk) else { return; };
+		let new = cb(old);
+		match px {
+			4 => for (dst, v) in chunk.iter_mut().zip(new) {
+				#[expect(clippy::cast_possible_truncation, reason = "Values are clamped.")]
+				#[expect(clippy::cast_sign_loss, reason = "Values are clamped.")]
+				{ *dst = quantize(v, 255.0) as u8; }
+			},
+			8 => for (pair, v) in chunk.chunks_exact_mut(2).zip(new) {
+				#[expect(clippy::cast_possible_truncation, reason = "Values are clamped.")]
+				#[expect(clippy::cast_sign_loss, reason = "Values are clamped.")]
+				pair.copy_from_slice((quantize(v, 65_535.0) as u16).to_le_bytes().as_slice());
+			},
+			_ => for (quad, v) in chunk.chunks_exact_mut(4).zip(new) {
+				#[expect(clippy::cast_possible_truncation, reason = "Values are from f32.")]
+				quad.copy_from_slice(normalize_f32(v as f32).to_le_bytes().as_slice());
+			},
+		}
+	}
+}
+
+#[inline]
+/// # Normalize Float.
+///
+/// Collapse the various zeroes and NaNs to a single representation apiece so
+/// they don't throw off the hash.
+const fn normalize_f32(f: f32) -> f32 {
+	if f.is_nan() { f32::NAN }
+	else if f == 0.0 { 0.0 }
+	else { f }
 }
 
 
@@ -242,13 +1509,22 @@ impl PxImage {
 #[cfg(test)]
 mod test {
 	use super::*;
+	use crate::distance;
+
+	/// # Checksum Mode Bits.
+	fn mode(chk: &[u8; 32]) -> u16 {
+		u16::from_le_bytes([chk[0], chk[1]]) & Checksum::FLAGS
+	}
 
 	/// # Test Assets / Expected Kinds.
 	const KINDS: &[(&str, Option<PxKind>)] = &[
+		#[cfg(feature = "jpeg2k")]
 		("skel/assets/ace.jp2", Some(PxKind::Jpeg2k)),
+		#[cfg(feature = "jxl")]
 		("skel/assets/ace.jxl", Some(PxKind::JpegXl)),
 		("skel/assets/ace.webp", Some(PxKind::WebP)),
 		("skel/assets/ash.jpg", Some(PxKind::Jpeg)),
+		#[cfg(feature = "avif")]
 		("skel/assets/atom.avif", Some(PxKind::Avif)),
 		("skel/assets/atom.png", Some(PxKind::Png)),
 		("skel/assets/carl.jpg", Some(PxKind::Jpeg)),
@@ -271,12 +1547,18 @@ mod test {
 	];
 
 	/// # Our Types to Image Crate Types.
-	const IMAGE_KINDS: [(PxKind, ImageFormat); 7] = [
+	const IMAGE_KINDS: [(PxKind, ImageFormat); 13] = [
 		(PxKind::Bmp, ImageFormat::Bmp),
+		(PxKind::Farbfeld, ImageFormat::Farbfeld),
 		(PxKind::Gif, ImageFormat::Gif),
+		(PxKind::Hdr, ImageFormat::Hdr),
 		(PxKind::Ico, ImageFormat::Ico),
 		(PxKind::Jpeg, ImageFormat::Jpeg),
+		(PxKind::OpenExr, ImageFormat::OpenExr),
 		(PxKind::Png, ImageFormat::Png),
+		(PxKind::Pnm, ImageFormat::Pnm),
+		(PxKind::Qoi, ImageFormat::Qoi),
+		(PxKind::Tga, ImageFormat::Tga),
 		(PxKind::Tiff, ImageFormat::Tiff),
 		(PxKind::WebP, ImageFormat::WebP),
 	];
@@ -298,6 +1580,510 @@ mod test {
 		}
 	}
 
+	#[test]
+	/// # Test Dimension Hashing.
+	///
+	/// Legacy checksums only consider the flat pixel buffer, so differently
+	/// shaped images with the same data collide; V2 checksums should not.
+	fn t_dimensions() {
+		let buf: Vec<u8> = (0..64_u8).collect();
+		let img = |width, height| PxImage {
+			buf: buf.clone(),
+			no_alpha: true,
+			width,
+			height,
+			frames: 1,
+			loops: 0,
+			delays: Vec::new(),
+			sizes: Vec::new(),
+			px: 4,
+			background: [0; 3],
+			tiles: Vec::new(),
+			sensor: false,
+		};
+
+		for strict in [0, Checksum::STRICT] {
+			assert_eq!(
+				img(2, 8).into_checksum(strict),
+				img(4, 4).into_checksum(strict),
+				"Legacy checksums should collide.",
+			);
+
+			let a = img(2, 8).into_checksum(strict | Checksum::V2);
+			let b = img(4, 4).into_checksum(strict | Checksum::V2);
+			assert_ne!(a, b, "V2 checksums should not collide.");
+			assert_eq!(mode(&a) & Checksum::V2, Checksum::V2, "Missing V2 flag.");
+			assert_eq!(mode(&a) & Checksum::STRICT, strict, "Wrong strictness.");
+		}
+	}
+
+	#[test]
+	/// # Test Alpha Policies.
+	///
+	/// Each policy should only forgive the sort of alpha-related differences
+	/// it is meant to.
+	fn t_alpha() {
+		use image::{
+			ImageBuffer,
+			Rgb,
+			Rgba,
+			RgbaImage,
+		};
+		use std::io::Cursor;
+
+		/// # Encode PNG.
+		fn encode(img: &DynamicImage) -> Vec<u8> {
+			let mut out = Cursor::new(Vec::new());
+			img.write_to(&mut out, ImageFormat::Png).expect("PNG encoding failed.");
+			out.into_inner()
+		}
+
+		/// # Checksum.
+		fn chk(src: &[u8], flags: u16, background: [u8; 3]) -> [u8; 32] {
+			let flags = flags | Checksum::V2;
+			PxImage::new(src, PxKind::Png, flags).expect("Decode failed.")
+				.with_background(background)
+				.into_checksum(flags)
+		}
+
+		// A semi-transparent image, and a copy that has been round-tripped
+		// through premultiplied storage.
+		let original: RgbaImage = ImageBuffer::from_fn(8, 8, |x, y| {
+			let v = u8::try_from(x * 32 + y).unwrap();
+			Rgba([v, 255 - v, v / 2, u8::try_from(y * 8 + 1).unwrap()])
+		});
+		let drifted: RgbaImage = ImageBuffer::from_fn(8, 8, |x, y| {
+			let Rgba([r, g, b, a]) = *original.get_pixel(x, y);
+			let trip = |c: u8| {
+				let pre = (u16::from(c) * u16::from(a) + 127) / 255;
+				u8::try_from(((pre * 255 + u16::from(a) / 2) / u16::from(a)).min(255)).unwrap()
+			};
+			Rgba([trip(r), trip(g), trip(b), a])
+		});
+		assert_ne!(original, drifted, "The round trip should have drifted.");
+		let (a, b) = (
+			encode(&DynamicImage::ImageRgba8(original.clone())),
+			encode(&DynamicImage::ImageRgba8(drifted)),
+		);
+		for flags in [Checksum::LOOSE, Checksum::STRICT, Checksum::IGNORE_ALPHA] {
+			assert_ne!(chk(&a, flags, [0; 3]), chk(&b, flags, [0; 3]), "Drift should matter.");
+		}
+		let chk_a = chk(&a, Checksum::PREMULTIPLIED, [0; 3]);
+		assert_eq!(chk_a, chk(&b, Checksum::PREMULTIPLIED, [0; 3]), "Premultiplied checksums should match.");
+		assert_eq!(mode(&chk_a) & Checksum::ALPHA_POLICY, Checksum::PREMULTIPLIED);
+
+		// An opaque (RGB) copy should match when alpha is ignored.
+		let opaque: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_fn(8, 8, |x, y| {
+			let Rgba([r, g, b, _]) = *original.get_pixel(x, y);
+			Rgb([r, g, b])
+		});
+		let opaque = encode(&DynamicImage::ImageRgb8(opaque));
+		assert_ne!(chk(&a, Checksum::LOOSE, [0; 3]), chk(&opaque, Checksum::LOOSE, [0; 3]));
+		assert_eq!(
+			chk(&a, Checksum::IGNORE_ALPHA, [0; 3]),
+			chk(&opaque, Checksum::IGNORE_ALPHA, [0; 3]),
+			"Ignore-alpha checksums should match.",
+		);
+
+		// A cut-out should match a copy pre-flattened over the same color.
+		let bg = [255, 128, 0];
+		let cutout: RgbaImage = ImageBuffer::from_fn(8, 8, |x, y| {
+			let v = u8::try_from(x * 32 + y).unwrap();
+			if x < 4 { Rgba([v, v, v, 255]) }
+			else { Rgba([v, 0, 255 - v, 0]) }
+		});
+		let flat: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_fn(8, 8, |x, y| {
+			let Rgba([r, g, b, a]) = *cutout.get_pixel(x, y);
+			if a == 0 { Rgb(bg) } else { Rgb([r, g, b]) }
+		});
+		let (cutout, flat) = (
+			encode(&DynamicImage::ImageRgba8(cutout)),
+			encode(&DynamicImage::ImageRgb8(flat)),
+		);
+		let chk_a = chk(&cutout, Checksum::FLATTEN, bg);
+		assert_eq!(chk_a, chk(&flat, Checksum::FLATTEN, bg), "Flattened checksums should match.");
+		assert_eq!(chk_a[2..5], bg, "Missing background.");
+		assert_eq!(mode(&chk_a) & Checksum::ALPHA_POLICY, Checksum::FLATTEN);
+		assert_ne!(
+			chk(&cutout, Checksum::FLATTEN, [0; 3]),
+			chk(&flat, Checksum::FLATTEN, [0; 3]),
+			"The background color should matter.",
+		);
+	}
+
+	#[test]
+	/// # Test Deep Hashing.
+	///
+	/// Sixteen-bit images that differ only in their low bits should match in
+	/// regular mode, but not deep mode.
+	fn t_deep() {
+		use image::{
+			ImageBuffer,
+			Rgba,
+		};
+		use std::io::Cursor;
+
+		let encode = |low: u16| {
+			let img: ImageBuffer<Rgba<u16>, Vec<u16>> = ImageBuffer::from_fn(4, 4, |x, y| {
+				let v = u16::try_from(x * 4 + y).unwrap() << 8;
+				Rgba([v | low, v, v, u16::MAX])
+			});
+			let mut out = Cursor::new(Vec::new());
+			DynamicImage::ImageRgba16(img).write_to(&mut out, ImageFormat::Png)
+				.expect("PNG encoding failed.");
+			out.into_inner()
+		};
+		let (a, b) = (encode(0), encode(1));
+
+		for flags in [Checksum::V2, Checksum::V2 | Checksum::STRICT] {
+			let chk_a = PxImage::new(&a, PxKind::Png, flags).expect("Decode failed.")
+				.into_checksum(flags);
+			let chk_b = PxImage::new(&b, PxKind::Png, flags).expect("Decode failed.")
+				.into_checksum(flags);
+			assert_eq!(chk_a, chk_b, "Regular checksums should match.");
+
+			let flags = flags | Checksum::DEEP;
+			let chk_a = PxImage::new(&a, PxKind::Png, flags).expect("Decode failed.")
+				.into_checksum(flags);
+			let chk_b = PxImage::new(&b, PxKind::Png, flags).expect("Decode failed.")
+				.into_checksum(flags);
+			assert_ne!(chk_a, chk_b, "Deep checksums should not match.");
+			assert_eq!(mode(&chk_a), flags, "Wrong flags.");
+		}
+	}
+
+	#[test]
+	/// # Test Animations.
+	///
+	/// Animations sharing a first frame should only match in first-frame
+	/// mode, and timing should only matter when requested.
+	fn t_frames() {
+		use image::{
+			Delay,
+			RgbaImage,
+			codecs::gif::{
+				GifEncoder,
+				Repeat,
+			},
+		};
+
+		let encode = |colors: &[u8], delay: u32| {
+			let mut out = Vec::new();
+			{
+				let mut enc = GifEncoder::new(&mut out);
+				enc.set_repeat(Repeat::Infinite).expect("GIF encoding failed.");
+				for &c in colors {
+					let img = RgbaImage::from_pixel(4, 4, image::Rgba([c, c, c, 255]));
+					let frame = Frame::from_parts(img, 0, 0, Delay::from_numer_denom_ms(delay, 1));
+					enc.encode_frame(frame).expect("GIF encoding failed.");
+				}
+			}
+			out
+		};
+		let chk = |src: &[u8], flags: u16| {
+			let flags = flags | Checksum::V2;
+			PxImage::new(src, PxKind::Gif, flags).expect("Decode failed.").into_checksum(flags)
+		};
+
+		let a = encode(&[0, 128], 100);
+		let b = encode(&[0, 255], 100);
+		let c = encode(&[0, 128], 200);
+		let still = encode(&[0], 100);
+
+		// The first frames are the same.
+		assert_eq!(chk(&a, 0), chk(&b, 0), "First frames should match.");
+
+		// But the rest aren't.
+		let all = chk(&a, Checksum::ALL_FRAMES);
+		assert_ne!(all, chk(&b, Checksum::ALL_FRAMES), "All frames should not match.");
+		assert_eq!(mode(&all), Checksum::ALL_FRAMES | Checksum::V2, "Wrong flags.");
+
+		// Timing only matters if we say so.
+		assert_eq!(all, chk(&c, Checksum::ALL_FRAMES), "Untimed frames should match.");
+		let timed = Checksum::ALL_FRAMES | Checksum::TIMING;
+		assert_ne!(chk(&a, timed), chk(&c, timed), "Timed frames should not match.");
+
+		// Static images are unaffected.
+		assert_eq!(chk(&still, 0), chk(&still, timed), "Static flags should not stick.");
+	}
+
+	#[test]
+	/// # Test Orientation.
+	///
+	/// An image with an EXIF rotation tag should match one with the rotation
+	/// baked in, but only in oriented mode.
+	fn t_oriented() {
+		use image::{
+			ExtendedColorType,
+			ImageEncoder,
+			RgbaImage,
+			codecs::png::PngEncoder,
+		};
+
+		let encode = |img: &RgbaImage, exif: Option<u8>| {
+			let mut out = Vec::new();
+			let mut enc = PngEncoder::new(&mut out);
+			if let Some(o) = exif {
+				let mut tiff = b"II*\0\x08\0\0\0\x01\0\x12\x01\x03\0\x01\0\0\0".to_vec();
+				tiff.extend_from_slice(&[o, 0, 0, 0, 0, 0, 0, 0]);
+				enc.set_exif_metadata(tiff).expect("EXIF failed.");
+			}
+			enc.write_image(img.as_raw(), img.width(), img.height(), ExtendedColorType::Rgba8)
+				.expect("PNG encoding failed.");
+			out
+		};
+		let chk = |src: &[u8], flags: u16| {
+			let flags = flags | Checksum::V2;
+			PxImage::new(src, PxKind::Png, flags).expect("Decode failed.").into_checksum(flags)
+		};
+
+		let img = RgbaImage::from_fn(4, 2, |x, y| {
+			let v = u8::try_from(x * 40 + y * 10).unwrap();
+			image::Rgba([v, v, v, 255])
+		});
+		let baked = encode(&image::imageops::rotate90(&img), None);
+		let tagged = encode(&img, Some(6)); // Rotate 90 clockwise.
+
+		assert_ne!(chk(&baked, 0), chk(&tagged, 0), "Unoriented checksums should not match.");
+		let oriented = chk(&baked, Checksum::ORIENTED);
+		assert_eq!(oriented, chk(&tagged, Checksum::ORIENTED), "Oriented checksums should match.");
+		assert_eq!(mode(&oriented) & Checksum::ORIENTED, Checksum::ORIENTED, "Missing flag.");
+
+		// HEIF properties.
+		#[cfg(any(feature = "avif", feature = "heif"))]
+		{
+			assert!(heif_orientation(b"nothing to see here").is_empty());
+			assert_eq!(
+				heif_orientation(b"..\0\0\0\x09imir\x01..\0\0\0\x09irot\x03.."),
+				[Orientation::Rotate90, Orientation::FlipHorizontal],
+			);
+			assert_eq!(
+				heif_orientation(b"..\0\0\0\x09imir\x00.."),
+				[Orientation::FlipVertical],
+			);
+		}
+	}
+
+	#[test]
+	/// # Test Color Management.
+	///
+	/// An image tagged with a wide-gamut profile should match a copy that was
+	/// converted to sRGB, but only in color-managed mode.
+	fn t_color() {
+		use image::{
+			ExtendedColorType,
+			ImageEncoder,
+			RgbImage,
+			codecs::png::PngEncoder,
+		};
+		use moxcms::{
+			Layout,
+			TransformOptions,
+		};
+
+		let encode = |raw: &[u8], color: ExtendedColorType, icc: Option<Vec<u8>>| {
+			let mut out = Vec::new();
+			let mut enc = PngEncoder::new(&mut out);
+			if let Some(icc) = icc { enc.set_icc_profile(icc).expect("ICC failed."); }
+			enc.write_image(raw, 8, 8, color).expect("PNG encoding failed.");
+			out
+		};
+		let chk = |src: &[u8], kind: PxKind, flags: u16| {
+			let flags = flags | Checksum::V2;
+			PxImage::new(src, kind, flags).expect("Decode failed.").into_checksum(flags)
+		};
+
+		// Adobe RGB vs. its sRGB equivalent.
+		let img = RgbImage::from_fn(8, 8, |x, y| {
+			let v = u8::try_from(x * 30 + y).unwrap();
+			image::Rgb([v, 255 - v, u8::try_from(y * 30).unwrap()])
+		});
+		let adobe = ColorProfile::new_adobe_rgb();
+		let mut converted = vec![0_u8; img.as_raw().len()];
+		adobe.create_transform_8bit(Layout::Rgb, &ColorProfile::new_srgb(), Layout::Rgb, TransformOptions::default())
+			.expect("Transform failed.")
+			.transform(img.as_raw(), &mut converted)
+			.expect("Transform failed.");
+		assert_ne!(img.as_raw(), &converted, "Conversion should change things.");
+
+		let tagged = encode(img.as_raw(), ExtendedColorType::Rgb8, Some(adobe.encode().expect("ICC failed.")));
+		let plain = encode(&converted, ExtendedColorType::Rgb8, None);
+		assert_ne!(chk(&tagged, PxKind::Png, 0), chk(&plain, PxKind::Png, 0), "Unmanaged checksums should not match.");
+		let managed = chk(&tagged, PxKind::Png, Checksum::COLOR_MANAGED);
+		assert_eq!(managed, chk(&plain, PxKind::Png, Checksum::COLOR_MANAGED), "Managed checksums should match.");
+		assert_eq!(mode(&managed) & Checksum::COLOR_MANAGED, Checksum::COLOR_MANAGED, "Missing flag.");
+
+		// Tagged and untagged sRGB should match even at higher precision.
+		let raw: Vec<u8> = (0..8 * 8 * 3).flat_map(|v: u16| (v * 331).to_be_bytes()).collect();
+		let srgb = ColorProfile::new_srgb().encode().expect("ICC failed.");
+		let flags = Checksum::COLOR_MANAGED | Checksum::DEEP;
+		assert_eq!(
+			chk(&encode(&raw, ExtendedColorType::Rgb16, Some(srgb)), PxKind::Png, flags),
+			chk(&encode(&raw, ExtendedColorType::Rgb16, None), PxKind::Png, flags),
+			"sRGB profiles should be ignored.",
+		);
+
+		// CMYK JPEGs get special treatment.
+		let raw = std::fs::read("skel/assets/cmyk.JPG").expect("Missing cmyk.JPG.");
+		assert_ne!(
+			chk(&raw, PxKind::Jpeg, 0),
+			chk(&raw, PxKind::Jpeg, Checksum::COLOR_MANAGED),
+			"CMYK should be converted differently.",
+		);
+	}
+
+	#[test]
+	/// # Test Multi-Image Containers.
+	///
+	/// Changes to any sub-image should affect the combined checksum, and each
+	/// sub-image should be individually addressable.
+	fn t_pages() {
+		use image::{
+			ExtendedColorType,
+			codecs::ico::{
+				IcoEncoder,
+				IcoFrame,
+			},
+		};
+
+		// Multi-size (RGBA) icons.
+		let ico = |sizes: &[(u8, u8)]| {
+			let frames: Vec<IcoFrame> = sizes.iter().map(|&(size, c)| {
+				let buf = vec![c; usize::from(size) * usize::from(size) * 4];
+				let size = u32::from(size);
+				IcoFrame::as_png(&buf, size, size, ExtendedColorType::Rgba8)
+					.expect("ICO frame failed.")
+			}).collect();
+			let mut out = Vec::new();
+			IcoEncoder::new(&mut out).encode_images(&frames).expect("ICO encoding failed.");
+			out
+		};
+
+		// Multi-page (uncompressed grayscale) TIFFs, the hard way.
+		let tiff = |sizes: &[(u8, u8)]| {
+			let mut out = b"II*\0\0\0\0\0".to_vec();
+			let mut next = 4;
+			for &(size, c) in sizes {
+				let data = u32::try_from(out.len()).unwrap();
+				let size = u32::from(size);
+				out.resize(out.len() + (size * size) as usize, c);
+				if out.len() % 2 == 1 { out.push(0); }
+
+				// Point the previous directory (or header) to this one.
+				let ifd = u32::try_from(out.len()).unwrap();
+				out[next..next + 4].copy_from_slice(ifd.to_le_bytes().as_slice());
+
+				out.extend_from_slice(9_u16.to_le_bytes().as_slice());
+				for (tag, kind, value) in [
+					(256_u16, 4_u16, size), // Width.
+					(257, 4, size),         // Height.
+					(258, 3, 8),            // Bits per sample.
+					(259, 3, 1),            // No compression.
+					(262, 3, 1),            // Black is zero.
+					(273, 4, data),         // Strip offset.
+					(277, 3, 1),            // Samples per pixel.
+					(278, 4, size),         // Rows per strip.
+					(279, 4, size * size),  // Strip length.
+				] {
+					out.extend_from_slice(tag.to_le_bytes().as_slice());
+					out.extend_from_slice(kind.to_le_bytes().as_slice());
+					out.extend_from_slice(1_u32.to_le_bytes().as_slice());
+					out.extend_from_slice(value.to_le_bytes().as_slice());
+				}
+				next = out.len();
+				out.extend_from_slice(&[0, 0, 0, 0]);
+			}
+			out
+		};
+
+		let chk = |src: &[u8], kind: PxKind, flags: u16| {
+			let flags = flags | Checksum::V2;
+			PxImage::new(src, kind, flags).expect("Decode failed.").into_checksum(flags)
+		};
+		let chk_page = |src: &[u8], kind: PxKind, page: usize| {
+			PxImage::new_page(src, kind, Checksum::V2, page).map(|img| img.into_checksum(Checksum::V2))
+		};
+
+		// Only the secondary sub-images differ; ICO decoding otherwise
+		// favors the largest entry.
+		for (kind, a, b) in [
+			(PxKind::Ico, ico(&[(4, 10), (8, 20)]), ico(&[(4, 30), (8, 20)])),
+			(PxKind::Tiff, tiff(&[(4, 10), (8, 20)]), tiff(&[(4, 10), (8, 30)])),
+		] {
+			assert_eq!(PxKind::try_from_magic(&a), Ok(kind), "Bad {kind:?} test image.");
+			assert_eq!(kind.pages(&a), 2, "Wrong {kind:?} page count.");
+
+			// The default image is the same.
+			assert_eq!(chk(&a, kind, 0), chk(&b, kind, 0), "{kind:?} images should match.");
+
+			// But the whole isn't.
+			let all = chk(&a, kind, Checksum::ALL_FRAMES);
+			assert_ne!(all, chk(&b, kind, Checksum::ALL_FRAMES), "{kind:?} pages should not match.");
+			assert_eq!(mode(&all) & Checksum::ALL_FRAMES, Checksum::ALL_FRAMES, "Missing {kind:?} flag.");
+
+			// Page by page.
+			let (a0, b0) = (chk_page(&a, kind, 0), chk_page(&b, kind, 0));
+			let (a1, b1) = (chk_page(&a, kind, 1), chk_page(&b, kind, 1));
+			assert!(a0.is_ok() && a1.is_ok(), "{kind:?} pages failed.");
+			assert_ne!(a0, a1, "{kind:?} pages should be different.");
+			if matches!(kind, PxKind::Tiff) {
+				assert_eq!(a0, b0, "{kind:?} first pages should match.");
+				assert_ne!(a1, b1, "{kind:?} second pages should not match.");
+			}
+			else {
+				assert_ne!(a0, b0, "{kind:?} first pages should not match.");
+				assert_eq!(a1, b1, "{kind:?} second pages should match.");
+			}
+			assert!(chk_page(&a, kind, 2).is_err(), "{kind:?} page 2 should not exist.");
+		}
+
+		// Page zero of a normal image is the image.
+		let raw = std::fs::read("skel/assets/poe.png").expect("Unable to read poe.png.");
+		assert_eq!(PxKind::Png.pages(&raw), 1, "Wrong PNG page count.");
+		assert_eq!(
+			chk_page(&raw, PxKind::Png, 0),
+			Ok(chk(&raw, PxKind::Png, Checksum::ALL_FRAMES)),
+			"PNG page zero mismatch.",
+		);
+		assert!(chk_page(&raw, PxKind::Png, 1).is_err(), "PNG page 1 should not exist.");
+	}
+
+	#[test]
+	/// # Test Fingerprints.
+	///
+	/// Resized copies of an image should have the same or similar
+	/// fingerprints, while different images should not.
+	fn t_fingerprint() {
+		use std::io::Cursor;
+
+		let fp = |path: &str| {
+			let raw = std::fs::read(path).expect("Unable to read image.");
+			let kind = PxKind::try_from_magic(&raw).expect("Unknown image type.");
+			PxImage::new(&raw, kind, 0).expect("Decode failed.").fingerprint()
+		};
+
+		// Resize Statler and re-encode him as a PNG.
+		let small = {
+			let img = image::open("skel/assets/statler.png")
+				.expect("Unable to open statler.png.")
+				.resize_exact(37, 41, image::imageops::FilterType::Triangle);
+			let mut out = Cursor::new(Vec::new());
+			img.write_to(&mut out, ImageFormat::Png).expect("PNG encoding failed.");
+			out.into_inner()
+		};
+		let small = PxImage::new(&small, PxKind::Png, 0).expect("Decode failed.").fingerprint();
+
+		let statler = fp("skel/assets/statler.png");
+		assert_eq!(statler, fp("skel/assets/statler.webp"), "Statlers should match.");
+		assert!(distance(statler, small) <= 4, "Resized Statler is too different.");
+		assert!(8 < distance(statler, fp("skel/assets/waldorf.png")), "Waldorf is too similar.");
+
+		// Deep mode shouldn't matter.
+		let raw = std::fs::read("skel/assets/statler.png").expect("Unable to read image.");
+		let deep = PxImage::new(&raw, PxKind::Png, Checksum::DEEP).expect("Decode failed.");
+		assert_eq!(statler, deep.fingerprint(), "Deep fingerprint mismatch.");
+	}
+
 	#[test]
 	fn t_guess() {
 		use std::io::Read;
@@ -319,4 +2105,112 @@ mod test {
 			);
 		}
 	}
+
+	#[test]
+	/// # Test Minor Formats.
+	///
+	/// We don't keep assets around for every last format the image crate can
+	/// decode, so generate some on the fly and make sure they're detected and
+	/// decoded the same as a PNG of the same pixels.
+	fn t_guess_encoded() {
+		use image::{
+			ImageBuffer,
+			Rgb,
+		};
+		use std::io::Cursor;
+
+		let img = DynamicImage::ImageRgb8(ImageBuffer::from_fn(4, 4, |x, y| {
+			let v = u8::try_from(x * 64 + y * 16).unwrap();
+			Rgb([v, 255 - v, v / 2])
+		}));
+		let encode = |img: DynamicImage, fmt: ImageFormat| {
+			let mut out = Cursor::new(Vec::new());
+			img.write_to(&mut out, fmt).expect("Encoding failed.");
+			out.into_inner()
+		};
+		let expected = PxImage::new(&encode(img.clone(), ImageFormat::Png), PxKind::Png, Checksum::V2)
+			.expect("Decode failed.")
+			.into_checksum(Checksum::V2);
+
+		for (kind, raw) in [
+			(PxKind::Farbfeld, encode(img.to_rgba16().into(), ImageFormat::Farbfeld)),
+			(PxKind::OpenExr, encode(img.to_rgba32f().into(), ImageFormat::OpenExr)),
+			(PxKind::Pnm, encode(img.clone(), ImageFormat::Pnm)),
+			(PxKind::Qoi, encode(img.clone(), ImageFormat::Qoi)),
+		] {
+			assert_eq!(PxKind::try_from_magic(&raw), Ok(kind), "Wrong type guessed for {kind:?}!");
+			let chk = PxImage::new(&raw, kind, Checksum::V2).expect("Decode failed.")
+				.into_checksum(Checksum::V2);
+			assert_eq!(chk, expected, "{kind:?} checksum mismatch.");
+		}
+
+		// Radiance is lossy, so all we can do is make sure it decodes.
+		let raw = encode(img.to_rgb32f().into(), ImageFormat::Hdr);
+		assert_eq!(PxKind::try_from_magic(&raw), Ok(PxKind::Hdr), "Wrong type guessed for HDR!");
+		assert!(PxImage::new(&raw, PxKind::Hdr, Checksum::V2).is_ok(), "HDR decode failed.");
+
+		// TGAs have no signature, so need the extension.
+		let raw = encode(img, ImageFormat::Tga);
+		assert!(PxKind::try_from_magic(&raw).is_err(), "TGAs shouldn't be guessable.");
+		for ext in ["TGA", "tga", "icb", "VDA", "vst"] {
+			assert_eq!(
+				PxKind::try_from_extension(format!("/foo/bar.{ext}").as_bytes()),
+				Some(PxKind::Tga),
+				"Wrong type guessed for .{ext}.",
+			);
+			assert!(crate::check_extension(format!("/foo/bar.{ext}").as_bytes()), "Missing .{ext}.");
+		}
+		assert_eq!(PxKind::try_from_extension(b"/foo/bar.png"), None);
+		assert_eq!(PxKind::try_from_extension(b"tga"), None);
+		let chk = PxImage::new(&raw, PxKind::Tga, Checksum::V2).expect("Decode failed.")
+			.into_checksum(Checksum::V2);
+		assert_eq!(chk, expected, "TGA checksum mismatch.");
+	}
+
+	#[cfg(feature = "heif")]
+	#[test]
+	fn t_heif() {
+		// Brand detection.
+		for brand in [b"heic", b"heix", b"hevc", b"mif1", b"msf1"] {
+			let mut raw = *b"\0\0\0\x18ftyp....\0\0\0\0";
+			raw[8..12].copy_from_slice(brand);
+			assert_eq!(PxKind::try_from_magic(&raw), Ok(PxKind::Heif), "Missed {brand:?}.");
+		}
+		assert!(PxKind::try_from_magic(b"\0\0\0\x18ftypisom\0\0\0\0").is_err());
+
+		// Padded rows should be trimmed.
+		let img = heif_image(&[1, 2, 3, 0, 4, 5, 6, 0], 1, 2, 4, 8, false)
+			.expect("Conversion failed.");
+		assert_eq!(img.as_bytes(), [1, 2, 3, 4, 5, 6]);
+
+		// Ten-bit samples should be scaled to sixteen.
+		let img = heif_image(&[0xff, 0x03, 0, 0, 0x00, 0x02, 0xff, 0x03], 1, 1, 8, 10, true)
+			.expect("Conversion failed.");
+		let DynamicImage::ImageRgba16(img) = img else { panic!("Wrong color type."); };
+		assert_eq!(img.into_raw(), [u16::MAX, 0, 0x8020, u16::MAX]);
+
+		// Short buffers are no good.
+		assert!(heif_image(&[1, 2, 3], 1, 2, 3, 8, false).is_none());
+
+		// And now for a real one: a 64x48 gradient with alpha, stored as a
+		// (padded) grid, rotated ninety degrees clockwise.
+		let raw = std::fs::read("skel/assets/gradient.heic").expect("Unable to read HEIC.");
+		assert_eq!(PxKind::try_from_magic(&raw), Ok(PxKind::Heif));
+		let img = PxKind::Heif.decode(&raw, 0).expect("HEIC decode failed.");
+		let DynamicImage::ImageRgba8(img) = img else { panic!("Wrong color type."); };
+		assert_eq!(img.dimensions(), (64, 48), "Wrong dimensions.");
+
+		// It's lossy, so can't be expected to match exactly.
+		let worst = img.enumerate_pixels()
+			.flat_map(|(x, y, px)| {
+				let expected = [x * 4, y * 5, 128, 255 - x * 2];
+				px.0.into_iter().zip(expected).map(|(a, b)| u32::from(a).abs_diff(b))
+			})
+			.max();
+		assert!(worst.is_some_and(|w| w <= 8), "Pixels are way off: {worst:?}");
+
+		// Orientation should be optional.
+		let img = PxKind::Heif.decode(&raw, Checksum::ORIENTED).expect("HEIC decode failed.");
+		assert_eq!((img.width(), img.height()), (48, 64), "Wrong oriented dimensions.");
+	}
 }
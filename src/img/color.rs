@@ -0,0 +1,377 @@
+/*!
+# pxsum: Image Color Management.
+*/
+
+use crate::PxsumError;
+use image::{
+	ColorType,
+	DynamicImage,
+	ImageBuffer,
+};
+use moxcms::{
+	CicpColorPrimaries,
+	CicpProfile,
+	ColorProfile,
+	DataColorSpace,
+	Layout,
+	MatrixCoefficients,
+	RenderingIntent,
+	TransferCharacteristics,
+	TransformExecutor,
+	TransformOptions,
+};
+use zune_core::{
+	bytestream::ZCursor,
+	colorspace::ColorSpace,
+	options::DecoderOptions,
+};
+use zune_jpeg::JpegDecoder;
+
+
+
+/// # Parse ICC Profile.
+///
+/// Parse a raw ICC profile, returning `None` if it is effectively sRGB (and
+/// can therefore be left alone).
+///
+/// ## Errors
+///
+/// This will return an error if the profile is malformed.
+pub(super) fn icc_profile(raw: &[u8]) -> Result<Option<ColorProfile>, PxsumError> {
+	let profile = ColorProfile::new_from_slice(raw)?;
+	Ok((! is_srgb(&profile)).then_some(profile))
+}
+
+/// # Build CICP Profile.
+///
+/// Build a profile from the color primaries, transfer characteristics,
+/// matrix coefficients, and full-range flag of a PNG `cICP` chunk or HEIF
+/// `nclx` box, returning `None` if it is effectively sRGB (or unspecified).
+///
+/// As the decoders have already done any YUV-to-RGB conversion by the time
+/// we get the pixels, the matrix is only used for validation.
+///
+/// ## Errors
+///
+/// This will return an error if the values are invalid.
+pub(super) fn cicp_profile(primaries: u8, transfer: u8, matrix: u8, full_range: bool)
+-> Result<Option<ColorProfile>, PxsumError> {
+	// sRGB is sRGB.
+	if primaries == 1 && transfer == 13 { return Ok(None); }
+
+	let profile = ColorProfile::new_from_cicp(CicpProfile {
+		color_primaries: CicpColorPrimaries::try_from(primaries)?,
+		transfer_characteristics: TransferCharacteristics::try_from(transfer)?,
+		matrix_coefficients: MatrixCoefficients::try_from(matrix)?,
+		full_range,
+	});
+
+	// Unspecified or reserved values leave the profile incomplete; treat
+	// those like untagged images.
+	if profile.red_trc.is_none() || is_srgb(&profile) { Ok(None) }
+	else { Ok(Some(profile)) }
+}
+
+/// # PNG Color Profile.
+///
+/// Return the profile described by the PNG's `cICP` chunk, if any, or else
+/// its (already extracted) ICC profile. Per the spec, the former takes
+/// precedence.
+///
+/// ## Errors
+///
+/// This will return an error if the profile is malformed.
+pub(super) fn png_profile(src: &[u8], icc: Option<Vec<u8>>)
+-> Result<Option<ColorProfile>, PxsumError> {
+	// Walk the chunks until we find the cICP or run out of headers.
+	let mut pos = 8;
+	while let Some([a, b, c, d, t1, t2, t3, t4, rest @ ..]) = src.get(pos..) {
+		let len = usize::try_from(u32::from_be_bytes([*a, *b, *c, *d])).map_err(|_| PxsumError::Decode)?;
+		match [*t1, *t2, *t3, *t4] {
+			[b'c', b'I', b'C', b'P'] => {
+				if let [primaries, transfer, matrix, full_range, ..] = *rest {
+					return cicp_profile(primaries, transfer, matrix, full_range != 0);
+				}
+				return Err(PxsumError::Decode);
+			},
+			// The cICP has to come before the image data.
+			[b'I', b'D', b'A', b'T'] => break,
+			// Skip the data and CRC.
+			_ => { pos += 12 + len; },
+		}
+	}
+
+	icc.map_or(Ok(None), |raw| icc_profile(&raw))
+}
+
+#[cfg(any(feature = "avif", feature = "heif"))]
+/// # HEIF Color Profile.
+///
+/// Return the profile described by the HEIF color (`colr`) property, if any.
+/// (This applies equally to AVIF, which uses the same container.)
+/// Embedded ICC profiles (`prof` or `rICC`) take precedence over `nclx`
+/// values.
+///
+/// ## Errors
+///
+/// This will return an error if the profile is malformed.
+pub(super) fn heif_profile(src: &[u8]) -> Result<Option<ColorProfile>, PxsumError> {
+	let mut nclx = None;
+	for (idx, w) in src.windows(8).enumerate() {
+		if let [a, b, c, d, b'c', b'o', b'l', b'r'] = *w {
+			let len = usize::try_from(u32::from_be_bytes([a, b, c, d])).map_err(|_| PxsumError::Decode)?;
+			let body = len.checked_sub(8)
+				.and_then(|len| src.get(idx + 8..idx + 8 + len))
+				.ok_or(PxsumError::Decode)?;
+
+			match body {
+				[b'p', b'r', b'o', b'f', raw @ ..] | [b'r', b'I', b'C', b'C', raw @ ..] => {
+					return icc_profile(raw);
+				},
+				[b'n', b'c', b'l', b'x', 0, primaries, 0, transfer, 0, matrix, full_range, ..] => {
+					nclx.replace((*primaries, *transfer, *matrix, 0 != full_range & 0b1000_0000));
+				},
+				_ => {},
+			}
+		}
+	}
+
+	nclx.map_or(Ok(None), |(p, t, m, f)| cicp_profile(p, t, m, f))
+}
+
+/// # Decode CMYK JPEG.
+///
+/// The regular JPEG decoder converts CMYK to RGB naively, ignoring any
+/// embedded profile, so we have to go back and fetch the raw samples
+/// ourselves to do the job properly.
+///
+/// Both plain CMYK and YCCK are supported. Following Adobe's convention, the
+/// stored values are inverted.
+///
+/// ## Errors
+///
+/// This will return an error if the image is not actually CMYK or cannot be
+/// decoded or transformed.
+pub(super) fn decode_cmyk_jpeg(src: &[u8], profile: &ColorProfile)
+-> Result<DynamicImage, PxsumError> {
+	// Read the headers first to figure out what we're dealing with.
+	let mut dec = JpegDecoder::new(ZCursor::new(src));
+	dec.decode_headers().map_err(|_| PxsumError::Decode)?;
+	let input = dec.input_colorspace().ok_or(PxsumError::Decode)?;
+	if ! matches!(input, ColorSpace::CMYK | ColorSpace::YCCK) {
+		return Err(PxsumError::Unsupported);
+	}
+
+	// Decoding to the same colorspace leaves the samples untouched.
+	let mut dec = JpegDecoder::new_with_options(
+		ZCursor::new(src),
+		DecoderOptions::default().jpeg_set_out_colorspace(input),
+	);
+	let mut buf = dec.decode().map_err(|_| PxsumError::Decode)?;
+	let (width, height) = dec.dimensions().ok_or(PxsumError::Decode)?;
+
+	for px in buf.chunks_exact_mut(4) {
+		// YCCK needs to be converted to (inverted) CMY first, using the usual
+		// JFIF formulas, which leaves the C, M, and Y the right way around.
+		if input == ColorSpace::YCCK {
+			let y = f32::from(px[0]);
+			let cb = f32::from(px[1]) - 128.0;
+			let cr = f32::from(px[2]) - 128.0;
+			px[0] = unit_u8(1.402_f32.mul_add(cr, y));
+			px[1] = unit_u8(0.714_136_f32.mul_add(-cr, 0.344_136_f32.mul_add(-cb, y)));
+			px[2] = unit_u8(1.772_f32.mul_add(cb, y));
+		}
+		else {
+			for v in &mut px[..3] { *v = 255 - *v; }
+		}
+		px[3] = 255 - px[3];
+	}
+
+	// The four-channel RGBA layout doubles for CMYK.
+	let srgb = ColorProfile::new_srgb();
+	let mut dst = vec![0_u8; buf.len()];
+	profile.create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, options())?
+		.transform(&buf, &mut dst)?;
+
+	let width = u32::try_from(width).map_err(|_| PxsumError::Decode)?;
+	let height = u32::try_from(height).map_err(|_| PxsumError::Decode)?;
+	ImageBuffer::from_raw(width, height, dst)
+		.map(DynamicImage::ImageRgba8)
+		.ok_or(PxsumError::Decode)
+}
+
+/// # Convert to sRGB.
+///
+/// Transform the pixels of an RGB or grayscale image from the given profile
+/// to sRGB, at their native precision. Images with alpha channels keep them;
+/// those without don't gain them.
+///
+/// ## Errors
+///
+/// This will return an error if the profile is unsupported or doesn't match
+/// the image, e.g. a grayscale profile on an RGB image.
+pub(super) fn into_srgb(img: DynamicImage, profile: &ColorProfile)
+-> Result<DynamicImage, PxsumError> {
+	let (width, height) = (img.width(), img.height());
+	let color = img.color();
+	let alpha = color.has_alpha();
+	let gray = match profile.color_space {
+		DataColorSpace::Rgb => false,
+		DataColorSpace::Gray if matches!(
+			color,
+			ColorType::L8 | ColorType::La8 | ColorType::L16 | ColorType::La16
+		) => true,
+		_ => return Err(PxsumError::Unsupported),
+	};
+	let (from, to) = match (gray, alpha) {
+		(false, false) => (Layout::Rgb, Layout::Rgb),
+		(false, true) => (Layout::Rgba, Layout::Rgba),
+		(true, false) => (Layout::Gray, Layout::Rgb),
+		(true, true) => (Layout::GrayAlpha, Layout::Rgba),
+	};
+	let srgb = ColorProfile::new_srgb();
+
+	let out = match color {
+		ColorType::Rgb32F | ColorType::Rgba32F => {
+			let src = if alpha { img.into_rgba32f().into_raw() } else { img.into_rgb32f().into_raw() };
+			let exec = profile.create_transform_f32(from, &srgb, to, options())?;
+			let dst = transform(&src, from, to, exec.as_ref())?;
+			if alpha { ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgba32F) }
+			else { ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgb32F) }
+		},
+		ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16 => {
+			let src = match from {
+				Layout::Gray => img.into_luma16().into_raw(),
+				Layout::GrayAlpha => img.into_luma_alpha16().into_raw(),
+				Layout::Rgb => img.into_rgb16().into_raw(),
+				_ => img.into_rgba16().into_raw(),
+			};
+			let exec = profile.create_transform_16bit(from, &srgb, to, options())?;
+			let dst = transform(&src, from, to, exec.as_ref())?;
+			if alpha { ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgba16) }
+			else { ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgb16) }
+		},
+		_ => {
+			let src = match from {
+				Layout::Gray => img.into_luma8().into_raw(),
+				Layout::GrayAlpha => img.into_luma_alpha8().into_raw(),
+				Layout::Rgb => img.into_rgb8().into_raw(),
+				_ => img.into_rgba8().into_raw(),
+			};
+			let exec = profile.create_transform_8bit(from, &srgb, to, options())?;
+			let dst = transform(&src, from, to, exec.as_ref())?;
+			if alpha { ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgba8) }
+			else { ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgb8) }
+		},
+	};
+
+	out.ok_or(PxsumError::Decode)
+}
+
+
+
+/// # Is sRGB?
+///
+/// Embedded sRGB profiles are extremely common, but can vary slightly in how
+/// they describe the space. Rather than compare the numbers, we check whether
+/// an 8-bit transform to our own sRGB leaves a grid of sample colors
+/// untouched.
+///
+/// Profiles that pass are skipped entirely, so that tagged and untagged
+/// copies of an sRGB image hash the same way, even at higher precision.
+fn is_srgb(profile: &ColorProfile) -> bool {
+	let srgb = ColorProfile::new_srgb();
+	match profile.color_space {
+		DataColorSpace::Rgb => {
+			let src: Vec<u8> = (0..=255_u8).step_by(17)
+				.flat_map(|r| (0..=255_u8).step_by(17).flat_map(move |g|
+					(0..=255_u8).step_by(17).flat_map(move |b| [r, g, b])
+				))
+				.collect();
+			profile.create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options()).ok()
+				.and_then(|exec| transform(&src, Layout::Rgb, Layout::Rgb, exec.as_ref()).ok())
+				.is_some_and(|dst| dst == src)
+		},
+		DataColorSpace::Gray => {
+			let src: Vec<u8> = (0..=255_u8).collect();
+			profile.create_transform_8bit(Layout::Gray, &srgb, Layout::Rgb, options()).ok()
+				.and_then(|exec| transform(&src, Layout::Gray, Layout::Rgb, exec.as_ref()).ok())
+				.is_some_and(|dst| dst.chunks_exact(3).zip(src).all(|(px, v)| px == [v, v, v]))
+		},
+		_ => false,
+	}
+}
+
+/// # Transform Options.
+///
+/// The rendering intent is pinned so a change of defaults upstream won't
+/// quietly change everybody's checksums.
+fn options() -> TransformOptions {
+	TransformOptions {
+		rendering_intent: RenderingIntent::Perceptual,
+		..TransformOptions::default()
+	}
+}
+
+/// # Transform.
+///
+/// Run the pixels through the transform, returning a new buffer.
+fn transform<T: Copy + Default>(
+	src: &[T],
+	from: Layout,
+	to: Layout,
+	exec: &(dyn TransformExecutor<T> + Send + Sync),
+) -> Result<Vec<T>, PxsumError> {
+	let mut dst = vec![T::default(); src.len() / from.channels() * to.channels()];
+	exec.transform(src, &mut dst)?;
+	Ok(dst)
+}
+
+#[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss, reason = "It is clamped.")]
+/// # Unit Float to U8.
+const fn unit_u8(v: f32) -> u8 { v.round().clamp(0.0, 255.0) as u8 }
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_cicp() {
+		/// # PNG With (Fake) cICP Chunk.
+		fn png(primaries: u8, transfer: u8) -> Vec<u8> {
+			let mut out = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+			out.extend_from_slice(&[0; 13 + 4]);
+			out.extend_from_slice(b"\0\0\0\x04cICP");
+			out.extend_from_slice(&[primaries, transfer, 0, 1, 0, 0, 0, 0]);
+			out.extend_from_slice(b"\0\0\0\0IDAT\0\0\0\0");
+			out
+		}
+
+		// Display P3 needs converting.
+		let profile = png_profile(&png(12, 13), None)
+			.expect("Profile failed.")
+			.expect("Missing profile.");
+		assert_eq!(profile.color_space, DataColorSpace::Rgb);
+
+		// But sRGB and unspecified don't.
+		assert!(png_profile(&png(1, 13), None).expect("Profile failed.").is_none());
+		assert!(png_profile(&png(2, 2), None).expect("Profile failed.").is_none());
+
+		// cICP takes precedence over ICC.
+		let icc = ColorProfile::new_adobe_rgb().encode().expect("ICC failed.");
+		assert!(png_profile(&png(1, 13), Some(icc.clone())).expect("Profile failed.").is_none());
+		assert!(png_profile(b"\x89PNG\r\n\x1a\n", Some(icc)).expect("Profile failed.").is_some());
+
+		// Same deal for AVIF/HEIF, sans ICC.
+		#[cfg(any(feature = "avif", feature = "heif"))]
+		{
+			let avif = b"..\0\0\0\x13colrnclx\0\x09\0\x10\0\0\x80..";
+			assert!(heif_profile(avif).expect("Profile failed.").is_some());
+			let avif = b"..\0\0\0\x13colrnclx\0\x01\0\x0d\0\0\x80..";
+			assert!(heif_profile(avif).expect("Profile failed.").is_none());
+			assert!(heif_profile(b"nothing to see here").expect("Profile failed.").is_none());
+		}
+	}
+}
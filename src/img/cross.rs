@@ -0,0 +1,127 @@
+/*!
+# pxsum: Alternate Decoders.
+*/
+
+use crate::{
+	PxKind,
+	PxsumError,
+};
+use image::{
+	DynamicImage,
+	ImageBuffer,
+};
+use jpeg_decoder::{
+	Decoder,
+	PixelFormat,
+};
+
+
+
+/// # Decode With Alternate Backend.
+///
+/// Decode the image with a second backend — one sharing no decoding code
+/// with the primary — if there is one for the format, so the results can be
+/// checked against the primary's.
+///
+/// Unlike `PxKind::decode`, no orientation or color management is applied;
+/// the pixels are returned exactly as the backend produced them.
+///
+/// Returns `None` if the format only has the one backend.
+pub(super) fn decode(kind: PxKind, src: &[u8]) -> Option<Result<DynamicImage, PxsumError>> {
+	match kind {
+		PxKind::Jpeg => Some(decode_jpeg(src)),
+		_ => None,
+	}
+}
+
+#[cold]
+/// # Decode JPEG (jpeg-decoder).
+///
+/// The image crate decodes JPEGs with zune-jpeg; jpeg-decoder is a separate
+/// implementation from the ground up.
+fn decode_jpeg(src: &[u8]) -> Result<DynamicImage, PxsumError> {
+	let mut dec = Decoder::new(src);
+	let buf = dec.decode().map_err(|_| PxsumError::Decode)?;
+	let info = dec.info().ok_or(PxsumError::Decode)?;
+	let (width, height) = (u32::from(info.width), u32::from(info.height));
+
+	match info.pixel_format {
+		PixelFormat::L8 => ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma8),
+		PixelFormat::L16 => ImageBuffer::from_raw(
+			width,
+			height,
+			buf.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect(),
+		).map(DynamicImage::ImageLuma16),
+		PixelFormat::RGB24 => ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8),
+		PixelFormat::CMYK32 => ImageBuffer::from_raw(
+			width,
+			height,
+			buf.chunks_exact(4).flat_map(cmyk_to_rgb).collect(),
+		).map(DynamicImage::ImageRgb8),
+	}
+		.ok_or(PxsumError::Decode)
+}
+
+/// # CMYK to RGB.
+///
+/// This is the same naive, profile-less conversion the primary backend
+/// uses.
+fn cmyk_to_rgb(px: &[u8]) -> [u8; 3] {
+	#[expect(clippy::cast_possible_truncation, reason = "Values max out at 255.")]
+	/// # Ink to Channel.
+	const fn f(c: u8, k: u8) -> u8 {
+		(((255 - c as u16) * (255 - k as u16) + 127) / 255) as u8
+	}
+	[f(px[0], px[3]), f(px[1], px[3]), f(px[2], px[3])]
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		Checksum,
+		PxDiff,
+		PxImage,
+	};
+
+	#[test]
+	fn t_cross_check() {
+		// Baseline, progressive, and (progressive) CMYK JPEGs should all come
+		// out the same either way, give or take some rounding.
+		for path in [
+			"skel/assets/lenna.jpeg",
+			"skel/assets/ash.jpg",
+			"skel/assets/carl.jpg",
+			"skel/assets/cmyk.JPG",
+		] {
+			let raw = std::fs::read(path).expect("Unable to read JPEG.");
+			let diff = PxImage::cross_check(&raw, PxKind::Jpeg, Checksum::V2)
+				.expect("Cross-check failed.")
+				.expect("Missing alternate JPEG backend.");
+			assert!(diff.comparable(), "Decoders should agree on the dimensions: {path}");
+			assert!(diff.within(PxDiff::DECODER_TOLERANCE), "Decoders disagree: {path}: {diff}");
+		}
+
+		// PNGs and JPEG 2000s only have the one backend.
+		let raw = std::fs::read("skel/assets/poe.png").expect("Unable to read PNG.");
+		assert!(
+			PxImage::cross_check(&raw, PxKind::Png, Checksum::V2).expect("Cross-check failed.").is_none(),
+			"PNGs shouldn't have an alternate backend.",
+		);
+		assert!(decode(PxKind::Png, &raw).is_none());
+
+		#[cfg(feature = "jpeg2k")]
+		{
+			let raw = std::fs::read("skel/assets/ace.jp2").expect("Unable to read JPEG 2000.");
+			assert!(decode(PxKind::Jpeg2k, &raw).is_none());
+		}
+
+		// Garbage is garbage.
+		assert!(
+			PxImage::cross_check(b"\xff\xd8\xff nope nope nope", PxKind::Jpeg, Checksum::V2).is_err(),
+			"Garbage should fail.",
+		);
+	}
+}
@@ -0,0 +1,357 @@
+/*!
+# pxsum: Image Differences.
+*/
+
+use crate::PxImage;
+use dactyl::NiceU64;
+use image::{
+	Rgba,
+	RgbaImage,
+};
+use std::fmt;
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// # Pixel Difference Report.
+///
+/// This holds the statistics from a pixel-by-pixel comparison of two images
+/// (see `PxImage::diff`): how many pixels changed, by how much, and where.
+///
+/// Channel values are normalized to `0.0..=1.0` regardless of depth, so the
+/// numbers are comparable across regular and deep images alike.
+pub struct PxDiff {
+	/// # Our Dimensions (Width, Height, Frames).
+	ours: (u32, u32, u32),
+
+	/// # Their Dimensions (Width, Height, Frames).
+	theirs: (u32, u32, u32),
+
+	/// # Comparable?
+	comparable: bool,
+
+	/// # Total Pixels.
+	pixels: u64,
+
+	/// # Changed Pixels.
+	changed: u64,
+
+	/// # Max Channel Delta.
+	max_delta: f64,
+
+	/// # Sum of Squared (Channel) Errors.
+	sse: f64,
+
+	/// # Bounding Box (Left, Top, Right, Bottom), Inclusive.
+	bounds: Option<(u32, u32, u32, u32)>,
+}
+
+impl fmt::Display for PxDiff {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		/// # Dimensions.
+		struct Dim((u32, u32, u32));
+		impl fmt::Display for Dim {
+			fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+				let (w, h, frames) = self.0;
+				write!(f, "{w}x{h}")?;
+				if 1 < frames { write!(f, " ({frames} frames)")?; }
+				Ok(())
+			}
+		}
+
+		if ! self.comparable {
+			return write!(f, "dimensions differ ({} vs {})", Dim(self.ours), Dim(self.theirs));
+		}
+		if self.changed == 0 { return f.write_str("pixels identical"); }
+
+		write!(
+			f,
+			"{} of {} pixels differ ({:.2}%); max channel delta {:.2}%",
+			NiceU64::from(self.changed),
+			NiceU64::from(self.pixels),
+			self.changed_ratio() * 100.0,
+			self.max_delta * 100.0,
+		)?;
+		if let Some(psnr) = self.psnr() { write!(f, "; PSNR {psnr:.2} dB")?; }
+		if let Some((x, y, w, h)) = self.bounds() { write!(f, "; bounds {w}x{h}+{x}+{y}")?; }
+		Ok(())
+	}
+}
+
+impl PxDiff {
+	/// # Decoder Tolerance.
+	///
+	/// Lossy decoding involves a fair bit of rounding — in the IDCT, chroma
+	/// upsampling, and color conversion — so two correct implementations can
+	/// still disagree slightly. This is the largest (normalized) channel
+	/// delta expected from that alone, four levels out of 255.
+	pub const DECODER_TOLERANCE: f64 = 4.0 / 255.0;
+
+	#[must_use]
+	/// # Comparable?
+	///
+	/// Returns `true` if the images have the same dimensions, frame/page
+	/// layout, and precision. If not, the other statistics are all zero.
+	pub const fn comparable(&self) -> bool { self.comparable }
+
+	#[must_use]
+	/// # Identical?
+	///
+	/// Returns `true` if the images are comparable and no pixels differ.
+	pub const fn identical(&self) -> bool { self.comparable && self.changed == 0 }
+
+	#[must_use]
+	/// # Within Tolerance?
+	///
+	/// Returns `true` if the images are comparable and no channel differs by
+	/// more than `max_delta` (from `0.0` to `1.0`).
+	pub const fn within(&self, max_delta: f64) -> bool {
+		self.comparable && self.max_delta <= max_delta
+	}
+
+	#[must_use]
+	/// # Total Pixels.
+	pub const fn pixels(&self) -> u64 { self.pixels }
+
+	#[must_use]
+	/// # Changed Pixels.
+	pub const fn changed(&self) -> u64 { self.changed }
+
+	#[must_use]
+	#[expect(clippy::cast_precision_loss, reason = "Close enough.")]
+	/// # Changed Pixel Ratio.
+	pub fn changed_ratio(&self) -> f64 {
+		if self.pixels == 0 { 0.0 }
+		else { self.changed as f64 / self.pixels as f64 }
+	}
+
+	#[must_use]
+	/// # Max Channel Delta.
+	///
+	/// Return the largest difference between any two (normalized) channels,
+	/// from `0.0` to `1.0`.
+	pub const fn max_delta(&self) -> f64 { self.max_delta }
+
+	#[must_use]
+	#[expect(clippy::cast_precision_loss, reason = "Close enough.")]
+	/// # Peak Signal-to-Noise Ratio.
+	///
+	/// Return the PSNR — in decibels — across all channels, or `None` if
+	/// the images are identical (or incomparable).
+	pub fn psnr(&self) -> Option<f64> {
+		if self.changed == 0 || self.pixels == 0 { return None; }
+		let mse = self.sse / (self.pixels as f64 * 4.0);
+		if 0.0 < mse { Some(-10.0 * mse.log10()) }
+		else { None }
+	}
+
+	#[must_use]
+	/// # Bounding Box.
+	///
+	/// Return the left, top, width, and height of the smallest box containing
+	/// every changed pixel, if any. For animations and multi-image
+	/// containers, the coordinates are relative to each frame or page.
+	pub const fn bounds(&self) -> Option<(u32, u32, u32, u32)> {
+		if let Some((x0, y0, x1, y1)) = self.bounds {
+			Some((x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+		}
+		else { None }
+	}
+}
+
+
+
+/// # Compare.
+///
+/// Crunch the difference statistics for two images.
+pub(super) fn compare(ours: &PxImage, theirs: &PxImage) -> PxDiff {
+	let mut out = PxDiff {
+		ours: (ours.width, ours.height, ours.frames),
+		theirs: (theirs.width, theirs.height, theirs.frames),
+		comparable: false,
+		pixels: 0,
+		changed: 0,
+		max_delta: 0.0,
+		sse: 0.0,
+		bounds: None,
+	};
+
+	let mut pixels = 0;
+	let mut changed = 0;
+	let mut max_delta = 0.0_f64;
+	let mut sse = 0.0_f64;
+	let mut bounds: Option<(u32, u32, u32, u32)> = None;
+	let comparable = walk(ours, theirs, |x, y, _, a, b| {
+		pixels += 1;
+		let mut delta = 0.0_f64;
+		for (a, b) in a.into_iter().zip(b) {
+			let d = (a - b).abs();
+			if d.is_finite() {
+				delta = delta.max(d);
+				sse = d.mul_add(d, sse);
+			}
+			else if a.to_bits() != b.to_bits() { delta = 1.0; }
+		}
+		if 0.0 < delta {
+			changed += 1;
+			max_delta = max_delta.max(delta);
+			bounds = Some(bounds.map_or(
+				(x, y, x, y),
+				|(x0, y0, x1, y1)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+			));
+		}
+	});
+
+	if comparable {
+		out.comparable = true;
+		out.pixels = pixels;
+		out.changed = changed;
+		out.max_delta = max_delta.min(1.0);
+		out.sse = sse;
+		out.bounds = bounds;
+	}
+	out
+}
+
+/// # Visualize.
+///
+/// Render the differences between two images as an RGBA8 image, with
+/// unchanged pixels as a faded grayscale copy of `ours` and changed pixels
+/// in red, brighter for bigger deltas.
+///
+/// Frames and pages are stacked top to bottom.
+pub(super) fn visualize(ours: &PxImage, theirs: &PxImage) -> Option<RgbaImage> {
+	let planes = ours.planes();
+	let width = planes.iter().map(|&(w, _)| w).max()?;
+	let height = planes.iter().map(|&(_, h)| h).sum();
+	let mut out = RgbaImage::new(width, height);
+
+	let comparable = walk(ours, theirs, |x, y, top, a, b| {
+		let delta = a.into_iter()
+			.zip(b)
+			.map(|(a, b)| (a - b).abs())
+			.fold(0.0_f64, |acc, d| if d.is_nan() { 1.0 } else { acc.max(d) });
+		let px =
+			if 0.0 < delta { Rgba([unit_u8(delta.mul_add(0.5, 0.5)), 0, 0, 255]) }
+			else {
+				let [r, g, b, alpha] = a;
+				let luma = 0.114_f64.mul_add(b, 0.299_f64.mul_add(r, 0.587 * g)) * alpha.clamp(0.0, 1.0);
+				let v = unit_u8(luma.mul_add(0.25, 0.125));
+				Rgba([v, v, v, 255])
+			};
+		out.put_pixel(x, top + y, px);
+	});
+
+	if comparable { Some(out) }
+	else { None }
+}
+
+/// # Walk Pixels.
+///
+/// Run both images through `cb` pixel by pixel — with the plane-relative
+/// coordinates, the plane's (stacked) top offset, and the normalized
+/// channels — returning `false` if they aren't comparable.
+fn walk<F>(ours: &PxImage, theirs: &PxImage, mut cb: F) -> bool
+where F: FnMut(u32, u32, u32, [f64; 4], [f64; 4]) {
+	let planes = ours.planes();
+	if
+		ours.px != theirs.px ||
+		ours.buf.len() != theirs.buf.len() ||
+		planes != theirs.planes()
+	{
+		return false;
+	}
+
+	let px = usize::from(ours.px);
+	let mut iter = ours.buf.chunks_exact(px).zip(theirs.buf.chunks_exact(px));
+	let mut top = 0;
+	for (width, height) in planes {
+		for y in 0..height {
+			for x in 0..width {
+				let Some((a, b)) = iter.next() else { return false; };
+				let (Some(a), Some(b)) = (super::unpack(a), super::unpack(b)) else {
+					return false;
+				};
+				cb(x, y, top, a, b);
+			}
+		}
+		top += height;
+	}
+
+	true
+}
+
+#[expect(clippy::cast_possible_truncation, reason = "Values are clamped.")]
+#[expect(clippy::cast_sign_loss, reason = "Values are clamped.")]
+/// # Unit Float to U8.
+fn unit_u8(v: f64) -> u8 { (v.clamp(0.0, 1.0) * 255.0).round() as u8 }
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// # Test Image.
+	fn img(width: u32, height: u32, buf: Vec<u8>) -> PxImage {
+		PxImage {
+			buf,
+			no_alpha: false,
+			width,
+			height,
+			frames: 1,
+			loops: 0,
+			delays: Vec::new(),
+			sizes: Vec::new(),
+			px: 4,
+			background: [0; 3],
+			tiles: Vec::new(),
+			sensor: false,
+		}
+	}
+
+	#[test]
+	fn t_diff() {
+		let a = img(4, 4, vec![255; 64]);
+		let same = a.diff(&img(4, 4, vec![255; 64]));
+		assert!(same.identical(), "Images should match.");
+		assert_eq!(same.psnr(), None);
+		assert_eq!(same.bounds(), None);
+		assert_eq!(same.to_string(), "pixels identical");
+
+		// Change a couple pixels.
+		let mut buf = vec![255; 64];
+		buf[(2 * 4 + 1) * 4] = 204;
+		buf[(3 * 4 + 3) * 4 + 1] = 230;
+		let b = img(4, 4, buf);
+		let diff = a.diff(&b);
+		assert!(diff.comparable(), "Images should be comparable.");
+		assert!(! diff.identical(), "Images should differ.");
+		assert_eq!(diff.pixels(), 16);
+		assert_eq!(diff.changed(), 2);
+		assert!((diff.max_delta() - 0.2).abs() < f64::EPSILON, "Wrong max delta.");
+		assert_eq!(diff.bounds(), Some((1, 2, 3, 2)));
+		let psnr = diff.psnr().expect("Missing PSNR.");
+		let (d1, d2) = (0.2_f64, 25.0_f64 / 255.0);
+		let expected = -10.0 * (d1.mul_add(d1, d2 * d2) / 64.0).log10();
+		assert!((psnr - expected).abs() < 1e-9, "Wrong PSNR.");
+		assert_eq!(
+			diff.to_string(),
+			format!("2 of 16 pixels differ (12.50%); max channel delta 20.00%; PSNR {expected:.2} dB; bounds 3x2+1+2"),
+		);
+
+		// The visualization should flag the changed pixels.
+		let vis = a.diff_image(&b).expect("Missing visualization.");
+		assert_eq!(vis.dimensions(), (4, 4));
+		assert_eq!(vis.get_pixel(1, 2).0, [153, 0, 0, 255]);
+		assert_eq!(vis.get_pixel(0, 0).0, [96, 96, 96, 255]);
+
+		// Different sizes aren't comparable.
+		let c = img(2, 8, vec![255; 64]);
+		let diff = a.diff(&c);
+		assert!(! diff.comparable(), "Images shouldn't be comparable.");
+		assert!(! diff.identical(), "Images shouldn't be identical.");
+		assert_eq!(diff.to_string(), "dimensions differ (4x4 vs 2x8)");
+		assert!(a.diff_image(&c).is_none(), "Visualization should fail.");
+	}
+}
@@ -0,0 +1,271 @@
+/*!
+# pxsum: JPEG XL.
+*/
+
+use crate::PxsumError;
+use image::DynamicImage;
+use super::color;
+
+
+
+/// # Container Signature.
+const CONTAINER: &[u8] = b"\0\0\0\x0cJXL \r\n\x87\n";
+
+/// # Codestream Signature.
+const CODESTREAM: &[u8] = b"\xff\x0a";
+
+
+
+#[cold]
+/// # Decode JPEG XL.
+///
+/// Not a popular format, hence cold.
+///
+/// The decoder can't return the individual frames of an animation, so
+/// animations are rejected outright.
+pub(super) fn decode(src: &[u8], managed: bool) -> Result<DynamicImage, PxsumError> {
+	use jpegxl_rs::image::ToDynamic;
+
+	if is_animated(src) { return Err(PxsumError::Unsupported); }
+
+	let img = jpegxl_rs::decoder_builder()
+		.build()
+		.and_then(|dec| dec.decode_to_image(src))
+		.ok()
+		.flatten()
+		.ok_or(PxsumError::Decode)?;
+
+	// The profile is only reported alongside the pixels, but the image
+	// conversion doesn't ask for it, so we need a second pass.
+	if managed {
+		let (meta, _) = jpegxl_rs::decoder_builder()
+			.icc_profile(true)
+			.build()
+			.and_then(|dec| dec.decode(src))
+			.map_err(|_| PxsumError::Decode)?;
+		if let Some(raw) = meta.icc_profile {
+			if let Some(profile) = color::icc_profile(&raw)? {
+				return color::into_srgb(img, &profile);
+			}
+		}
+	}
+
+	Ok(img)
+}
+
+/// # Animated?
+///
+/// The `jpegxl-rs` decoder renders every frame of an animation into the same
+/// buffer, returning only the last, so animations have to be caught ahead of
+/// time by reading the `have_animation` bit from the image header.
+///
+/// This only parses as far as that bit; anything malformed is left for the
+/// real decoder to complain about.
+fn is_animated(src: &[u8]) -> bool {
+	codestream(src).and_then(have_animation).unwrap_or(false)
+}
+
+/// # Find Codestream.
+///
+/// Return the (start of the) codestream, unwrapping it from its ISOBMFF
+/// container if necessary.
+fn codestream(src: &[u8]) -> Option<&[u8]> {
+	if let Some(rest) = src.strip_prefix(CODESTREAM) { return Some(rest); }
+
+	let mut rest = src.strip_prefix(CONTAINER)?;
+	while rest.len() >= 8 {
+		let size = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]);
+		let kind = &rest[4..8];
+		let (head, size) = match size {
+			0 => (8, rest.len()),
+			1 => {
+				let size = u64::from_be_bytes(rest.get(8..16)?.try_into().ok()?);
+				(16, usize::try_from(size).ok()?)
+			},
+			n => (8, usize::try_from(n).ok()?),
+		};
+		let body = rest.get(head..size.max(head))?;
+
+		match kind {
+			b"jxlc" => return body.strip_prefix(CODESTREAM),
+			// Partial codestreams are prefixed with a four-byte index; the
+			// header lives in the first.
+			b"jxlp" => return body.get(4..)?.strip_prefix(CODESTREAM),
+			_ => { rest = rest.get(size..)?; },
+		}
+	}
+
+	None
+}
+
+/// # Have Animation?
+///
+/// Skip past the `SizeHeader` and the leading fields of `ImageMetadata` to
+/// read its `have_animation` bit.
+fn have_animation(src: &[u8]) -> Option<bool> {
+	let mut bits = Bits::new(src);
+
+	// SizeHeader.
+	bits.size(false)?;
+
+	// ImageMetadata.
+	if bits.bool()? { return Some(false); } // All default.
+	if ! bits.bool()? { return Some(false); } // No extra fields.
+	bits.skip(3)?; // Orientation.
+	if bits.bool()? { bits.size(false)?; } // Intrinsic size.
+	if bits.bool()? { bits.size(true)?; } // Preview.
+	bits.bool()
+}
+
+
+
+/// # Bit Reader.
+///
+/// JPEG XL headers are packed least-significant-bit first.
+struct Bits<'a> {
+	/// # Source.
+	src: &'a [u8],
+
+	/// # Bit Position.
+	pos: usize,
+}
+
+impl<'a> Bits<'a> {
+	/// # New.
+	const fn new(src: &'a [u8]) -> Self { Self { src, pos: 0 } }
+
+	/// # Read Bits.
+	fn read(&mut self, n: usize) -> Option<u32> {
+		let mut out = 0;
+		for i in 0..n {
+			let byte = self.src.get(self.pos / 8)?;
+			out |= u32::from((byte >> (self.pos % 8)) & 1) << i;
+			self.pos += 1;
+		}
+		Some(out)
+	}
+
+	/// # Read Bool.
+	fn bool(&mut self) -> Option<bool> { self.read(1).map(|b| b == 1) }
+
+	/// # Skip Bits.
+	fn skip(&mut self, n: usize) -> Option<()> { self.read(n).map(|_| ()) }
+
+	/// # Skip Size (or Preview) Header.
+	///
+	/// The two are laid out the same way, save for the bit depths.
+	fn size(&mut self, preview: bool) -> Option<()> {
+		/// # Dimension Bit Depths (Regular).
+		const SIZE: [usize; 4] = [9, 13, 18, 30];
+
+		/// # Dimension Bit Depths (Preview, Div8).
+		const PREVIEW_DIV8: [usize; 4] = [0, 0, 5, 9];
+
+		/// # Dimension Bit Depths (Preview).
+		const PREVIEW: [usize; 4] = [6, 8, 10, 12];
+
+		let depths =
+			if preview {
+				if self.bool()? { PREVIEW_DIV8 } else { PREVIEW }
+			}
+			else if self.bool()? {
+				// Small images use fixed five-bit dimensions.
+				self.skip(5)?;
+				if 0 == self.read(3)? { self.skip(5)?; }
+				return Some(());
+			}
+			else { SIZE };
+
+		let sel = self.read(2)? as usize;
+		self.skip(depths[sel])?;
+		if 0 == self.read(3)? {
+			let sel = self.read(2)? as usize;
+			self.skip(depths[sel])?;
+		}
+		Some(())
+	}
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// # Bit Writer.
+	#[derive(Default)]
+	struct Writer(Vec<bool>);
+
+	impl Writer {
+		/// # Write Bits.
+		fn write(&mut self, n: usize, v: u32) -> &mut Self {
+			for i in 0..n { self.0.push(1 == (v >> i) & 1); }
+			self
+		}
+
+		/// # Finish.
+		fn finish(&self) -> Vec<u8> {
+			let mut out = CODESTREAM.to_vec();
+			for chunk in self.0.chunks(8) {
+				out.push(chunk.iter().enumerate().fold(0, |acc, (i, b)| acc | (u8::from(*b) << i)));
+			}
+			out
+		}
+	}
+
+	/// # Header With Animation.
+	///
+	/// Build a codestream header with a regular size, intrinsic size, and
+	/// preview, with the `have_animation` bit set as specified.
+	fn header(animated: bool) -> Vec<u8> {
+		let mut w = Writer::default();
+		w
+			.write(1, 0).write(2, 1).write(13, 1999).write(3, 0).write(2, 0).write(9, 300) // Size.
+			.write(1, 0).write(1, 1).write(3, 5) // Metadata, extra fields, orientation.
+			.write(1, 1).write(1, 1).write(5, 7).write(3, 1) // Intrinsic size (small).
+			.write(1, 1).write(1, 1).write(2, 2).write(5, 3).write(3, 0).write(2, 0) // Preview.
+			.write(1, u32::from(animated))
+			.write(2, 0).write(2, 0).write(1, 0); // Some of the AnimationHeader.
+		w.finish()
+	}
+
+	#[test]
+	fn t_is_animated() {
+		// Our static test image.
+		let raw = std::fs::read("skel/assets/ace.jxl").expect("Unable to read ace.jxl.");
+		assert!(! is_animated(&raw), "Static JPEG XL flagged as animated.");
+
+		// Synthetic headers, naked and boxed.
+		for animated in [false, true] {
+			let raw = header(animated);
+			assert_eq!(is_animated(&raw), animated, "Codestream animation mismatch.");
+
+			let mut boxed = CONTAINER.to_vec();
+			boxed.extend_from_slice(b"\0\0\0\x14ftypjxl \0\0\0\0jxl ");
+			boxed.extend_from_slice(&u32::try_from(raw.len() + 8).unwrap().to_be_bytes());
+			boxed.extend_from_slice(b"jxlc");
+			boxed.extend_from_slice(&raw);
+			assert_eq!(is_animated(&boxed), animated, "Container animation mismatch.");
+
+			let mut boxed = CONTAINER.to_vec();
+			boxed.extend_from_slice(&u32::try_from(raw.len() + 12).unwrap().to_be_bytes());
+			boxed.extend_from_slice(b"jxlp\0\0\0\0");
+			boxed.extend_from_slice(&raw);
+			assert_eq!(is_animated(&boxed), animated, "Partial container animation mismatch.");
+		}
+
+		// Animations can't be decoded properly, so shouldn't be decoded at all.
+		assert_eq!(
+			decode(&header(true), false).err(),
+			Some(PxsumError::Unsupported),
+			"Animated JPEG XL was decoded.",
+		);
+
+		// All-default metadata and junk are static.
+		let mut w = Writer::default();
+		w.write(1, 1).write(5, 3).write(3, 1).write(1, 1);
+		assert!(! is_animated(&w.finish()), "Default metadata flagged as animated.");
+		assert!(! is_animated(b"\xff\x0a"), "Truncated stream flagged as animated.");
+		assert!(! is_animated(b"GIF89a"), "Non-JXL flagged as animated.");
+	}
+}
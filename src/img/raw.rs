@@ -0,0 +1,435 @@
+/*!
+# pxsum: Camera RAW.
+*/
+
+use crate::{
+	PxKind,
+	PxsumError,
+};
+use image::{
+	DynamicImage,
+	ImageBuffer,
+	metadata::Orientation,
+};
+use rawloader::{
+	RawImage,
+	RawImageData,
+};
+
+
+
+/// # TIFF Tag: Make.
+const TAG_MAKE: u16 = 0x010f;
+
+/// # TIFF Tag: Orientation.
+const TAG_ORIENTATION: u16 = 0x0112;
+
+/// # TIFF Tag: Sub-IFDs.
+const TAG_SUB_IFDS: u16 = 0x014a;
+
+/// # TIFF Tag: DNG Version.
+const TAG_DNG_VERSION: u16 = 0xc612;
+
+
+
+/// # Identify RAW.
+///
+/// CR2s have a signature of their own, but NEFs, ARWs, and DNGs are
+/// indistinguishable from regular TIFFs until you peek at their first
+/// directory. DNGs carry a version tag; the others are recognized by make,
+/// and the sub-directories holding the actual sensor data.
+///
+/// Returns `None` if the TIFF is just a TIFF.
+pub(super) fn kind(src: &[u8]) -> Option<PxKind> {
+	let ifd = Ifd0::new(src)?;
+	if ifd.entry(TAG_DNG_VERSION).is_some() { return Some(PxKind::Dng); }
+	ifd.entry(TAG_SUB_IFDS)?;
+
+	let make = ifd.ascii(TAG_MAKE)?;
+	if make.starts_with(b"NIKON") { Some(PxKind::Nef) }
+	else if make.starts_with(b"SONY") { Some(PxKind::Arw) }
+	else { None }
+}
+
+/// # Decode RAW (Demosaiced).
+///
+/// Develop the RAW with fixed parameters, so the results never depend on
+/// anything but the file itself:
+///
+/// * The default crop;
+/// * Black/white level normalization;
+/// * The camera's white balance (as shot);
+/// * Bilinear demosaicing;
+/// * The sRGB transfer curve.
+///
+/// The colors are left in the camera's own space, so color management is
+/// unsupported.
+///
+/// ## Errors
+///
+/// This will return an error if the RAW cannot be decoded, or if color
+/// management was requested.
+pub(super) fn decode(src: &[u8], oriented: bool, managed: bool)
+-> Result<DynamicImage, PxsumError> {
+	if managed { return Err(PxsumError::Unsupported); }
+
+	let raw = load(src)?;
+	let mut img = develop(&raw).ok_or(PxsumError::Decode)?;
+	if oriented { img.apply_orientation(orientation(src)); }
+	Ok(img)
+}
+
+/// # Decode RAW (Sensor).
+///
+/// Return the untouched sensor data — every photosite, including those
+/// outside the default crop — as grayscale for mosaiced sensors, or RGB
+/// for the (rare) linear ones. Integer samples are returned as-is rather
+/// than scaled, making this the truly lossless representation.
+///
+/// ## Errors
+///
+/// This will return an error if the RAW cannot be decoded.
+pub(super) fn sensor(src: &[u8]) -> Result<DynamicImage, PxsumError> {
+	let raw = load(src)?;
+	let width = u32::try_from(raw.width).map_err(|_| PxsumError::Decode)?;
+	let height = u32::try_from(raw.height).map_err(|_| PxsumError::Decode)?;
+
+	match (raw.cpp, raw.data) {
+		(1, RawImageData::Integer(buf)) => ImageBuffer::from_raw(width, height, buf)
+			.map(DynamicImage::ImageLuma16),
+		(3, RawImageData::Integer(buf)) => ImageBuffer::from_raw(width, height, buf)
+			.map(DynamicImage::ImageRgb16),
+		// There's no grayscale float type, so mosaiced floats are tripled.
+		(1, RawImageData::Float(buf)) => ImageBuffer::from_raw(
+			width,
+			height,
+			buf.into_iter().flat_map(|v| [v, v, v]).collect(),
+		)
+			.map(DynamicImage::ImageRgb32F),
+		(3, RawImageData::Float(buf)) => ImageBuffer::from_raw(width, height, buf)
+			.map(DynamicImage::ImageRgb32F),
+		_ => None,
+	}
+		.ok_or(PxsumError::Decode)
+}
+
+/// # Load RAW.
+fn load(src: &[u8]) -> Result<RawImage, PxsumError> {
+	rawloader::decode(&mut std::io::Cursor::new(src)).map_err(|_| PxsumError::Decode)
+}
+
+/// # Orientation.
+///
+/// Return the orientation recorded in the first TIFF directory, if any.
+fn orientation(src: &[u8]) -> Orientation {
+	Ifd0::new(src)
+		.and_then(|ifd| ifd.short(TAG_ORIENTATION))
+		.and_then(|v| u8::try_from(v).ok())
+		.and_then(Orientation::from_exif)
+		.unwrap_or(Orientation::NoTransforms)
+}
+
+/// # Develop.
+///
+/// See `decode` for the particulars.
+fn develop(raw: &RawImage) -> Option<DynamicImage> {
+	let [top, right, bottom, left] = raw.crops;
+	let out_width = raw.width.checked_sub(left + right).filter(|&n| n != 0)?;
+	let out_height = raw.height.checked_sub(top + bottom).filter(|&n| n != 0)?;
+	if raw.width.checked_mul(raw.height)?.checked_mul(raw.cpp)? != raw_len(&raw.data) {
+		return None;
+	}
+
+	// Per-color scaling: black/white levels and white balance, relative to
+	// green.
+	let wb = |c: usize| {
+		let (v, g) = (raw.wb_coeffs[c], raw.wb_coeffs[1]);
+		if v.is_finite() && g.is_finite() && 0.0 < v && 0.0 < g { v / g }
+		else { 1.0 }
+	};
+	let level = |c: usize, v: f32| match raw.data {
+		RawImageData::Integer(_) => {
+			let black = f32::from(raw.blacklevels[c]);
+			let range = f32::from(raw.whitelevels[c]) - black;
+			if 0.0 < range { ((v - black) / range).clamp(0.0, 1.0) * wb(c) }
+			else { 0.0 }
+		},
+		RawImageData::Float(_) => v.clamp(0.0, 1.0) * wb(c),
+	};
+	let sample = |idx: usize| match &raw.data {
+		RawImageData::Integer(buf) => f32::from(buf[idx]),
+		RawImageData::Float(buf) => buf[idx],
+	};
+
+	let mut out = Vec::with_capacity(out_width * out_height * 3);
+	for row in top..top + out_height {
+		for col in left..left + out_width {
+			let px: [f32; 3] =
+				// Linear sensors just need leveling.
+				if raw.cpp == 3 {
+					let idx = (row * raw.width + col) * 3;
+					[0, 1, 2].map(|c| level(c, sample(idx + c)))
+				}
+				// Mosaiced ones need the blanks filled in.
+				else {
+					let mut sum = [0.0_f32; 3];
+					let mut count = [0_u8; 3];
+					let own = channel(raw.cfa.color_at(row, col));
+					for y in row.saturating_sub(1)..(row + 2).min(raw.height) {
+						for x in col.saturating_sub(1)..(col + 2).min(raw.width) {
+							let color = raw.cfa.color_at(y, x);
+							let c = channel(color);
+							if c == own && (y != row || x != col) { continue; }
+							sum[c] += level(color, sample(y * raw.width + x));
+							count[c] += 1;
+						}
+					}
+					[0, 1, 2].map(|c|
+						if count[c] == 0 { 0.0 }
+						else { sum[c] / f32::from(count[c]) }
+					)
+				};
+
+			for v in px {
+				out.push(u16_from_linear(v));
+			}
+		}
+	}
+
+	ImageBuffer::from_raw(
+		u32::try_from(out_width).ok()?,
+		u32::try_from(out_height).ok()?,
+		out,
+	)
+		.map(DynamicImage::ImageRgb16)
+}
+
+/// # CFA Color to Channel.
+///
+/// The fourth CFA color — emerald, in the odd RGBE sensor — is treated as
+/// green.
+const fn channel(color: usize) -> usize {
+	if color < 3 { color } else { 1 }
+}
+
+/// # Raw Data Length.
+const fn raw_len(data: &RawImageData) -> usize {
+	match data {
+		RawImageData::Integer(buf) => buf.len(),
+		RawImageData::Float(buf) => buf.len(),
+	}
+}
+
+#[expect(clippy::cast_possible_truncation, reason = "False positive.")]
+#[expect(clippy::cast_sign_loss, reason = "False positive.")]
+/// # Linear to sRGB16.
+///
+/// Clamp the (possibly white-balanced past one) linear value, apply the
+/// sRGB transfer curve, and scale the result to sixteen bits.
+fn u16_from_linear(v: f32) -> u16 {
+	let v = v.clamp(0.0, 1.0);
+	let v =
+		if v <= 0.003_130_8 { v * 12.92 }
+		else { 1.055_f32.mul_add(v.powf(1.0 / 2.4), -0.055) };
+	(v * 65_535.0).round() as u16
+}
+
+
+
+/// # First TIFF Directory.
+///
+/// This is just enough of a TIFF parser to look up a few tags in the first
+/// image file directory.
+struct Ifd0<'a> {
+	/// # Source.
+	src: &'a [u8],
+
+	/// # Directory Entries.
+	entries: &'a [u8],
+
+	/// # Big Endian?
+	be: bool,
+}
+
+impl<'a> Ifd0<'a> {
+	/// # New.
+	fn new(src: &'a [u8]) -> Option<Self> {
+		let be = match src.get(..4)? {
+			b"II*\0" => false,
+			b"MM\0*" => true,
+			_ => return None,
+		};
+		let mut out = Self { src, entries: &[], be };
+		let pos = usize::try_from(out.u32(src.get(4..8)?)?).ok()?;
+		let count = usize::from(out.u16(src.get(pos..pos + 2)?)?);
+		out.entries = src.get(pos + 2..pos + 2 + count * 12)?;
+		Some(out)
+	}
+
+	/// # Entry.
+	///
+	/// Return the raw twelve-byte entry for the tag, if present.
+	fn entry(&self, tag: u16) -> Option<&'a [u8]> {
+		self.entries.chunks_exact(12).find(|e| self.u16(&e[..2]) == Some(tag))
+	}
+
+	/// # ASCII Value.
+	fn ascii(&self, tag: u16) -> Option<&'a [u8]> {
+		let entry = self.entry(tag)?;
+		if self.u16(&entry[2..4])? != 2 { return None; }
+		let len = usize::try_from(self.u32(&entry[4..8])?).ok()?;
+		if len <= 4 { entry.get(8..8 + len) }
+		else {
+			let pos = usize::try_from(self.u32(&entry[8..12])?).ok()?;
+			self.src.get(pos..pos + len)
+		}
+	}
+
+	/// # Short Value.
+	fn short(&self, tag: u16) -> Option<u16> {
+		let entry = self.entry(tag)?;
+		if self.u16(&entry[2..4])? == 3 { self.u16(&entry[8..10]) }
+		else { None }
+	}
+
+	/// # Read U16.
+	fn u16(&self, src: &[u8]) -> Option<u16> {
+		let src: [u8; 2] = src.try_into().ok()?;
+		Some(if self.be { u16::from_be_bytes(src) } else { u16::from_le_bytes(src) })
+	}
+
+	/// # Read U32.
+	fn u32(&self, src: &[u8]) -> Option<u32> {
+		let src: [u8; 4] = src.try_into().ok()?;
+		Some(if self.be { u32::from_be_bytes(src) } else { u32::from_le_bytes(src) })
+	}
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// # Build TIFF Header + IFD0.
+	fn tiff(entries: &[(u16, u16, u32, [u8; 4])], extra: &[u8]) -> Vec<u8> {
+		let mut out = b"II*\0\x08\0\0\0".to_vec();
+		out.extend_from_slice(&u16::try_from(entries.len()).unwrap().to_le_bytes());
+		for (tag, kind, count, value) in entries {
+			out.extend_from_slice(&tag.to_le_bytes());
+			out.extend_from_slice(&kind.to_le_bytes());
+			out.extend_from_slice(&count.to_le_bytes());
+			out.extend_from_slice(value);
+		}
+		out.extend_from_slice(&[0, 0, 0, 0]);
+		out.extend_from_slice(extra);
+		out
+	}
+
+	#[test]
+	fn t_kind() {
+		// The make is stored out of line, right after the directory.
+		let make = |make: &[u8], sub: bool| {
+			let len = u32::try_from(make.len()).unwrap();
+			let pos: u32 = if sub { 38 } else { 26 };
+			let mut entries = vec![(TAG_MAKE, 2, len, pos.to_le_bytes())];
+			if sub { entries.push((TAG_SUB_IFDS, 4, 1, [0, 0, 0, 0])); }
+			tiff(&entries, make)
+		};
+		assert_eq!(kind(&make(b"NIKON CORPORATION\0", true)), Some(PxKind::Nef));
+		assert_eq!(kind(&make(b"SONY\0\0\0\0\0", true)), Some(PxKind::Arw));
+		assert_eq!(kind(&make(b"Canon\0\0\0\0", true)), None);
+		assert_eq!(kind(&make(b"NIKON CORPORATION\0", false)), None);
+
+		// DNGs are DNGs.
+		let dng = tiff(&[(TAG_DNG_VERSION, 1, 4, [1, 4, 0, 0])], &[]);
+		assert_eq!(kind(&dng), Some(PxKind::Dng));
+
+		// CR2s have their own signature, but the rest need refinement.
+		assert_eq!(PxKind::try_from_magic(b"II*\0\x10\0\0\0CR\x02\0"), Ok(PxKind::Cr2));
+		assert_eq!(PxKind::try_from_magic(&dng).map(|k| k.refine(&dng)), Ok(PxKind::Dng));
+		assert_eq!(PxKind::Png.refine(&dng), PxKind::Png);
+
+		// And nothing is nothing.
+		assert_eq!(kind(b"II*\0\x08\0\0\0"), None);
+		assert_eq!(kind(b"nope"), None);
+
+		// Orientation.
+		let tagged = tiff(&[(TAG_ORIENTATION, 3, 1, [6, 0, 0, 0])], &[]);
+		assert_eq!(orientation(&tagged), Orientation::Rotate90);
+		assert_eq!(orientation(&dng), Orientation::NoTransforms);
+	}
+
+	#[test]
+	fn t_dng() {
+		// A 16x8 RGGB mosaic with twelve-bit samples, as-shot white balance,
+		// and nothing else in the way.
+		let raw = std::fs::read("skel/assets/bayer.dng").expect("Unable to read bayer.dng.");
+		assert_eq!(PxKind::try_from_magic(&raw).map(|k| k.refine(&raw)), Ok(PxKind::Dng));
+
+		// The sensor data should come through untouched.
+		let Ok(DynamicImage::ImageLuma16(img)) = sensor(&raw) else {
+			panic!("Sensor decode failed.");
+		};
+		assert_eq!(img.dimensions(), (16, 8));
+		for (x, y, px) in img.enumerate_pixels() {
+			assert_eq!(px.0[0], u16::try_from((x * 97 + y * 31) % 256 * 16).unwrap(), "Photosite {x}x{y} changed.");
+		}
+
+		// Developed, it should be RGB, and deterministic.
+		let Ok(DynamicImage::ImageRgb16(img)) = decode(&raw, false, false) else {
+			panic!("Develop failed.");
+		};
+		assert_eq!(img.dimensions(), (16, 8));
+		let Ok(DynamicImage::ImageRgb16(img2)) = decode(&raw, true, false) else {
+			panic!("Develop failed.");
+		};
+		assert_eq!(img, img2, "Non-rotated DNG changed under orientation.");
+
+		// Color management is unsupported.
+		assert_eq!(decode(&raw, false, true).err(), Some(PxsumError::Unsupported));
+	}
+
+	#[test]
+	fn t_develop() {
+		// A 4x4 RGGB mosaic, with a two-pixel crop on the right and bottom.
+		let raw = RawImage {
+			make: String::new(),
+			model: String::new(),
+			clean_make: String::new(),
+			clean_model: String::new(),
+			width: 4,
+			height: 4,
+			cpp: 1,
+			wb_coeffs: [2.0, 1.0, 1.0, f32::NAN],
+			whitelevels: [1023; 4],
+			blacklevels: [0; 4],
+			xyz_to_cam: [[0.0; 3]; 4],
+			cfa: rawloader::CFA::new("RGGB"),
+			crops: [0, 2, 2, 0],
+			blackareas: Vec::new(),
+			data: RawImageData::Integer(vec![
+				100, 200, 100, 200,
+				200, 300, 200, 300,
+				100, 200, 100, 200,
+				200, 300, 200, 300,
+			]),
+		};
+		let Some(DynamicImage::ImageRgb16(img)) = develop(&raw) else {
+			panic!("Develop failed.");
+		};
+		assert_eq!(img.dimensions(), (2, 2));
+
+		// The top-left red photosite has its own red (doubled by the white
+		// balance), the average of its four green neighbors, and its one blue
+		// neighbor.
+		let expected = [200.0_f32, 200.0, 300.0].map(|v| u16_from_linear(v / 1023.0));
+		assert_eq!(img.get_pixel(0, 0).0, expected);
+		assert_eq!(u16_from_linear(2.0), u16::MAX);
+		assert_eq!(u16_from_linear(-1.0), 0);
+
+		// Bad lengths are bad.
+		let short = RawImage { data: RawImageData::Integer(vec![0; 15]), ..raw };
+		assert!(develop(&short).is_none());
+	}
+}
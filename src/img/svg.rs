@@ -0,0 +1,310 @@
+/*!
+# pxsum: SVG Rasterization.
+*/
+
+use crate::PxsumError;
+use image::{
+	DynamicImage,
+	RgbaImage,
+};
+use resvg::{
+	tiny_skia::{
+		Pixmap,
+		Transform,
+	},
+	usvg::{
+		Options,
+		Tree,
+	},
+};
+
+
+
+/// # Maximum Dimension.
+///
+/// Renders are capped at this many pixels per side to keep a hostile (or
+/// typo'd) size from eating all the RAM.
+const MAX_SIZE: u32 = 16_384;
+
+/// # Sniff Limit.
+///
+/// The root element has to turn up within this many bytes for the data to be
+/// treated as SVG.
+const SNIFF_SIZE: usize = 4096;
+
+
+
+/// # Render SVG.
+///
+/// Parse and rasterize the (optionally gzipped) SVG, returning the pixels
+/// as straight (un-premultiplied) RGBA.
+///
+/// The `size` is the desired width and height, with zero meaning "auto". If
+/// both are zero, the image is rendered at its intrinsic size; if only one
+/// is, it is derived from the other to preserve the aspect ratio.
+///
+/// Rendering is kept deterministic by giving the parser nothing external to
+/// go on: there is no font database — text is not rendered at all, so should
+/// be converted to paths beforehand — and no resource directory, so linked
+/// (as opposed to embedded) images are ignored.
+///
+/// ## Errors
+///
+/// This will return an error if the document is invalid or the size is
+/// unrenderable.
+pub(super) fn render(src: &[u8], size: [u32; 2]) -> Result<DynamicImage, PxsumError> {
+	// Linked images would be read relative to the working directory, making
+	// the render depend on whatever happens to be lying around; only embedded
+	// (data: URI) images are allowed.
+	let mut options = Options::default();
+	options.image_href_resolver.resolve_string = Box::new(|_, _| None);
+
+	let tree = Tree::from_data(src, &options).map_err(|_| PxsumError::Decode)?;
+	let intrinsic = tree.size();
+	let [width, height] = scale(intrinsic.width(), intrinsic.height(), size)
+		.ok_or(PxsumError::Decode)?;
+
+	let mut pixmap = Pixmap::new(width, height).ok_or(PxsumError::Decode)?;
+	let transform = Transform::from_scale(
+		f32_from_u32(width) / intrinsic.width(),
+		f32_from_u32(height) / intrinsic.height(),
+	);
+	resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+	let buf: Vec<u8> = pixmap.pixels()
+		.iter()
+		.flat_map(|px| {
+			let px = px.demultiply();
+			[px.red(), px.green(), px.blue(), px.alpha()]
+		})
+		.collect();
+	RgbaImage::from_raw(width, height, buf)
+		.map(DynamicImage::ImageRgba8)
+		.ok_or(PxsumError::Decode)
+}
+
+/// # Has SVG Root?
+///
+/// XML declarations and byte order marks aren't specific to SVG, so rather
+/// than trusting the prefix, skip past the prolog — whitespace, processing
+/// instructions, comments, and the doctype — and make sure the first real
+/// element is an `<svg>`.
+///
+/// Only the first `SNIFF_SIZE` bytes are considered.
+pub(super) const fn has_root(src: &[u8]) -> bool {
+	let len = if src.len() < SNIFF_SIZE { src.len() } else { SNIFF_SIZE };
+	let mut i =
+		if starts_at(src, len, 0, b"\xef\xbb\xbf") { 3 }
+		else { 0 };
+
+	while i < len {
+		match src[i] {
+			b' ' | b'\t' | b'\r' | b'\n' => { i += 1; },
+			b'<' =>
+				if starts_at(src, len, i, b"<!--") {
+					i = match find(src, len, i + 4, b"-->") {
+						Some(j) => j + 3,
+						None => return false,
+					};
+				}
+				else if starts_at(src, len, i, b"<?") {
+					i = match find(src, len, i + 2, b"?>") {
+						Some(j) => j + 2,
+						None => return false,
+					};
+				}
+				else if starts_at(src, len, i, b"<!") {
+					// The doctype might have an internal subset with its own
+					// angle brackets.
+					let mut subset = false;
+					i += 2;
+					while i < len && (subset || src[i] != b'>') {
+						if src[i] == b'[' { subset = true; }
+						else if src[i] == b']' { subset = false; }
+						i += 1;
+					}
+					i += 1;
+				}
+				else {
+					return
+						starts_at(src, len, i, b"<svg") &&
+						i + 4 < len &&
+						matches!(src[i + 4], b' ' | b'\t' | b'\r' | b'\n' | b'/' | b'>');
+				},
+			_ => return false,
+		}
+	}
+
+	false
+}
+
+/// # Starts At?
+///
+/// Returns true if `src[i..len]` starts with `needle`.
+const fn starts_at(src: &[u8], len: usize, i: usize, needle: &[u8]) -> bool {
+	if len < i || len - i < needle.len() { return false; }
+	let mut j = 0;
+	while j < needle.len() {
+		if src[i + j] != needle[j] { return false; }
+		j += 1;
+	}
+	true
+}
+
+/// # Find.
+///
+/// Return the index of the first `needle` in `src[i..len]`, if any.
+const fn find(src: &[u8], len: usize, mut i: usize, needle: &[u8]) -> Option<usize> {
+	while i < len {
+		if starts_at(src, len, i, needle) { return Some(i); }
+		i += 1;
+	}
+	None
+}
+
+#[expect(clippy::cast_precision_loss, reason = "Dimensions are capped well below 2^24.")]
+/// # U32 to F32.
+const fn f32_from_u32(n: u32) -> f32 { n as f32 }
+
+#[expect(clippy::cast_possible_truncation, reason = "False positive.")]
+#[expect(clippy::cast_sign_loss, reason = "False positive.")]
+/// # Output Dimensions.
+///
+/// Work out the pixel dimensions for the render, rounding fractional
+/// intrinsic sizes up. See `render` for the meaning of `size`.
+///
+/// Returns `None` if either dimension would be zero or exceed `MAX_SIZE`.
+fn scale(width: f32, height: f32, size: [u32; 2]) -> Option<[u32; 2]> {
+	let px = |n: f32| {
+		let n = n.ceil();
+		if (1.0..=f32_from_u32(MAX_SIZE)).contains(&n) { Some(n as u32) }
+		else { None }
+	};
+
+	match size {
+		[0, 0] => Some([px(width)?, px(height)?]),
+		[w, 0] => Some([px(f32_from_u32(w))?, px(height * f32_from_u32(w) / width)?]),
+		[0, h] => Some([px(width * f32_from_u32(h) / height)?, px(f32_from_u32(h))?]),
+		[w, h] => Some([px(f32_from_u32(w))?, px(f32_from_u32(h))?]),
+	}
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::PxKind;
+
+	/// # Test Icon.
+	const ICON: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="8" viewBox="0 0 16 8"><rect x="0" y="0" width="8" height="8" fill="#f00"/><rect x="8" y="0" width="8" height="8" fill="blue" fill-opacity="0.5"/></svg>"##;
+
+	#[test]
+	fn t_render() {
+		let img = render(ICON.as_bytes(), [0, 0]).expect("Render failed.");
+		let img = img.as_rgba8().expect("Wrong color type.");
+		assert_eq!(img.dimensions(), (16, 8));
+		assert_eq!(img.get_pixel(2, 2).0, [255, 0, 0, 255]);
+		assert_eq!(img.get_pixel(12, 2).0, [0, 0, 255, 128]);
+
+		// Syntactic changes shouldn't matter.
+		let reformatted = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Pretty! -->
+<svg viewBox="0 0 16 8" height="8" width="16" xmlns="http://www.w3.org/2000/svg">
+	<rect fill="red" height="8" width="8" />
+	<rect style="fill:#0000ff;fill-opacity:.5" height="8" width="8" x="8" />
+</svg>"#;
+		let img2 = render(reformatted.as_bytes(), [0, 0]).expect("Render failed.");
+		assert_eq!(img2.as_rgba8(), Some(img));
+
+		// Both should be recognized as such.
+		assert_eq!(PxKind::try_from_magic(ICON.as_bytes()), Ok(PxKind::Svg));
+		assert_eq!(PxKind::try_from_magic(reformatted.as_bytes()), Ok(PxKind::Svg));
+		assert_eq!(PxKind::try_from_magic(b"\n\n<svg></svg>\n"), Err(PxsumError::Decode));
+		assert_eq!(PxKind::try_from_extension(b"icon.svg"), Some(PxKind::Svg));
+		assert_eq!(PxKind::try_from_extension(b"icon.SVGZ"), Some(PxKind::Svg));
+
+		// Resizing.
+		let img = render(ICON.as_bytes(), [32, 0]).expect("Render failed.");
+		assert_eq!((img.width(), img.height()), (32, 16));
+		let img = render(ICON.as_bytes(), [0, 4]).expect("Render failed.");
+		assert_eq!((img.width(), img.height()), (8, 4));
+		let img = render(ICON.as_bytes(), [10, 10]).expect("Render failed.");
+		assert_eq!((img.width(), img.height()), (10, 10));
+		assert!(render(ICON.as_bytes(), [MAX_SIZE + 1, 0]).is_err());
+
+		// Garbage is garbage.
+		assert!(render(b"<svg nope", [0, 0]).is_err());
+	}
+
+	#[test]
+	fn t_render_linked() {
+		/// # Red Square.
+		const RED: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4"><rect width="4" height="4" fill="red"/></svg>"#;
+
+		/// # Image Wrapper.
+		fn svg(href: &str) -> String {
+			format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4"><image href="{href}" width="4" height="4"/></svg>"#)
+		}
+
+		// Embedded images are fine. (This is RED, base64-encoded.)
+		let img = render(
+			svg("data:image/svg+xml;base64,PHN2ZyB4bWxucz0iaHR0cDovL3d3dy53My5vcmcvMjAwMC9zdmciIHdpZHRoPSI0IiBoZWlnaHQ9IjQiPjxyZWN0IHdpZHRoPSI0IiBoZWlnaHQ9IjQiIGZpbGw9InJlZCIvPjwvc3ZnPg==").as_bytes(),
+			[0, 0],
+		).expect("Render failed.");
+		assert_eq!(img.as_rgba8().map(|img| img.get_pixel(2, 2).0), Some([255, 0, 0, 255]));
+
+		// Linked ones are not.
+		let path = std::env::temp_dir().join(format!("pxsum-linked-{}.svg", std::process::id()));
+		std::fs::write(&path, RED).expect("Unable to write temporary SVG.");
+		let img = render(svg(&path.to_string_lossy()).as_bytes(), [0, 0]);
+		let _res = std::fs::remove_file(&path);
+
+		let img = img.expect("Render failed.");
+		let img = img.as_rgba8().expect("Wrong color type.");
+		assert!(img.pixels().all(|px| px.0 == [0, 0, 0, 0]), "Linked image was rendered.");
+	}
+
+	#[test]
+	fn t_has_root() {
+		for raw in [
+			ICON.as_bytes(),
+			b"<svg version=\"1.1\"/>",
+			b"\xef\xbb\xbf<svg></svg>\n",
+			b"<?xml version=\"1.0\"?>\n<!-- <html> -->\n<svg\n>",
+			b"<?xml version=\"1.0\"?><!DOCTYPE svg PUBLIC \"-//W3C//DTD SVG 1.1//EN\" \"x.dtd\"><svg>",
+			b"<!DOCTYPE svg [<!ENTITY a \"<b>\">]><svg xmlns=\"http://www.w3.org/2000/svg\">",
+		] {
+			assert!(has_root(raw), "Missed SVG: {:?}", String::from_utf8_lossy(raw));
+			assert_eq!(PxKind::try_from_magic(raw), Ok(PxKind::Svg));
+		}
+
+		for raw in [
+			b"<?xml version=\"1.0\"?><rss version=\"2.0\"></rss>".as_slice(),
+			b"\xef\xbb\xbf<html><svg></svg></html>",
+			b"<?xml version=\"1.0\"?><!-- <svg> -->",
+			b"<?xml version=\"1.0\"?><svgfont/>",
+			b"<!DOCTYPE html><html>",
+			b"<svg",
+		] {
+			assert!(! has_root(raw), "False SVG: {:?}", String::from_utf8_lossy(raw));
+			assert_eq!(PxKind::try_from_magic(raw), Err(PxsumError::Decode));
+		}
+
+		// The root has to turn up early.
+		let mut raw = b"<?xml version=\"1.0\"?><!--".to_vec();
+		raw.resize(SNIFF_SIZE, b' ');
+		raw.extend_from_slice(b"--><svg></svg>");
+		assert!(! has_root(&raw), "Root found past the sniff limit.");
+	}
+
+	#[test]
+	fn t_scale() {
+		assert_eq!(scale(15.2, 7.0, [0, 0]), Some([16, 7]));
+		assert_eq!(scale(100.0, 50.0, [20, 0]), Some([20, 10]));
+		assert_eq!(scale(100.0, 50.0, [0, 20]), Some([40, 20]));
+		assert_eq!(scale(100.0, 50.0, [3, 3]), Some([3, 3]));
+		assert_eq!(scale(0.0, 50.0, [0, 0]), None);
+		assert_eq!(scale(100.0, 50.0, [0, MAX_SIZE]), None);
+	}
+}
@@ -0,0 +1,254 @@
+/*!
+# pxsum: Image Tiles.
+*/
+
+use std::fmt;
+
+
+
+/// # Tile Size.
+///
+/// Tiled checksums split each image (frame or page) into squares of this many
+/// pixels per side. Tiles along the right and bottom edges may be smaller.
+pub(super) const TILE: u32 = 64;
+
+/// # Merkle Leaf Prefix.
+const LEAF: u8 = 0;
+
+/// # Merkle Branch Prefix.
+const BRANCH: u8 = 1;
+
+
+
+#[derive(Debug, Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// # Tile Region.
+///
+/// This is a bounding box — in pixels — around a contiguous run of tiles
+/// whose hashes differ between two tiled checksums of the same-sized image.
+/// See `Checksum::changed_regions`.
+///
+/// When formatted, it uses the `WxH+X+Y` geometry notation, with a `#index`
+/// suffix for the frame or page of images having more than one.
+pub struct TileRegion {
+	/// # Frame or Page Index.
+	plane: Option<u32>,
+
+	/// # Left.
+	x: u32,
+
+	/// # Top.
+	y: u32,
+
+	/// # Width.
+	width: u32,
+
+	/// # Height.
+	height: u32,
+}
+
+impl fmt::Display for TileRegion {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}x{}+{}+{}", self.width, self.height, self.x, self.y)?;
+		if let Some(plane) = self.plane { write!(f, "#{plane}")?; }
+		Ok(())
+	}
+}
+
+impl TileRegion {
+	#[must_use]
+	/// # Frame or Page Index.
+	///
+	/// Return the index of the animation frame or container page the region
+	/// belongs to, if the image has more than one.
+	pub const fn plane(&self) -> Option<u32> { self.plane }
+
+	#[must_use]
+	/// # Left.
+	pub const fn x(&self) -> u32 { self.x }
+
+	#[must_use]
+	/// # Top.
+	pub const fn y(&self) -> u32 { self.y }
+
+	#[must_use]
+	/// # Width.
+	pub const fn width(&self) -> u32 { self.width }
+
+	#[must_use]
+	/// # Height.
+	pub const fn height(&self) -> u32 { self.height }
+}
+
+
+
+/// # Hash Tiles.
+///
+/// Hash each tile of each plane — i.e. frame or page — of `buf`, in order,
+/// returning the (Merkle leaf) digests.
+pub(super) fn hash_tiles(buf: &[u8], px: usize, planes: &[(u32, u32)]) -> Vec<[u8; 32]> {
+	let mut out = Vec::new();
+	let mut offset = 0;
+	for &(width, height) in planes {
+		let (width, height) = (width as usize, height as usize);
+		let tile = TILE as usize;
+		let Some(plane) = buf.get(offset..offset + width * height * px) else { break; };
+		offset += plane.len();
+
+		for y0 in (0..height).step_by(tile) {
+			for x0 in (0..width).step_by(tile) {
+				let mut hasher = blake3::Hasher::new();
+				hasher.update(&[LEAF]);
+				for y in y0..height.min(y0 + tile) {
+					let start = (y * width + x0) * px;
+					let end = (y * width + width.min(x0 + tile)) * px;
+					hasher.update(&plane[start..end]);
+				}
+				out.push(<[u8; 32]>::from(hasher.finalize()));
+			}
+		}
+	}
+	out
+}
+
+/// # Merkle Root.
+///
+/// Pair up and hash the digests level by level until only one remains. (Odd
+/// digests out are promoted as-is.)
+pub(super) fn merkle_root(mut level: Vec<[u8; 32]>) -> [u8; 32] {
+	while 1 < level.len() {
+		level = level.chunks(2)
+			.map(|pair|
+				if let [a, b] = pair {
+					let mut hasher = blake3::Hasher::new();
+					hasher.update(&[BRANCH]);
+					hasher.update(a);
+					hasher.update(b);
+					<[u8; 32]>::from(hasher.finalize())
+				}
+				else { pair[0] }
+			)
+			.collect();
+	}
+	level.first().copied().unwrap_or_default()
+}
+
+/// # Changed Regions.
+///
+/// Compare two sets of tile digests for the same plane layout, grouping the
+/// changed tiles of each plane into (four-way) connected runs and returning
+/// their bounding boxes.
+///
+/// If the digest counts don't match the layout, the images aren't comparable
+/// and nothing is returned.
+pub(super) fn changed_regions(planes: &[(u32, u32)], a: &[[u8; 8]], b: &[[u8; 8]])
+-> Vec<TileRegion> {
+	let total: usize = planes.iter().map(|&(w, h)| grid(w) * grid(h)).sum();
+	if a.len() != total || b.len() != total { return Vec::new(); }
+
+	let mut out = Vec::new();
+	let mut offset = 0;
+	let mut stack = Vec::new();
+	for (idx, &(width, height)) in planes.iter().enumerate() {
+		let (cols, rows) = (grid(width), grid(height));
+		let len = cols * rows;
+		let mut changed: Vec<bool> = a[offset..offset + len].iter()
+			.zip(&b[offset..offset + len])
+			.map(|(a, b)| a != b)
+			.collect();
+		offset += len;
+
+		// Flood-fill each run, clearing as we go.
+		for start in 0..len {
+			if ! changed[start] { continue; }
+			changed[start] = false;
+			stack.push(start);
+			let (mut x0, mut y0, mut x1, mut y1) = (cols, rows, 0, 0);
+			while let Some(pos) = stack.pop() {
+				let (x, y) = (pos % cols, pos / cols);
+				x0 = x0.min(x);
+				y0 = y0.min(y);
+				x1 = x1.max(x);
+				y1 = y1.max(y);
+
+				let mut visit = |next: usize| if changed[next] {
+					changed[next] = false;
+					stack.push(next);
+				};
+				if 0 < x { visit(pos - 1); }
+				if x + 1 < cols { visit(pos + 1); }
+				if 0 < y { visit(pos - cols); }
+				if y + 1 < rows { visit(pos + cols); }
+			}
+
+			// Convert to pixels.
+			let (x, y) = (pixels(x0), pixels(y0));
+			out.push(TileRegion {
+				plane: (1 < planes.len()).then(|| u32::try_from(idx).unwrap_or(u32::MAX)),
+				x,
+				y,
+				width: width.min(pixels(x1 + 1)) - x,
+				height: height.min(pixels(y1 + 1)) - y,
+			});
+		}
+	}
+
+	out
+}
+
+/// # Grid Length.
+///
+/// Return the number of tiles needed to cover `len` pixels.
+const fn grid(len: u32) -> usize { len.div_ceil(TILE) as usize }
+
+#[expect(clippy::cast_possible_truncation, reason = "Grids come from u32 lengths.")]
+/// # Grid Position to Pixels.
+const fn pixels(pos: usize) -> u32 { (pos as u32).saturating_mul(TILE) }
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_merkle_root() {
+		let leaves: Vec<[u8; 32]> = (0..5_u8).map(|n| [n; 32]).collect();
+
+		// One leaf is its own root; nothing is nothing.
+		assert_eq!(merkle_root(leaves[..1].to_vec()), leaves[0]);
+		assert_eq!(merkle_root(Vec::new()), [0; 32]);
+
+		// Order matters.
+		let root = merkle_root(leaves.clone());
+		let mut swapped = leaves.clone();
+		swapped.swap(1, 2);
+		assert_ne!(root, merkle_root(swapped), "Leaf order should matter.");
+		assert_eq!(root, merkle_root(leaves), "Roots should be deterministic.");
+	}
+
+	#[test]
+	fn t_changed_regions() {
+		// A 200x150 image has a 4x3 grid.
+		let planes = [(200, 150)];
+		let a = vec![[0_u8; 8]; 12];
+		assert!(changed_regions(&planes, &a, &a).is_empty(), "Nothing changed.");
+		assert!(changed_regions(&planes, &a, &a[1..]).is_empty(), "Incomparable.");
+
+		// Change two adjacent tiles in the middle, and the bottom-right one.
+		let mut b = a.clone();
+		b[5][0] = 1;
+		b[6][0] = 1;
+		b[11][0] = 1;
+		let regions: Vec<String> = changed_regions(&planes, &a, &b).iter()
+			.map(ToString::to_string)
+			.collect();
+		assert_eq!(regions, ["128x64+64+64", "8x22+192+128"]);
+
+		// Multiple planes get numbered.
+		let planes = [(64, 64), (64, 64)];
+		let regions: Vec<String> = changed_regions(&planes, &[[0; 8], [0; 8]], &[[0; 8], [1; 8]]).iter()
+			.map(ToString::to_string)
+			.collect();
+		assert_eq!(regions, ["64x64+0+0#1"]);
+	}
+}
@@ -12,7 +12,7 @@
 ///
 /// This will trim and ignore empty lines automatically, but otherwise doesn't
 /// go out of its way to ensure the data is properly formatted.
-pub(super) struct ManifestLines<I: Iterator<Item=String>> {
+pub struct ManifestLines<I: Iterator<Item=String>> {
 	/// # Iterator.
 	iter: I,
 
@@ -22,7 +22,7 @@ pub(super) struct ManifestLines<I: Iterator<Item=String>> {
 
 impl<I: Iterator<Item=String>> ManifestLines<I> {
 	/// # New.
-	pub(super) const fn new(iter: I) -> Self {
+	pub const fn new(iter: I) -> Self {
 		Self { iter, buf: None }
 	}
 }
@@ -43,7 +43,7 @@ impl<I: Iterator<Item=String>> Iterator for ManifestLines<I> {
 			if len == 0 || line.trim_start().is_empty() { continue; }
 
 			// Grouped: new checksum.
-			if len == 64 && line.bytes().all(|b| b.is_ascii_hexdigit()) {
+			if is_group_checksum(&line) {
 				self.buf.replace(line);
 				continue;
 			}
@@ -68,6 +68,24 @@ impl<I: Iterator<Item=String>> Iterator for ManifestLines<I> {
 
 
 
+/// # Group Checksum?
+///
+/// Grouped manifests list each checksum on its own line — 64 hex digits,
+/// plus a colon and the tile digests for tiled checksums — followed by the
+/// indented paths.
+fn is_group_checksum(line: &str) -> bool {
+	let Some((chk, tiles)) = line.split_at_checked(64) else { return false; };
+	chk.bytes().all(|b| b.is_ascii_hexdigit()) &&
+	(
+		tiles.is_empty() ||
+		tiles.strip_prefix(':').is_some_and(|t|
+			! t.is_empty() && t.bytes().all(|b| b.is_ascii_hexdigit())
+		)
+	)
+}
+
+
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -118,6 +136,30 @@ mod test {
 		}
 	}
 
+	#[test]
+	fn t_manifest_lines_tiled() {
+		let chk = "5e0d6a0a3b0a8bdf0f4e6b9d1c5a1e1c9a0b2f5f8b1e6c7d6a2d4b7e3f0a9c81";
+		let tiles = "0123456789abcdef0123456789abcdef";
+		let lines = [
+			format!("{chk}:{tiles}"),
+			"  ./a.png".to_owned(),
+			"  ./b.png".to_owned(),
+			chk.to_owned(),
+			"  ./c.png".to_owned(),
+			format!("{chk}:nope"),
+		];
+		let out: Vec<String> = ManifestLines::new(lines.into_iter()).collect();
+		assert_eq!(
+			out,
+			[
+				format!("{chk}:{tiles}  ./a.png"),
+				format!("{chk}:{tiles}  ./b.png"),
+				format!("{chk}  ./c.png"),
+				format!("{chk}:nope"),
+			],
+		);
+	}
+
 	#[test]
 	fn t_manifest_lines_strict() {
 		let expected = run_read_reference("skel/strict.chk");
@@ -0,0 +1,169 @@
+/*!
+# pxsum
+
+This library exposes the pixel-checksumming machinery behind the `pxsum` CLI
+so that it can be used directly from Rust, no shelling out required.
+
+## Examples
+
+Crunch a checksum from a file path:
+
+```
+use pxsum::Checksum;
+
+let mut chk = Checksum::new(Checksum::DEFAULT);
+chk.crunch("skel/assets/carl.jpg").unwrap();
+println!("{chk}"); // "<64 hex chars>  ./skel/assets/carl.jpg"
+```
+
+`Checksum::DEFAULT` matches the CLI's default mode. Passing `0` instead hashes
+only the first frame of animated or multi-page images.
+
+Or from raw bytes (or any other `std::io::Read` source):
+
+```
+use pxsum::Checksum;
+
+let raw = std::fs::read("skel/assets/carl.jpg").unwrap();
+let mut chk = Checksum::new(Checksum::DEFAULT);
+chk.crunch_bytes(&raw).unwrap();
+assert_eq!(chk.src(), "-");
+```
+
+Existing pxsum/path pairs — from any manifest flavor — can be verified too:
+
+```
+use pxsum::{
+    Checksum,
+    ManifestLines,
+};
+
+let list = std::fs::read_to_string("skel/loose.chk").unwrap();
+let mut chk = Checksum::new(0);
+for line in ManifestLines::new(list.lines().map(String::from)).take(1) {
+    let line = line.replace("  ./assets/", "  ./skel/assets/");
+    assert_eq!(chk.verify_existing(&line), Ok(true));
+}
+```
+*/
+
+#![forbid(unsafe_code)]
+
+#![deny(
+	clippy::allow_attributes_without_reason,
+	clippy::correctness,
+	unreachable_pub,
+)]
+
+#![warn(
+	clippy::complexity,
+	clippy::nursery,
+	clippy::pedantic,
+	clippy::perf,
+	clippy::style,
+
+	clippy::allow_attributes,
+	clippy::clone_on_ref_ptr,
+	clippy::create_dir,
+	clippy::filetype_is_file,
+	clippy::format_push_string,
+	clippy::get_unwrap,
+	clippy::impl_trait_in_params,
+	clippy::implicit_clone,
+	clippy::lossy_float_literal,
+	clippy::missing_assert_message,
+	clippy::missing_docs_in_private_items,
+	clippy::needless_raw_strings,
+	clippy::panic_in_result_fn,
+	clippy::pub_without_shorthand,
+	clippy::rest_pat_in_fully_bound_structs,
+	clippy::semicolon_inside_block,
+	clippy::str_to_string,
+	clippy::todo,
+	clippy::undocumented_unsafe_blocks,
+	clippy::unneeded_field_pattern,
+	clippy::unseparated_literal_suffix,
+	clippy::unwrap_in_result,
+
+	macro_use_extern_crate,
+	missing_copy_implementations,
+	missing_docs,
+	non_ascii_idents,
+	trivial_casts,
+	trivial_numeric_casts,
+	unused_crate_dependencies,
+	unused_extern_crates,
+	unused_import_braces,
+)]
+
+mod chk;
+mod error;
+mod img;
+mod iter;
+mod lint;
+mod similar;
+
+pub use chk::{
+	Checksum,
+	check_archive,
+};
+pub use error::PxsumError;
+pub use img::{
+	PxDiff,
+	PxImage,
+	PxKind,
+	TileRegion,
+};
+pub use iter::ManifestLines;
+pub use lint::PxLint;
+pub use similar::{
+	SimilarityIndex,
+	distance,
+};
+use std::sync::Once;
+
+// These are only used by the binary.
+use crossbeam_channel as _;
+use fyi_msg as _;
+use walkdir as _;
+
+
+
+// See build.rs.
+include!(concat!(env!("OUT_DIR"), "/pxsum-ext.rs"));
+
+
+
+/// # STDIN Used?
+static STDIN_USED: Once = Once::new();
+
+
+
+/// # Claim STDIN.
+///
+/// Lock STDIN for reading, but only once per process, and only if it is
+/// being redirected.
+///
+/// `Checksum` sources of "-" are read through this same guard, so a program
+/// that is itself reading STDIN — a manifest, say — should claim it this way
+/// too; its checksums will then fail fast on "-" with `PxsumError::Stdin`
+/// rather than blocking on a lock that will never be released.
+///
+/// ## Errors
+///
+/// This will return an error if STDIN is a terminal or has already been
+/// claimed by a previous call to this method.
+pub fn claim_stdin() -> Result<std::io::StdinLock<'static>, PxsumError> {
+	use std::io::IsTerminal;
+
+	// If this is the first call, `available` should be `true` afterwards.
+	let mut available = false;
+	STDIN_USED.call_once(|| { available = true; });
+
+	if available {
+		let stdin = std::io::stdin();
+		if stdin.is_terminal() { Err(PxsumError::Stdin) }
+		else { Ok(stdin.lock()) }
+	}
+	else { Err(PxsumError::Stdin) }
+}
@@ -0,0 +1,324 @@
+/*!
+# pxsum: Lint.
+*/
+
+use crate::{
+	Checksum,
+	PxImage,
+	PxKind,
+	PxsumError,
+};
+use std::fmt;
+
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// # Lint Issue.
+///
+/// This enum describes a problem with an image file that doesn't prevent it
+/// from being decoded — and checksummed — and so would otherwise go
+/// unnoticed. See `PxLint::check`.
+pub enum PxLint {
+	/// # Misnamed.
+	///
+	/// The file extension is missing or belongs to a different format than
+	/// the file signature. This holds the actual format and its proper
+	/// extension.
+	Misnamed(PxKind, &'static str),
+
+	/// # Trailing Data.
+	///
+	/// The file has this many bytes of extra data after the image's end
+	/// marker.
+	TrailingData(usize),
+
+	/// # Truncated.
+	///
+	/// The image data ends prematurely, but the decoder made do with what was
+	/// there.
+	Truncated,
+}
+
+impl fmt::Display for PxLint {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Misnamed(kind, ext) => write!(f, "misnamed ({}; should be .{ext})", kind.as_str()),
+			Self::TrailingData(n) => write!(f, "{n} trailing byte{}", if *n == 1 { "" } else { "s" }),
+			Self::Truncated => f.write_str("truncated"),
+		}
+	}
+}
+
+impl PxLint {
+	/// # Check Image.
+	///
+	/// Examine the raw image data for extension/signature mismatches —
+	/// provided the original file `path` — trailing garbage, and truncation,
+	/// returning any issues found.
+	///
+	/// As with checksumming, formats are identified by their file signatures,
+	/// falling back to the extension only when there isn't one.
+	///
+	/// The trailing data and truncation checks require a well-defined end
+	/// marker, so are only performed for GIF, JPEG, PNG, and WebP images.
+	/// Truncated images are decoded to see if they're salvageable.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the data is empty, unrecognizable, or
+	/// truncated beyond the decoder's tolerance.
+	pub fn check(src: &[u8], path: Option<&[u8]>) -> Result<Vec<Self>, PxsumError> {
+		if src.is_empty() { return Err(PxsumError::NoData); }
+		let kind = PxKind::try_from_magic(src)
+			.or_else(|e| path.and_then(PxKind::try_from_extension).ok_or(e))?
+			.refine(src);
+
+		let mut out = Vec::new();
+
+		// Check the extension.
+		if let Some(path) = path {
+			let ext = extension(path);
+			if ! ext.is_some_and(|ext| kind.extensions().iter().any(|e| e.as_bytes().eq_ignore_ascii_case(ext))) {
+				out.push(Self::Misnamed(kind, proper_extension(kind, src)));
+			}
+		}
+
+		// Check the end.
+		let end = match kind {
+			PxKind::Gif => gif_end(src),
+			PxKind::Jpeg => jpeg_end(src),
+			PxKind::Png => png_end(src),
+			PxKind::WebP => webp_end(src),
+			_ => Some(src.len()),
+		};
+		if let Some(end) = end {
+			if end < src.len() { out.push(Self::TrailingData(src.len() - end)); }
+		}
+		else {
+			PxImage::new(src, kind, Checksum::ALL_FRAMES)?;
+			out.push(Self::Truncated);
+		}
+
+		Ok(out)
+	}
+}
+
+
+
+/// # File Extension.
+///
+/// Return the extension from the file name portion of the path, if any.
+fn extension(path: &[u8]) -> Option<&[u8]> {
+	let name = path.rsplit(|&b| b == b'/').next()?;
+	let pos = name.iter().rposition(|&b| b == b'.').filter(|&pos| pos != 0)?;
+	Some(&name[pos + 1..]).filter(|ext| ! ext.is_empty())
+}
+
+/// # Proper Extension.
+///
+/// Return the preferred extension for the format, which for some depends on
+/// the specific flavor of the data.
+fn proper_extension(kind: PxKind, src: &[u8]) -> &'static str {
+	match (kind, src.get(1)) {
+		#[cfg(feature = "jpeg2k")]
+		(PxKind::Jpeg2k, Some(b'O')) => "j2k", // Bare codestream.
+		(PxKind::Pnm, Some(b'1' | b'4')) => "pbm",
+		(PxKind::Pnm, Some(b'2' | b'5')) => "pgm",
+		(PxKind::Pnm, Some(b'3' | b'6')) => "ppm",
+		(PxKind::Pnm, Some(b'7')) => "pam",
+		_ => kind.extensions()[0],
+	}
+}
+
+/// # GIF End.
+///
+/// Walk the blocks of a GIF, returning the position just past its trailer,
+/// or `None` if it isn't reached.
+fn gif_end(src: &[u8]) -> Option<usize> {
+	/// # Skip Color Table.
+	const fn color_table(flags: u8) -> usize {
+		if 0 == flags & 0x80 { 0 }
+		else { 3 << ((flags & 0b0111) + 1) }
+	}
+
+	/// # Skip Sub-Blocks.
+	fn sub_blocks(src: &[u8], mut pos: usize) -> Option<usize> {
+		loop {
+			let len = usize::from(*src.get(pos)?);
+			pos += 1 + len;
+			if len == 0 { return Some(pos); }
+		}
+	}
+
+	let mut pos = 13 + color_table(*src.get(10)?);
+	loop {
+		match *src.get(pos)? {
+			// Trailer.
+			0x3b => return Some(pos + 1),
+			// Extension.
+			0x21 => { pos = sub_blocks(src, pos + 2)?; },
+			// Image.
+			0x2c => {
+				pos += 10 + color_table(*src.get(pos + 9)?);
+				pos = sub_blocks(src, pos + 1)?; // Skip the LZW code size.
+			},
+			_ => return None,
+		}
+	}
+}
+
+/// # JPEG End.
+///
+/// Walk the segments of a JPEG, returning the position just past its EOI
+/// marker, or `None` if it isn't reached.
+///
+/// Embedded thumbnails have EOI markers of their own, but live inside APP
+/// segments, so are skipped over without a second thought.
+fn jpeg_end(src: &[u8]) -> Option<usize> {
+	let mut pos = 2;
+	loop {
+		// Markers can be padded with any number of fill bytes.
+		if *src.get(pos)? != 0xff { return None; }
+		while src.get(pos + 1) == Some(&0xff) { pos += 1; }
+		let marker = *src.get(pos + 1)?;
+		pos += 2;
+
+		match marker {
+			// EOI.
+			0xd9 => return Some(pos),
+			// Standalone markers.
+			0x01 | 0xd0..=0xd7 => {},
+			// Everything else has a length.
+			_ => {
+				let len = usize::from(u16::from_be_bytes(*src.get(pos..)?.first_chunk::<2>()?));
+				if len < 2 { return None; }
+				pos += len;
+				if src.len() < pos { return None; }
+
+				// Scans are followed by entropy-coded data, which runs until
+				// the next non-stuffed, non-restart marker.
+				if marker == 0xda {
+					pos += src.get(pos..)?.windows(2).position(|w|
+						w[0] == 0xff && ! matches!(w[1], 0x00 | 0xd0..=0xd7 | 0xff)
+					)?;
+				}
+			},
+		}
+	}
+}
+
+/// # PNG End.
+///
+/// Walk the chunks of a PNG, returning the position just past its `IEND`
+/// chunk, or `None` if it isn't reached.
+fn png_end(src: &[u8]) -> Option<usize> {
+	let mut pos = 8;
+	loop {
+		let chunk = src.get(pos..pos + 8)?;
+		let len = usize::try_from(u32::from_be_bytes(chunk[..4].try_into().ok()?)).ok()?;
+		pos = pos.checked_add(12)?.checked_add(len)?;
+		if src.len() < pos { return None; }
+		if &chunk[4..] == b"IEND" { return Some(pos); }
+	}
+}
+
+/// # WebP End.
+///
+/// Return the end of the RIFF container, per its header, or `None` if the
+/// data is shorter than that.
+fn webp_end(src: &[u8]) -> Option<usize> {
+	let len = u32::from_le_bytes(*src.get(4..)?.first_chunk::<4>()?);
+	let end = usize::try_from(len).ok()?.checked_add(8)?;
+	if end <= src.len() { Some(end) }
+	else { None }
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_extension() {
+		for (path, expected) in [
+			(&b"./a/b.png"[..], Some(&b"png"[..])),
+			(b"b.tar.gz", Some(b"gz")),
+			(b"./a.b/c", None),
+			(b"./.hidden", None),
+			(b"trailing.", None),
+		] {
+			assert_eq!(extension(path), expected, "Wrong extension for {:?}.", String::from_utf8_lossy(path));
+		}
+	}
+
+	#[test]
+	fn t_extensions() {
+		// Every listed extension should be recognized by the crawler.
+		for kind in [
+			PxKind::Bmp, PxKind::Farbfeld, PxKind::Gif, PxKind::Hdr,
+			PxKind::Ico, PxKind::Jpeg, PxKind::OpenExr, PxKind::Png,
+			PxKind::Pnm, PxKind::Qoi, PxKind::Svg, PxKind::Tga, PxKind::Tiff,
+			PxKind::WebP,
+		] {
+			for ext in kind.extensions() {
+				assert!(
+					crate::check_extension(format!("./image.{ext}").as_bytes()),
+					"Unrecognized {} extension: {ext}", kind.as_str(),
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn t_check() {
+		for (path, kind) in [
+			("skel/assets/ash.jpg", PxKind::Jpeg),
+			("skel/assets/down_arrow.gif", PxKind::Gif),
+			("skel/assets/poe.png", PxKind::Png),
+			("skel/assets/statler.webp", PxKind::WebP),
+		] {
+			let raw = std::fs::read(path).expect("Unable to read image.");
+			assert_eq!(PxLint::check(&raw, Some(path.as_bytes())), Ok(Vec::new()), "False positive for {path}.");
+
+			// No path, no extension problems.
+			assert_eq!(PxLint::check(&raw, None), Ok(Vec::new()));
+
+			// Wrong extension.
+			assert_eq!(
+				PxLint::check(&raw, Some(b"image.bmp")),
+				Ok(vec![PxLint::Misnamed(kind, kind.extensions()[0])]),
+			);
+
+			// Add some garbage.
+			let mut garbage = raw.clone();
+			garbage.extend_from_slice(b"Hello World");
+			assert_eq!(PxLint::check(&garbage, None), Ok(vec![PxLint::TrailingData(11)]));
+
+			// And take some away.
+			let short = &raw[..raw.len() - 2];
+			assert!(
+				matches!(PxLint::check(short, None).as_deref(), Ok([PxLint::Truncated]) | Err(PxsumError::Decode)),
+				"Missed truncation for {path}.",
+			);
+		}
+
+		// Herring is a red herring.
+		let raw = std::fs::read("skel/assets/herring.png").expect("Unable to read herring.");
+		assert_eq!(
+			PxLint::check(&raw, Some(b"skel/assets/herring.png")),
+			Ok(vec![PxLint::Misnamed(PxKind::WebP, "webp")]),
+		);
+
+		// Case doesn't matter.
+		let raw = std::fs::read("skel/assets/cmyk.JPG").expect("Unable to read cmyk.");
+		assert_eq!(PxLint::check(&raw, Some(b"skel/assets/cmyk.JPG")), Ok(Vec::new()));
+
+		// PNM extensions are flavor-specific.
+		assert_eq!(proper_extension(PxKind::Pnm, b"P5\n1 1\n255\n\0"), "pgm");
+		assert_eq!(proper_extension(PxKind::Png, b"\x89PNG"), "png");
+
+		// Empty is empty.
+		assert_eq!(PxLint::check(&[], None), Err(PxsumError::NoData));
+	}
+}
@@ -24,6 +24,7 @@
 	clippy::format_push_string,
 	clippy::get_unwrap,
 	clippy::impl_trait_in_params,
+	clippy::implicit_clone,
 	clippy::lossy_float_literal,
 	clippy::missing_assert_message,
 	clippy::missing_docs_in_private_items,
@@ -33,7 +34,6 @@
 	clippy::rest_pat_in_fully_bound_structs,
 	clippy::semicolon_inside_block,
 	clippy::str_to_string,
-	clippy::string_to_string,
 	clippy::todo,
 	clippy::undocumented_unsafe_blocks,
 	clippy::unneeded_field_pattern,
@@ -46,7 +46,6 @@
 	non_ascii_idents,
 	trivial_casts,
 	trivial_numeric_casts,
-	unused_crate_dependencies,
 	unused_extern_crates,
 	unused_import_braces,
 )]
@@ -54,27 +53,33 @@
 #![expect(clippy::redundant_pub_crate, reason = "Unresolvable.")]
 
 mod args;
-mod chk;
-mod error;
-mod img;
-mod iter;
+mod cli;
 
 
 
 use args::Settings;
-use chk::Checksum;
-use crossbeam_channel::Receiver;
+use cli::CliError;
+use crossbeam_channel::{
+	Receiver,
+	Sender,
+};
 use dactyl::NiceElapsed;
-use error::PxsumError;
 use fyi_msg::{
 	Msg,
 	MsgKind,
 };
-use img::{
+use image::ImageFormat;
+use pxsum::{
+	Checksum,
+	ManifestLines,
 	PxImage,
+	PxDiff,
 	PxKind,
+	PxLint,
+	PxsumError,
+	SimilarityIndex,
+	claim_stdin,
 };
-use iter::ManifestLines;
 use std::{
 	borrow::Cow,
 	collections::{
@@ -82,6 +87,7 @@ use std::{
 		BTreeSet,
 	},
 	ffi::OsString,
+	fmt::Write,
 	fs::File,
 	io::{
 		BufRead,
@@ -91,10 +97,13 @@ use std::{
 		NonZeroU64,
 		NonZeroUsize,
 	},
-	path::Path,
+	path::{
+		Component,
+		Path,
+		PathBuf,
+	},
 	sync::{
 		Mutex,
-		Once,
 		atomic::{
 			AtomicBool,
 			AtomicU64,
@@ -110,16 +119,6 @@ use std::{
 
 
 
-// See build.rs.
-include!(concat!(env!("OUT_DIR"), "/pxsum-ext.rs"));
-
-
-
-/// # STDIN Used?
-static STDIN_USED: Once = Once::new();
-
-
-
 /// # Main.
 fn main() {
 	#[cold]
@@ -135,11 +134,11 @@ fn main() {
 	let now = Instant::now();
 	let mut bench = false;
 
-	// The _main() method does all the hard work, but some responses warrant
+	// The main__() method does all the hard work, but some responses warrant
 	// additional output.
-	match _main(&mut bench) {
+	match main__(&mut bench) {
 		Ok(()) => if bench { print_time(now); },
-		Err(e @ (PxsumError::PrintHelp | PxsumError::PrintVersion)) => { println!("{e}"); },
+		Err(e @ (CliError::PrintHelp | CliError::PrintVersion)) => { println!("{e}"); },
 		Err(e) => {
 			// Print the message.
 			let code = e.exit_code();
@@ -161,7 +160,7 @@ fn main() {
 
 #[inline]
 /// # Actual Main.
-fn _main(print_time: &mut bool) -> Result<(), PxsumError> {
+fn main__(print_time: &mut bool) -> Result<(), CliError> {
 	// Parse CLI arguments.
 	let (settings, paths) = Settings::new()?;
 
@@ -169,18 +168,25 @@ fn _main(print_time: &mut bool) -> Result<(), PxsumError> {
 	// ensures it'll happen even if we run into errors during processing.
 	if settings.print_time() { *print_time = true; }
 
+	// Comparison mode.
+	if settings.compare() { compare_paths(&paths, &settings) }
 	// Verification mode.
-	if settings.check() { verify_paths(&paths, settings) }
+	else if settings.check() { verify_paths(&paths, &settings) }
+	// Lint mode.
+	else if settings.lint() { lint_paths(&paths, &settings) }
+	// TAR stream crunch.
+	else if settings.stdin_tar() { crunch_tar(&settings) }
 	// Regular ol' crunch.
-	else { crunch_paths(&paths, settings) }
+	else { crunch_paths(&paths, &settings) }
 }
 
+#[expect(clippy::too_many_lines, reason = "There are a lot of modes.")]
 #[inline(never)]
 /// # Crunch Paths.
 ///
 /// Calculate and output new pxsum/path pairs.
-fn crunch_paths(paths: &[OsString], settings: Settings)
--> Result<(), PxsumError> {
+fn crunch_paths(paths: &[OsString], settings: &Settings)
+-> Result<(), CliError> {
 	/// # Anything?
 	///
 	/// A simple flag to indicate that we successfully crunched at least one
@@ -189,72 +195,132 @@ fn crunch_paths(paths: &[OsString], settings: Settings)
 
 	/// # Paths by Checksum.
 	///
-	/// This is used for `-g`/`--group-by-checksum`.
-	static GROUPED: Mutex<BTreeMap<[u8; 32], BTreeSet<String>>> = Mutex::new(BTreeMap::new());
+	/// This is used for `-g`/`--group-by-checksum`. (Tiled checksums are
+	/// keyed with their tile digests.)
+	static GROUPED: Mutex<BTreeMap<Grouped, BTreeSet<String>>> = Mutex::new(BTreeMap::new());
+
+	/// # Fingerprints and Paths.
+	///
+	/// This is used for `--similar`.
+	static SIMILAR: Mutex<Vec<(u64, String)>> = Mutex::new(Vec::new());
+
+	/// # Restored Path Count.
+	///
+	/// This is used for `--guard`.
+	static RESTORED: AtomicU64 = AtomicU64::new(0);
+
+	/// # Unchecked Path Count.
+	///
+	/// This is used for `--cross-check`, to tally the images whose formats
+	/// have no alternate decoder.
+	static UNCHECKED: AtomicU64 = AtomicU64::new(0);
+
+	/// # Output Checksum.
+	///
+	/// Print the pxsum/path pair, or collect it for later if grouping.
+	fn push(chk: &Checksum, group_by_checksum: bool) {
+		if group_by_checksum {
+			let mut ptr = match GROUPED.lock() {
+				Ok(guard) => guard,
+				Err(poisoned) => poisoned.into_inner(),
+			};
+			ptr.entry((chk.chk(), chk.tiles().to_vec()))
+				.or_default()
+				.insert(chk.src().to_owned());
+		}
+		// Print now!
+		else {
+			ANY.store(true, Relaxed);
+			println!("{chk}");
+		}
+	}
+
+	/// # Output Fingerprint.
+	///
+	/// Print the fingerprint/path pair, or collect it for later if clustering.
+	fn push_fp(fp: u64, src: &str, similar: bool) {
+		if similar {
+			let mut ptr = match SIMILAR.lock() {
+				Ok(guard) => guard,
+				Err(poisoned) => poisoned.into_inner(),
+			};
+			ptr.push((fp, src.to_owned()));
+		}
+		// Print now!
+		else {
+			ANY.store(true, Relaxed);
+			println!("{fp:016x}  {src}");
+		}
+	}
 
 	/// # Worker Callback.
 	fn cb(rx: &Receiver::<&Path>, settings: &Settings) {
-		let mut chk = Checksum::new(settings.strict());
+		let mut chk = settings.checksum();
 		let print_warnings = settings.print_warnings();
 		let group_by_checksum = settings.group_by_checksum();
+		let split_pages = settings.split_pages();
+		let similar = settings.similar().is_some();
+		let perceptual = similar || settings.perceptual();
+		let command = settings.guard();
+		let cross_check = settings.cross_check() && command.is_none();
 		while let Ok(p) = rx.recv() {
-			match chk.crunch(p) {
-				Ok(()) =>
-					// Collect the results for later.
-					if group_by_checksum {
-						let mut ptr = match GROUPED.lock() {
-							Ok(guard) => guard,
-							Err(poisoned) => poisoned.into_inner(),
-						};
-						ptr.entry(chk.chk())
-							.or_default()
-							.insert(chk.src().to_owned());
-					}
-					// Print now!
-					else {
+			// Crunch a fingerprint or checksum(s), as appropriate.
+			let archive = pxsum::check_archive(p.as_os_str().as_encoded_bytes());
+			let res =
+				if let Some(cmd) = command {
+					guard(&mut chk, p, cmd, settings.print_valid()).map(|restored| {
 						ANY.store(true, Relaxed);
-						println!("{chk}");
-					},
-				Err(PxsumError::Path | PxsumError::NoData) => {}, // Silently ignore.
-				Err(_) => if print_warnings {
-					let mut src = Cow::Borrowed(chk.src());
-					if src.is_empty() { src = p.to_string_lossy(); }
-					Msg::warning(format!(
-						"Image could not be decoded.\n         \x1b[2m{src}\x1b[0m",
-					)).eprint();
-				},
+						if restored { RESTORED.fetch_add(1, Relaxed); }
+						None
+					})
+				}
+				else if perceptual && archive {
+					chk.crunch_archive_fingerprints(p, |c, res| match res {
+						Ok(fp) => push_fp(fp, c.src(), similar),
+						Err(PxsumError::Path | PxsumError::NoData) => {},
+						Err(_) => if print_warnings { warn_decode(c.src()); },
+					}).map(|()| None)
+				}
+				else if perceptual { chk.crunch_fingerprint(p).map(Some) }
+				else if archive {
+					chk.crunch_archive(p, |c, res| match res {
+						Ok(()) => {
+							push(c, group_by_checksum);
+							if cross_check && ! warn_cross_check(c) { UNCHECKED.fetch_add(1, Relaxed); }
+						},
+						Err(PxsumError::Path | PxsumError::NoData) => {},
+						Err(_) => if print_warnings { warn_decode(c.src()); },
+					}).map(|()| None)
+				}
+				else if split_pages {
+					chk.crunch_pages(p, |c| push(c, group_by_checksum)).map(|()| None)
+				}
+				else {
+					chk.crunch(p).map(|()| {
+						push(&chk, group_by_checksum);
+						None
+					})
+				};
+
+			// Double-check the decoder?
+			if
+				cross_check && ! archive && matches!(res, Ok(None)) &&
+				! warn_cross_check(&chk)
+			{
+				UNCHECKED.fetch_add(1, Relaxed);
 			}
-		}
-	}
 
-	#[cold]
-	/// # Print Results Grouped by Checksum.
-	fn print_grouped(only_dupes: bool) -> Result<(), PxsumError> {
-		use std::io::Write;
-		let mut any = false;
-		let mut buf = [0_u8; 64];
-
-		{
-			let mut lock = std::io::stdout().lock();
-			for (k, v) in GROUPED.lock().map_err(|_| PxsumError::JobServer)?.iter() {
-				if ! only_dupes || 1 < v.len() {
-					// Our buffer is the right size; this should never fail.
-					if let Ok(chk) = faster_hex::hex_encode(k.as_slice(), buf.as_mut_slice()) {
-						any = true;
-						let _res = writeln!(&mut lock, "{chk}");
-						for path in v {
-							let _res = writeln!(&mut lock, "  {path}");
-						}
-					}
-				}
+			match res {
+				Ok(Some(fp)) => push_fp(fp, chk.src(), similar),
+				// Already handled or silently ignored.
+				Ok(None) | Err(PxsumError::Path | PxsumError::NoData | PxsumError::NotImage) => {},
+				Err(_) => if print_warnings {
+					let src = chk.src();
+					if src.is_empty() { warn_decode(&p.to_string_lossy()); }
+					else { warn_decode(src); }
+				},
 			}
-			let _res = lock.flush();
 		}
-
-		// Warnings?
-		if any { Ok(()) }
-		else if only_dupes { Err(PxsumError::NoDupes) }
-		else { Err(PxsumError::Noop) }
 	}
 
 	// If there are fewer paths than threads, we can reduce the worker count.
@@ -267,68 +333,699 @@ fn crunch_paths(paths: &[OsString], settings: Settings)
 		// Set up the worker threads, either with or without progress.
 		let mut workers = Vec::with_capacity(threads.get());
 		for _ in 0..threads.get() {
-			workers.push(s.spawn(#[inline(always)] || cb(&rx, &settings)));
+			workers.push(s.spawn(#[inline(always)] || cb(&rx, settings)));
 		}
 
 		// Broadcast the jobs!
-		for p in paths { tx.send(p.as_ref()).map_err(|_| PxsumError::JobServer)?; }
+		for p in paths { tx.send(p.as_ref()).map_err(|_| CliError::JobServer)?; }
 
 		// Disconnect and wait for the threads to finish!
 		drop(tx);
 		for worker in workers { let _res = worker.join(); }
 
+		// Mention the images that couldn't be cross-checked, if any.
+		let unchecked = UNCHECKED.load(SeqCst);
+		if unchecked != 0 {
+			Msg::warning(format!(
+				"{unchecked} image{} could not be cross-checked; {} no alternate decoder.",
+				if unchecked == 1 { "" } else { "s" },
+				if unchecked == 1 { "its format has" } else { "their formats have" },
+			)).eprint();
+		}
+
 		// We're all good if we did at least one thing, but if not, emit an
 		// error so we can let the user know.
-		if settings.group_by_checksum() { print_grouped(settings.only_dupes()) }
+		if let Some(restored) = NonZeroU64::new(RESTORED.load(SeqCst)) {
+			Err(CliError::Restored(restored))
+		}
+		else if let Some(distance) = settings.similar() {
+			let list = std::mem::take(&mut *SIMILAR.lock().map_err(|_| CliError::JobServer)?);
+			print_similar(list, distance)
+		}
+		else if settings.group_by_checksum() {
+			let grouped = GROUPED.lock().map_err(|_| CliError::JobServer)?;
+			print_grouped(&grouped, settings.only_dupes())
+		}
 		else if ANY.load(SeqCst) { Ok(()) }
-		else { Err(PxsumError::Noop) }
+		else { Err(CliError::Noop) }
+	})
+}
+
+#[inline(never)]
+/// # Crunch TAR Stream.
+///
+/// Calculate and output new pxsum/path pairs for the images in a TAR stream
+/// piped through STDIN, using their member names as the paths.
+///
+/// The members have to be read in order, but are checksummed in parallel.
+fn crunch_tar(settings: &Settings) -> Result<(), CliError> {
+	/// # Anything?
+	static ANY: AtomicBool = AtomicBool::new(false);
+
+	/// # Worker Callback.
+	fn cb(rx: &Receiver::<(String, Vec<u8>)>, settings: &Settings) {
+		let mut chk = settings.checksum();
+		let print_warnings = settings.print_warnings();
+		while let Ok((name, raw)) = rx.recv() {
+			match chk.crunch_named(&name, &raw) {
+				Ok(()) => {
+					ANY.store(true, Relaxed);
+					println!("{chk}");
+				},
+				// Silently ignored.
+				Err(PxsumError::Path | PxsumError::NoData | PxsumError::NotImage) => {},
+				Err(_) => if print_warnings { warn_decode(chk.src()); },
+			}
+		}
+	}
+
+	/// # Feed Members.
+	///
+	/// Read each regular file from the stream and send it off to be
+	/// crunched.
+	fn feed(tx: &Sender::<(String, Vec<u8>)>) -> Result<(), CliError> {
+		use std::io::Read;
+
+		let mut tar = tar::Archive::new(claim_stdin()?);
+		for entry in tar.entries().map_err(|_| PxsumError::Read)? {
+			let mut entry = entry.map_err(|_| PxsumError::Read)?;
+			if ! entry.header().entry_type().is_file() { continue; }
+			let Ok(name) = String::from_utf8(entry.path_bytes().into_owned()) else { continue; };
+			let mut raw = Vec::new();
+			entry.read_to_end(&mut raw).map_err(|_| PxsumError::Read)?;
+			drop(entry);
+			tx.send((name, raw)).map_err(|_| CliError::JobServer)?;
+		}
+
+		Ok(())
+	}
+
+	let threads = settings.threads();
+	let (tx, rx) = crossbeam_channel::bounded::<(String, Vec<u8>)>(threads.get());
+	thread::scope(#[inline(always)] |s| {
+		// Set up the worker threads.
+		let mut workers = Vec::with_capacity(threads.get());
+		for _ in 0..threads.get() {
+			workers.push(s.spawn(#[inline(always)] || cb(&rx, settings)));
+		}
+
+		// Broadcast the jobs! Errors have to wait until the workers have been
+		// released.
+		let res = feed(&tx);
+
+		// Disconnect and wait for the threads to finish!
+		drop(tx);
+		for worker in workers { let _res = worker.join(); }
+		res?;
+
+		if ANY.load(SeqCst) { Ok(()) }
+		else { Err(CliError::Noop) }
 	})
 }
 
-/// # STDIN Lock.
+#[cold]
+/// # Decode Warning.
+///
+/// Print a warning about an image that could not be decoded.
+fn warn_decode(src: &str) {
+	Msg::warning(format!("Image could not be decoded.\n         \x1b[2m{src}\x1b[0m")).eprint();
+}
+
+#[cold]
+/// # Cross-Check Warning.
+///
+/// Decode the image again with an alternate backend and print a warning if
+/// the pixels don't match up.
+///
+/// Returns `false` if the format has no alternate backend, leaving it to the
+/// caller to tally and summarize such images.
+fn warn_cross_check(chk: &Checksum) -> bool {
+	let problem = match chk.cross_check() {
+		Ok(Some(diff)) if ! diff.within(PxDiff::DECODER_TOLERANCE) =>
+			Cow::Owned(format!("Decoders disagree: {diff}.")),
+		Err(_) => Cow::Borrowed("Alternate decoder failed."),
+		Ok(Some(_)) => return true,
+		Ok(None) => return false,
+	};
+	Msg::warning(format!("{problem}\n         \x1b[2m{}\x1b[0m", chk.src())).eprint();
+	true
+}
+
+/// # Guard.
+///
+/// Checksum the image, back it up, and run the `--guard` command on it —
+/// with `{}` replaced by the path, or the path appended if there is no `{}`
+/// — then checksum it again. If the pixels changed (or can no longer be
+/// decoded), the original is restored from the backup.
+///
+/// Returns `true` if the image had to be restored.
+///
+/// ## Errors
+///
+/// Errors are only returned if the original image cannot be checksummed (or
+/// guarded), in which case the command is never run.
+fn guard(chk: &mut Checksum, src: &Path, cmd: &[OsString], print_valid: bool)
+-> Result<bool, PxsumError> {
+	use std::os::unix::ffi::{
+		OsStrExt,
+		OsStringExt,
+	};
+
+	// STDIN can't be guarded.
+	if src == Path::new("-") { return Err(PxsumError::Path); }
+
+	// Checksum the original.
+	chk.crunch(src)?;
+	let before = chk.chk();
+
+	// Back it up.
+	let mut backup = src.as_os_str().to_owned();
+	backup.push(".pxsum-guard");
+	let backup = PathBuf::from(backup);
+	if backup.exists() || std::fs::copy(src, &backup).is_err() {
+		Msg::warning(format!(
+			"Unable to back up image; skipping.\n         \x1b[2m{}\x1b[0m",
+			chk.src(),
+		)).eprint();
+		return Ok(false);
+	}
+
+	// Run the command, filling in the path.
+	let path = src.as_os_str().as_bytes();
+	let mut found = false;
+	let mut args = cmd.iter().map(|arg| {
+		let arg = arg.as_bytes();
+		if ! arg.windows(2).any(|w| w == b"{}") { return OsString::from_vec(arg.to_vec()); }
+		found = true;
+		let mut out = Vec::with_capacity(arg.len() + path.len());
+		let mut rest = arg;
+		while let Some(pos) = rest.windows(2).position(|w| w == b"{}") {
+			out.extend_from_slice(&rest[..pos]);
+			out.extend_from_slice(path);
+			rest = &rest[pos + 2..];
+		}
+		out.extend_from_slice(rest);
+		OsString::from_vec(out)
+	}).collect::<Vec<_>>();
+	if ! found { args.push(src.as_os_str().to_owned()); }
+	let ran = std::process::Command::new(&args[0])
+		.args(&args[1..])
+		.stdin(std::process::Stdio::null())
+		.stdout(std::process::Stdio::null())
+		.status()
+		.is_ok_and(|s| s.success());
+
+	// Check it again.
+	let reason =
+		if ! ran { Some("command failed") }
+		else if chk.crunch(src).is_err() { Some("read/decode") }
+		else if chk.chk() == before { None }
+		else { Some("pixels changed") };
+
+	let Some(reason) = reason else {
+		let _res = std::fs::remove_file(&backup);
+		if print_valid { println!("{}: OK", chk.src()); }
+		return Ok(false);
+	};
+
+	// Roll it back!
+	if std::fs::rename(&backup, src).is_ok() {
+		println!("{}: RESTORED ({reason})", chk.src());
+	}
+	else {
+		Msg::warning(format!(
+			"Unable to restore image; the original is at:\n         \x1b[2m{}\x1b[0m",
+			backup.to_string_lossy(),
+		)).eprint();
+	}
+	Ok(true)
+}
+
+/// # Grouping Key.
 ///
-/// This method is used as a thin wrapper around STDIN to ensure the lock is
-/// only requested once and only returned if it is being redirected.
+/// A checksum and its tile digests, if any.
+type Grouped = ([u8; 32], Vec<[u8; 8]>);
+
+#[cold]
+/// # Print Results Grouped by Checksum.
+fn print_grouped(grouped: &BTreeMap<Grouped, BTreeSet<String>>, only_dupes: bool)
+-> Result<(), CliError> {
+	use std::io::Write;
+	let mut any = false;
+	let mut buf = [0_u8; 64];
+
+	{
+		let mut lock = std::io::stdout().lock();
+		for ((k, tiles), v) in grouped {
+			if ! only_dupes || 1 < v.len() {
+				// Our buffer is the right size; this should never fail.
+				if let Ok(chk) = faster_hex::hex_encode(k.as_slice(), buf.as_mut_slice()) {
+					any = true;
+					let _res = write!(&mut lock, "{chk}");
+					if ! tiles.is_empty() {
+						let _res = lock.write_all(b":");
+						for tile in tiles {
+							if let Ok(tile) = faster_hex::hex_encode(tile.as_slice(), &mut buf[..16]) {
+								let _res = lock.write_all(tile.as_bytes());
+							}
+						}
+					}
+					let _res = writeln!(&mut lock);
+					for path in v {
+						let _res = writeln!(&mut lock, "  {path}");
+					}
+				}
+			}
+		}
+		let _res = lock.flush();
+	}
+
+	// Warnings?
+	if any { Ok(()) }
+	else if only_dupes { Err(CliError::NoDupes) }
+	else { Err(CliError::Noop) }
+}
+
+#[cold]
+/// # Print Results Grouped by Similarity.
 ///
-/// This should hopefully help prevent indefinite hangs or other weirdness.
-fn stdin() -> Result<std::io::StdinLock<'static>, PxsumError> {
-	use std::io::IsTerminal;
-
-	// If this is the first call, `available` should be `true` afterwards.
-	let mut available = false;
-	STDIN_USED.call_once(|| { available = true; });
-
-	if available {
-		let stdin = std::io::stdin();
-		if stdin.is_terminal() { Err(PxsumError::Stdin) }
-		else { Ok(stdin.lock()) }
+/// Cluster the fingerprint/path pairs collected during a `--similar` crunch
+/// and print each group having two or more members.
+fn print_similar(mut list: Vec<(u64, String)>, distance: u32)
+-> Result<(), CliError> {
+	use std::io::Write;
+
+	// Sort by path so the output is deterministic.
+	list.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+
+	let mut index = SimilarityIndex::new();
+	for (fp, _) in &list { index.insert(*fp); }
+
+	let mut any = false;
+	let mut lock = std::io::stdout().lock();
+	for set in index.clusters(distance) {
+		any = true;
+		let _res = writeln!(&mut lock, "{:016x}", list[set[0]].0);
+		for idx in set {
+			let _res = writeln!(&mut lock, "  {}", list[idx].1);
+		}
 	}
-	else { Err(PxsumError::Stdin) }
+	let _res = lock.flush();
+
+	// Warnings?
+	if any { Ok(()) }
+	else { Err(CliError::NoDupes) }
+}
+
+#[inline(never)]
+/// # Compare Paths.
+///
+/// Decode the two images the same way they would be for checksumming, print
+/// their formats and dimensions, and — if their pixels differ — the
+/// difference statistics.
+///
+/// If both paths are directories, their contents are compared instead. (See
+/// `compare_dirs`.)
+fn compare_paths(paths: &[OsString], settings: &Settings)
+-> Result<(), CliError> {
+	let [a, b] = paths else { return Err(CliError::Compare); };
+	let (a, b) = (Path::new(a), Path::new(b));
+	match (a.is_dir(), b.is_dir()) {
+		(true, true) => return compare_dirs(a, b, settings),
+		(false, false) => {},
+		_ => return Err(CliError::Compare),
+	}
+
+	let flags = Checksum::V2 | settings.checksum_flags();
+	let background = settings.background().unwrap_or_default();
+	let svg_size = settings.svg_size();
+
+	let mut imgs = Vec::with_capacity(2);
+	for path in [a, b] {
+		match decode_image(path, flags, background, svg_size) {
+			Ok((kind, img)) => {
+				print!("{}: {} {}x{}", path.to_string_lossy(), kind.as_str(), img.width(), img.height());
+				if 1 < img.frames() { print!(" ({} frames)", img.frames()); }
+				println!();
+				imgs.push(img);
+			},
+			Err(e) => {
+				println!(
+					"{}: FAILED ({})",
+					path.to_string_lossy(),
+					if matches!(e, PxsumError::NoData) { "empty" }
+					else if path != "-" && ! path.exists() { "missing" }
+					else { "read/decode" }
+				);
+				return Err(e.into());
+			},
+		}
+	}
+
+	let diff = imgs[0].diff(&imgs[1]);
+	println!("{diff}");
+	if diff.identical() { Ok(()) }
+	else { Err(CliError::Different) }
+}
+
+#[inline(never)]
+/// # Compare Directories.
+///
+/// Crawl both directories for images, pair them up by relative path — sans
+/// extension if `--ignore-extension` — and compare each pair's pixels,
+/// printing the results as they come in. Unpaired entries are reported as
+/// MISSING; ambiguous ones — a second file with the same key — are skipped
+/// with a warning, but counted among them.
+fn compare_dirs(a: &Path, b: &Path, settings: &Settings)
+-> Result<(), CliError> {
+	/// # Changed or Undecodable Pair Count.
+	static CHANGED: AtomicU64 = AtomicU64::new(0);
+
+	/// # Unpaired Entry Count.
+	static MISSING: AtomicU64 = AtomicU64::new(0);
+
+	/// # Job.
+	///
+	/// The display name and the A and B paths, if any.
+	type Job = (String, Option<PathBuf>, Option<PathBuf>);
+
+	/// # Worker Callback.
+	fn cb(rx: &Receiver::<Job>, dirs: (&Path, &Path), settings: &Settings) {
+		let flags = Checksum::V2 | settings.checksum_flags();
+		let background = settings.background().unwrap_or_default();
+		let svg_size = settings.svg_size();
+		let print_valid = settings.print_valid();
+
+		while let Ok((name, a, b)) = rx.recv() {
+			let (a, b) = match (a, b) {
+				(Some(a), Some(b)) => (a, b),
+				(a, _) => {
+					MISSING.fetch_add(1, Relaxed);
+					println!(
+						"{name}: MISSING (from {})",
+						if a.is_some() { dirs.1 } else { dirs.0 }.to_string_lossy(),
+					);
+					continue;
+				},
+			};
+
+			match decode_image(&a, flags, background, svg_size)
+				.and_then(|(_, a)| decode_image(&b, flags, background, svg_size).map(|(_, b)| a.diff(&b)))
+			{
+				Ok(diff) if diff.identical() => if print_valid { println!("{name}: OK"); },
+				Ok(diff) => {
+					CHANGED.fetch_add(1, Relaxed);
+					println!("{name}: FAILED\n  diff: {diff}");
+				},
+				Err(e) => {
+					CHANGED.fetch_add(1, Relaxed);
+					println!(
+						"{name}: FAILED ({})",
+						if matches!(e, PxsumError::NoData) { "empty" }
+						else { "read/decode" }
+					);
+				},
+			}
+		}
+	}
+
+	// Pair everything up by (relative) key.
+	let ignore_extension = settings.ignore_extension();
+	let mut pairs: BTreeMap<PathBuf, (Option<PathBuf>, Option<PathBuf>)> = BTreeMap::new();
+	for (side, dir) in [a, b].into_iter().enumerate() {
+		let mut found: Vec<PathBuf> = args::crawl_dir(dir, settings.sniff())
+			.filter(|p| ! pxsum::check_archive(p.as_os_str().as_encoded_bytes()))
+			.collect();
+		found.sort_unstable();
+		for path in found {
+			let Ok(rel) = path.strip_prefix(dir) else { continue; };
+			let key = if ignore_extension { rel.with_extension("") } else { rel.to_path_buf() };
+			let entry = pairs.entry(key).or_default();
+			let slot = if side == 0 { &mut entry.0 } else { &mut entry.1 };
+			// Count the extras as unpaired so they don't slip by unnoticed.
+			if slot.is_some() {
+				MISSING.fetch_add(1, Relaxed);
+				Msg::warning(format!(
+					"Ambiguous pairing; skipping.\n         \x1b[2m{}\x1b[0m",
+					path.to_string_lossy(),
+				)).eprint();
+			}
+			else { slot.replace(path); }
+		}
+	}
+
+	let threads = settings.threads();
+	let (tx, rx) = crossbeam_channel::bounded::<Job>(threads.get());
+	thread::scope(#[inline(always)] |s| {
+		// Set up the worker threads.
+		let mut workers = Vec::with_capacity(threads.get());
+		for _ in 0..threads.get() {
+			workers.push(s.spawn(#[inline(always)] || cb(&rx, (a, b), settings)));
+		}
+
+		// Broadcast the jobs!
+		for (key, (pa, pb)) in pairs {
+			// Name it after whichever side(s) have it.
+			let rel = |p: &Path, dir: &Path| p.strip_prefix(dir).unwrap_or(p).to_string_lossy().into_owned();
+			let name = match (pa.as_deref(), pb.as_deref()) {
+				(Some(pa), Some(pb)) => {
+					let (ra, rb) = (rel(pa, a), rel(pb, b));
+					if ra == rb { ra } else { format!("{ra} vs {rb}") }
+				},
+				(Some(pa), None) => rel(pa, a),
+				(None, Some(pb)) => rel(pb, b),
+				(None, None) => key.to_string_lossy().into_owned(),
+			};
+			tx.send((name, pa, pb)).map_err(|_| CliError::JobServer)?;
+		}
+
+		// Disconnect and wait for the threads to finish!
+		drop(tx);
+		for worker in workers { let _res = worker.join(); }
+
+		// Anything amiss?
+		let changed = CHANGED.load(SeqCst);
+		let missing = MISSING.load(SeqCst);
+		if changed == 0 && missing == 0 { Ok(()) }
+		else { Err(CliError::Mismatched(changed, missing)) }
+	})
+}
+
+/// # Decode Image.
+///
+/// Decode an image — or STDIN if "-" — the same way it would be for
+/// checksumming, returning its format and the pixels normalized according to
+/// the alpha policy.
+fn decode_image(path: &Path, flags: u16, background: [u8; 3], svg_size: [u32; 2])
+-> Result<(PxKind, PxImage), PxsumError> {
+	use std::io::Read;
+
+	let mut raw = Vec::new();
+	if path == Path::new("-") { claim_stdin()?.read_to_end(&mut raw).map_err(|_| PxsumError::Read)?; }
+	else { raw = std::fs::read(path).map_err(|_| PxsumError::Read)?; }
+	if raw.is_empty() { return Err(PxsumError::NoData); }
+
+	let kind = PxKind::try_from_magic(&raw).or_else(|e|
+		PxKind::try_from_extension(path.as_os_str().as_encoded_bytes()).ok_or(e)
+	)?.refine(&raw);
+	let img =
+		if matches!(kind, PxKind::Svg) && svg_size != [0, 0] { PxImage::new_svg(&raw, flags, svg_size)? }
+		else { PxImage::new(&raw, kind, flags)? };
+	let mut img = img.with_background(background);
+	img.apply_alpha_policy(flags);
+	Ok((kind, img))
+}
+
+#[cold]
+/// # Diff Against Reference.
+///
+/// Compare the pixels of a failed image against its reference copy — the
+/// reference itself, or the same relative path within it if a directory —
+/// appending the statistics to `out`, and optionally saving a visualization
+/// to the `image` directory.
+fn diff_against(chk: &mut Checksum, reference: &Path, image: Option<&Path>, out: &mut String) {
+	let rel: PathBuf = Path::new(chk.file()).components()
+		.filter(|c| matches!(c, Component::Normal(_)))
+		.collect();
+	let reference =
+		if reference.is_dir() { Cow::Owned(reference.join(&rel)) }
+		else { Cow::Borrowed(reference) };
+
+	let (ours, theirs) = match chk.decode_against(&reference) {
+		Ok(pair) => pair,
+		Err(e) => {
+			let _res = write!(out, "\n  diff: {} ({e})", reference.to_string_lossy());
+			return;
+		},
+	};
+	let diff = theirs.diff(&ours);
+	let _res = write!(out, "\n  diff: {diff}");
+
+	// Save a picture too?
+	if let Some(dir) = image {
+		if ! diff.identical() {
+			if let Some(img) = theirs.diff_image(&ours) {
+				let mut dst = dir.join(rel).into_os_string();
+				if let Some((_, page)) = chk.src().rsplit_once('#').filter(|_| chk.src() != chk.file()) {
+					dst.push(format!("-{page}"));
+				}
+				dst.push(".diff.png");
+				let dst = PathBuf::from(dst);
+				let saved = dst.parent().is_some_and(|p| std::fs::create_dir_all(p).is_ok()) &&
+					img.save_with_format(&dst, ImageFormat::Png).is_ok();
+				if saved { let _res = write!(out, "\n  diff: saved {}", dst.to_string_lossy()); }
+				else {
+					Msg::warning(format!(
+						"Unable to save diff image.\n         \x1b[2m{}\x1b[0m",
+						dst.to_string_lossy(),
+					)).eprint();
+				}
+			}
+		}
+	}
+}
+
+#[inline(never)]
+/// # Lint Paths.
+///
+/// Check each image for misnamed extensions, trailing data, and tolerated
+/// truncation, printing a line for each file with issues, and — if
+/// `--fix-extensions` — renaming the misnamed ones.
+fn lint_paths(paths: &[OsString], settings: &Settings)
+-> Result<(), CliError> {
+	/// # Files With (Unfixed) Issues.
+	static ISSUES: AtomicU64 = AtomicU64::new(0);
+
+	/// # Worker Callback.
+	fn cb(rx: &Receiver::<&Path>, fix: bool) {
+		use std::io::Read;
+
+		while let Ok(p) = rx.recv() {
+			// Archives are only crawled for checksumming.
+			if pxsum::check_archive(p.as_os_str().as_encoded_bytes()) { continue; }
+
+			let is_stdin = p == Path::new("-");
+			let res =
+				if is_stdin {
+					let mut raw = Vec::new();
+					claim_stdin()
+						.and_then(|mut r| r.read_to_end(&mut raw).map_err(|_| PxsumError::Read))
+						.and_then(|_| PxLint::check(&raw, None))
+				}
+				else {
+					std::fs::read(p).map_err(|_| PxsumError::Read)
+						.and_then(|raw| PxLint::check(&raw, Some(p.as_os_str().as_encoded_bytes())))
+				};
+
+			match res {
+				Ok(issues) => {
+					if issues.is_empty() { continue; }
+					let mut out = format!(
+						"{}: {}",
+						p.to_string_lossy(),
+						issues.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "),
+					);
+
+					// Rename misnamed files?
+					let mut fixed = 0;
+					if fix && ! is_stdin {
+						let ext = issues.iter().find_map(|i|
+							if let PxLint::Misnamed(_, ext) = i { Some(*ext) }
+							else { None }
+						);
+						if let Some(ext) = ext {
+							let dst = p.with_extension(ext);
+							if dst.exists() {
+								let _res = write!(out, "\n  NOT renamed: {} already exists", dst.to_string_lossy());
+							}
+							else if std::fs::rename(p, &dst).is_ok() {
+								let _res = write!(out, "\n  renamed: {}", dst.to_string_lossy());
+								fixed = 1;
+							}
+							else { out.push_str("\n  NOT renamed: rename failed"); }
+						}
+					}
+
+					if fixed < issues.len() { ISSUES.fetch_add(1, Relaxed); }
+					println!("{out}");
+				},
+				Err(e) => {
+					ISSUES.fetch_add(1, Relaxed);
+					println!(
+						"{}: FAILED ({})",
+						p.to_string_lossy(),
+						if matches!(e, PxsumError::NoData) { "empty" }
+						else { "read/decode" }
+					);
+				},
+			}
+		}
+	}
+
+	// If there are fewer paths than threads, we can reduce the worker count.
+	let mut threads = settings.threads();
+	let Some(len) = NonZeroUsize::new(paths.len()) else { return Ok(()); };
+	if len < threads { threads = len; }
+
+	let fix = settings.fix_extensions();
+	let (tx, rx) = crossbeam_channel::bounded::<&Path>(threads.get());
+	thread::scope(#[inline(always)] |s| {
+		// Set up the worker threads.
+		let mut workers = Vec::with_capacity(threads.get());
+		for _ in 0..threads.get() {
+			workers.push(s.spawn(#[inline(always)] || cb(&rx, fix)));
+		}
+
+		// Broadcast the jobs!
+		for p in paths { tx.send(p.as_ref()).map_err(|_| CliError::JobServer)?; }
+
+		// Disconnect and wait for the threads to finish!
+		drop(tx);
+		for worker in workers { let _res = worker.join(); }
+
+		// Anything amiss?
+		NonZeroU64::new(ISSUES.load(SeqCst)).map_or(Ok(()), |n| Err(CliError::Linted(n)))
+	})
 }
 
 #[inline(never)]
 /// # Verify Paths.
 ///
 /// Verify existing paths and maybe print their statuses.
-fn verify_paths(paths: &[OsString], settings: Settings)
--> Result<(), PxsumError> {
+fn verify_paths(paths: &[OsString], settings: &Settings)
+-> Result<(), CliError> {
 	/// # Mismatched Path Count.
 	static FAILED: AtomicU64 = AtomicU64::new(0);
 
 	/// # Worker Callback.
-	fn cb(rx: &Receiver::<String>, settings: &Settings) {
-		let mut chk = Checksum::new(false);
+	///
+	/// Each job is either a single line, or all of the lines pointing inside
+	/// a given TAR archive, so it need only be read once.
+	fn cb(rx: &Receiver::<Vec<String>>, settings: &Settings) {
+		let mut chk = settings.checksum();
 		let print_valid =   settings.print_valid();
 		let print_warnings = settings.print_warnings();
 
-		while let Ok(line) = rx.recv() {
-			match chk.verify_existing(line.as_str()) {
+		while let Ok(lines) = rx.recv() {
+			chk.verify_existing_many(lines, |chk, line, res| match res {
 				Ok(true) => if print_valid {
 					println!("{}: OK", chk.src());
 				},
 				Ok(false) => {
 					FAILED.fetch_add(1, Relaxed);
-					println!("{}: FAILED", chk.src());
+					let mut out = format!("{}: FAILED", chk.src());
+					let changed = chk.changed_regions();
+					if ! changed.is_empty() {
+						let changed = changed.iter()
+							.map(ToString::to_string)
+							.collect::<Vec<_>>()
+							.join(", ");
+						let _res = write!(out, " (changed {changed})");
+					}
+					if let Some(reference) = settings.diff_against() {
+						diff_against(chk, reference, settings.diff_image(), &mut out);
+					}
+					println!("{out}");
 				},
 				Err(PxsumError::LineDecode | PxsumError::Path) => if print_warnings {
 					Msg::warning(format!(
@@ -341,29 +1038,45 @@ fn verify_paths(paths: &[OsString], settings: Settings)
 						"{}: FAILED ({})",
 						chk.src(),
 						if matches!(e, PxsumError::NoData) { "empty" }
-						else if ! Path::new(chk.src()).exists() { "missing" }
+						// (Archive entries aren't on disk, so only read
+						// errors count.)
+						else if
+							matches!(e, PxsumError::Read) &&
+							! Path::new(chk.file()).exists()
+						{
+							"missing"
+						}
 						else { "read/decode" }
 					);
 				},
-			}
+			});
 		}
 	}
 
 	let threads = settings.threads();
-	let (tx, rx) = crossbeam_channel::bounded::<String>(threads.get());
+	let (tx, rx) = crossbeam_channel::bounded::<Vec<String>>(threads.get());
 	thread::scope(#[inline(always)] |s| {
 		// Set up the worker threads, either with or without progress.
 		let mut workers = Vec::with_capacity(threads.get());
 		for _ in 0..threads.get() {
-			workers.push(s.spawn(#[inline(always)] || cb(&rx, &settings)));
+			workers.push(s.spawn(#[inline(always)] || cb(&rx, settings)));
 		}
 
-		// Broadcast the jobs!
+		// Broadcast the jobs! Lines inside TARs are held back until the end
+		// so each archive can be sent (and read) as a single job.
+		let mut tars = BTreeMap::<String, Vec<String>>::new();
+		let mut send = |line: String| {
+			if let Some(archive) = Checksum::tar_archive(&line).map(str::to_owned) {
+				tars.entry(archive).or_default().push(line);
+				Ok(())
+			}
+			else { tx.send(vec![line]).map_err(|_| CliError::JobServer) }
+		};
 		for p in paths {
 			// Read from STDIN.
 			if p == "-" {
-				for line in ManifestLines::new(stdin()?.lines().map_while(Result::ok)) {
-					tx.send(line).map_err(|_| PxsumError::JobServer)?;
+				for line in ManifestLines::new(claim_stdin()?.lines().map_while(Result::ok)) {
+					send(line)?;
 				}
 			}
 			// Read from File.
@@ -372,7 +1085,7 @@ fn verify_paths(paths: &[OsString], settings: Settings)
 				if let Ok(lines) = File::open(p).map(|f| BufReader::new(f).lines()) {
 					for line in ManifestLines::new(lines.map_while(Result::ok)) {
 						read = true;
-						tx.send(line).map_err(|_| PxsumError::JobServer)?;
+						send(line)?;
 					}
 				}
 
@@ -386,6 +1099,10 @@ fn verify_paths(paths: &[OsString], settings: Settings)
 			}
 		}
 
+		for lines in tars.into_values() {
+			tx.send(lines).map_err(|_| CliError::JobServer)?;
+		}
+
 		// Disconnect and wait for the threads to finish!
 		drop(tx);
 		for worker in workers { let _res = worker.join(); }
@@ -394,7 +1111,7 @@ fn verify_paths(paths: &[OsString], settings: Settings)
 		// with a non-zero code.
 		NonZeroU64::new(FAILED.load(SeqCst)).map_or(
 			Ok(()),
-			|failed| Err(PxsumError::Failed(failed))
+			|failed| Err(CliError::Failed(failed))
 		)
 	})
 }
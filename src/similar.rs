@@ -0,0 +1,241 @@
+/*!
+# pxsum: Similarity.
+*/
+
+
+
+#[derive(Debug, Clone, Default)]
+/// # Similarity Index.
+///
+/// This is a [BK-tree](https://en.wikipedia.org/wiki/BK-tree) of perceptual
+/// fingerprints (see `PxImage::fingerprint`), keyed by Hamming distance. It
+/// allows near-duplicate lookups without comparing every fingerprint against
+/// every other fingerprint, keeping things manageable even with hundreds of
+/// thousands of images.
+///
+/// Entries are identified by their insertion order, so any associated data —
+/// paths, etc. — can be stored in a parallel `Vec`.
+///
+/// Exact duplicates — common enough, as every flat image fingerprints the
+/// same — share a single node rather than forming a chain.
+pub struct SimilarityIndex {
+	/// # Nodes.
+	///
+	/// The first node, if any, is the root.
+	nodes: Vec<SimilarityNode>,
+
+	/// # Entry Count.
+	len: usize,
+}
+
+impl SimilarityIndex {
+	#[must_use]
+	/// # New (Empty) Instance.
+	pub const fn new() -> Self { Self { nodes: Vec::new(), len: 0 } }
+
+	#[must_use]
+	/// # Length.
+	pub const fn len(&self) -> usize { self.len }
+
+	#[must_use]
+	/// # Is Empty?
+	pub const fn is_empty(&self) -> bool { self.len == 0 }
+
+	/// # Insert.
+	///
+	/// Add a fingerprint to the index, returning its ID.
+	pub fn insert(&mut self, fingerprint: u64) -> usize {
+		let id = self.len;
+		self.len += 1;
+
+		// The first entry is the root; the rest need to find their place.
+		if ! self.nodes.is_empty() {
+			let mut idx = 0;
+			loop {
+				let dist = distance(self.nodes[idx].fingerprint, fingerprint);
+				if dist == 0 {
+					self.nodes[idx].ids.push(id);
+					return id;
+				}
+
+				if let Some(&(_, next)) = self.nodes[idx].children.iter().find(|(d, _)| *d == dist) {
+					idx = next;
+				}
+				else {
+					let next = self.nodes.len();
+					self.nodes[idx].children.push((dist, next));
+					break;
+				}
+			}
+		}
+
+		self.nodes.push(SimilarityNode { fingerprint, ids: vec![id], children: Vec::new() });
+		id
+	}
+
+	/// # Find.
+	///
+	/// Push the IDs of all fingerprints within `max_distance` of `fingerprint`
+	/// — inclusive — onto `out`, in no particular order.
+	pub fn find(&self, fingerprint: u64, max_distance: u32, out: &mut Vec<usize>) {
+		if self.nodes.is_empty() { return; }
+
+		let mut stack = vec![0_usize];
+		while let Some(idx) = stack.pop() {
+			let node = &self.nodes[idx];
+			let dist = distance(node.fingerprint, fingerprint);
+			if dist <= max_distance { out.extend_from_slice(&node.ids); }
+
+			// Per the triangle inequality, matches can only live in the
+			// subtrees whose edge distances are within range of ours.
+			let lo = dist.saturating_sub(max_distance);
+			let hi = dist.saturating_add(max_distance);
+			for &(d, child) in &node.children {
+				if lo <= d && d <= hi { stack.push(child); }
+			}
+		}
+	}
+
+	#[must_use]
+	/// # Clusters.
+	///
+	/// Group the entries transitively by similarity — if A is near B and B is
+	/// near C, all three wind up together — and return the IDs for each
+	/// cluster having two or more members.
+	///
+	/// Each cluster is sorted, as is the list of clusters.
+	pub fn clusters(&self, max_distance: u32) -> Vec<Vec<usize>> {
+		/// # Find Root (w/ Path Halving).
+		fn root(parents: &mut [usize], mut idx: usize) -> usize {
+			while parents[idx] != idx {
+				parents[idx] = parents[parents[idx]];
+				idx = parents[idx];
+			}
+			idx
+		}
+
+		// Union-find. (Exact duplicates turn up in their own node's results,
+		// so are joined along with everything else.)
+		let mut parents: Vec<usize> = (0..self.len).collect();
+		let mut found = Vec::new();
+		for node in &self.nodes {
+			found.truncate(0);
+			self.find(node.fingerprint, max_distance, &mut found);
+			for &other in &found {
+				let a = root(&mut parents, node.ids[0]);
+				let b = root(&mut parents, other);
+				if a != b { parents[a.max(b)] = a.min(b); }
+			}
+		}
+
+		// Regroup by root.
+		let mut out: Vec<Vec<usize>> = vec![Vec::new(); self.len];
+		for idx in 0..self.len {
+			let r = root(&mut parents, idx);
+			out[r].push(idx);
+		}
+		out.retain(|set| 1 < set.len());
+		out
+	}
+}
+
+
+
+#[derive(Debug, Clone)]
+/// # Similarity Node.
+struct SimilarityNode {
+	/// # Fingerprint.
+	fingerprint: u64,
+
+	/// # Entry IDs.
+	///
+	/// Every entry with this exact fingerprint, in insertion order.
+	ids: Vec<usize>,
+
+	/// # Children (Distance, Node Index).
+	children: Vec<(u32, usize)>,
+}
+
+
+
+#[must_use]
+#[inline]
+/// # Hamming Distance.
+///
+/// Return the number of bits that differ between two fingerprints.
+pub const fn distance(a: u64, b: u64) -> u32 { (a ^ b).count_ones() }
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_index() {
+		let fps = [
+			0b0000_0000_u64,
+			0b0000_0001,
+			0b0000_0011,
+			0b1111_0000,
+			0b1111_0001,
+			u64::MAX,
+			0b0000_0000, // Exact dupe.
+		];
+
+		let mut index = SimilarityIndex::new();
+		for (k, fp) in fps.iter().copied().enumerate() {
+			assert_eq!(index.insert(fp), k, "Wrong ID.");
+		}
+		assert_eq!(index.len(), fps.len());
+
+		// Compare finds against brute force.
+		let mut found = Vec::new();
+		for fp in fps {
+			for max in 0..=64 {
+				found.truncate(0);
+				index.find(fp, max, &mut found);
+				found.sort_unstable();
+
+				let expected: Vec<usize> = fps.iter()
+					.enumerate()
+					.filter_map(|(k, &v)| (distance(fp, v) <= max).then_some(k))
+					.collect();
+				assert_eq!(found, expected, "Find mismatch for {fp:b} within {max}.");
+			}
+		}
+
+		// Clusters.
+		assert_eq!(index.clusters(0), vec![vec![0, 6]]);
+		assert_eq!(index.clusters(1), vec![vec![0, 1, 2, 6], vec![3, 4]]);
+		assert_eq!(index.clusters(64), vec![vec![0, 1, 2, 3, 4, 5, 6]]);
+	}
+
+	#[test]
+	fn t_index_dupes() {
+		// Lots of flat images all fingerprint the same, and should all share
+		// a node rather than forming a long chain.
+		let mut index = SimilarityIndex::new();
+		for k in 0..10_000 {
+			let fp = if k % 100 == 0 { 0b1111 } else { 0 };
+			assert_eq!(index.insert(fp), k, "Wrong ID.");
+		}
+		assert_eq!(index.len(), 10_000);
+		assert_eq!(index.nodes.len(), 2, "Duplicates weren't collapsed.");
+
+		let mut found = Vec::new();
+		index.find(0, 0, &mut found);
+		assert_eq!(found.len(), 9_900);
+		assert!(found.windows(2).all(|w| w[0] < w[1]), "IDs out of order.");
+
+		found.truncate(0);
+		index.find(0b0111, 1, &mut found);
+		assert_eq!(found, (0..100).map(|k| k * 100).collect::<Vec<_>>());
+
+		assert_eq!(index.clusters(0), vec![
+			(0..10_000).filter(|k| k % 100 == 0).collect::<Vec<_>>(),
+			(0..10_000).filter(|k| k % 100 != 0).collect::<Vec<_>>(),
+		]);
+		assert_eq!(index.clusters(4), vec![(0..10_000).collect::<Vec<_>>()]);
+	}
+}